@@ -1,78 +1,65 @@
-//use clap::{arg, Command};
+// Renders the man page into OUT_DIR from the same definitions the binary parses, for
+// `utd manpage` to print.
 
-// Run this example as `cargo run --example man | man -l -`.
+#[allow(dead_code)]
+#[path = "src/args.rs"]
+mod args;
 
-use clap::{ArgEnum, Args, Command, Parser};
-#[derive(Debug, Parser)]
-#[clap(author, version, about)]
-pub struct Cli {
-    /// Add note(s)
-    #[clap(short, long, multiple_values = true)]
-    pub note: Option<Vec<String>>,
+use clap::CommandFactory;
 
-    /// Add a new task(s)
-    #[clap(short, long, multiple_values = true)]
-    pub add: Option<Vec<String>>,
-
-    /// Add a new task(s)
-    #[clap(short, long, multiple_values = true)]
-    pub delete: Option<Vec<String>>,
-
-    /// Check/uncheck task(s) as complete
-    #[clap(short, long, multiple_values = true)]
-    pub check: Option<Vec<String>>,
-
-    /// Start/stop task(s)
-    #[clap(short, long, multiple_values = true)]
-    pub begin: Option<Vec<String>>,
-
-    /// Show tasks and sort
-    #[clap(short, long, arg_enum)]
-    pub sort: Option<SortParam>,
-
-    /// Clear all completed notes/tasks
-    #[clap(short, long)]
-    pub tidy: bool,
-
-    /// Set a priority level
-    #[clap(short, long, arg_enum, multiple_values = true)]
-    pub priority: Option<Vec<PriorityLevel>>,
-
-    /// Set log level
-    #[clap(short, long, arg_enum)]
-    pub log: Option<LogLevel>,
-
-    /// Make ids sequential
-    #[clap(short, long, long = "reset-ids")]
-    pub re_set_ids: bool,
-}
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
-pub enum PriorityLevel {
-    Low,
-    Normal,
-    High,
-}
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
-pub enum LogLevel {
-    Trace,
-    Debug,
-    Info,
-    Warn,
-    Error,
-}
-
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
-pub enum SortParam {
-    Age,
-    Priority,
-}
 fn main() -> Result<(), std::io::Error> {
-    let val = Cli::augment_args(Command::new("utd"));
-    let man = clap_mangen::Man::new(val);
+    println!("cargo:rerun-if-changed=src/args.rs");
+    println!("cargo:rerun-if-changed=config.toml");
+    let man = clap_mangen::Man::new(args::Cli::command());
     let mut buffer: Vec<u8> = Default::default();
     man.render(&mut buffer)?;
-    std::fs::write("target/utd.1", buffer)?;
+    buffer.extend_from_slice(configuration_section().as_bytes());
+    buffer.extend_from_slice(FILES.as_bytes());
+    let out_dir = std::path::PathBuf::from(std::env::var_os("OUT_DIR").unwrap());
+    std::fs::write(out_dir.join("utd.1"), buffer)?;
     Ok(())
 }
+
+/// The sample config with every key, set in a literal block
+fn configuration_section() -> String {
+    let sample = include_str!("config.toml");
+    let mut section = String::from(
+        ".SH CONFIGURATION\n\
+         utd reads \\fIconfig.toml\\fR from its config directory, falling back to \
+         \\fIutd.toml\\fR next to it and then to the defaults below. Every key is \
+         optional.\n.PP\n.nf\n",
+    );
+    for line in sample.lines() {
+        // roff takes lines starting with a dot or quote as requests
+        let line = line.replace('\\', "\\e");
+        if line.starts_with('.') || line.starts_with('\'') {
+            section.push_str("\\&");
+        }
+        section.push_str(&line);
+        section.push('\n');
+    }
+    section.push_str(".fi\n");
+    section
+}
+
+const FILES: &str = ".SH FILES
+Paths are those of Linux; \\fButd paths\\fR prints the ones in use on any system.
+.TP
+\\fI~/.config/utd/config.toml\\fR
+The configuration, see CONFIGURATION.
+.TP
+\\fI~/.config/utd/scripts/\\fR
+Scripts run with \\fButd script <name>\\fR, or \\fButd <name>\\fR unless an alias takes the name.
+.TP
+\\fI~/.local/share/utd/.utd.json\\fR
+The board, \\fI.utd.jsonl\\fR with \\fBstorage = \"jsonl\"\\fR. Other boards, selected with \\fB+board\\fR, are kept as \\fI.utd-<board>.json\\fR.
+.TP
+\\fI~/.local/share/utd/.utd-archive.json\\fR
+Entries cleared with \\fB--tidy\\fR, for statistics and \\fB--done\\fR.
+.TP
+\\fI~/.local/share/utd/backups/\\fR
+Backups and the snapshots taken before destructive commands.
+.TP
+\\fI~/.local/share/utd/utd-log\\fR
+The log, unless \\fB[logs] path\\fR or \\fBdestination\\fR say otherwise.
+";
@@ -0,0 +1,120 @@
+use serde::Deserialize;
+
+use crate::{dates, push_tag, Config, Task};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Body of `POST /tasks`
+#[derive(Debug, Clone, Deserialize)]
+pub struct NewEntry {
+    pub name: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Add a note rather than a task
+    #[serde(default)]
+    pub note: bool,
+    pub priority: Option<String>,
+    /// When the task is due e.g. `friday` or `2022-04-01`
+    pub due: Option<String>,
+}
+
+/// Body of `PATCH /tasks/:id`, fields left out are kept as they are
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct EntryPatch {
+    pub name: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub priority: Option<String>,
+    /// A new due date, or an empty string to clear it
+    pub due: Option<String>,
+    pub done: Option<bool>,
+    pub in_progress: Option<bool>,
+}
+
+fn check_priority(config: &Config, priority: &str) -> Result<()> {
+    match config.priority_levels().iter().any(|f| f.name == priority) {
+        true => Ok(()),
+        false => Err(format!("unknown priority level {priority}").into()),
+    }
+}
+
+fn canonical_tags(config: &Config, tags: &[String]) -> Vec<String> {
+    let tag_config = config.tags.as_ref().cloned().unwrap_or_default();
+    let mut trimmed = Vec::with_capacity(tags.len());
+    for tag in tags {
+        push_tag(&mut trimmed, tag, tag_config.prefix());
+    }
+    tag_config.canonicalise(&trimmed)
+}
+
+impl NewEntry {
+    pub fn into_task(self, config: &Config, id: i64, timestamp: u128) -> Result<Task> {
+        if self.name.trim().is_empty() {
+            return Err("empty name".into());
+        }
+        let priority = match self.priority {
+            Some(priority) => {
+                check_priority(config, &priority)?;
+                priority
+            }
+            None => config.default_priority(),
+        };
+        let tags = canonical_tags(config, &self.tags);
+        let mut task = Task::new(&self.name, &tags, !self.note, id, &priority, timestamp);
+        task.owner = Some(config.user());
+        if let Some(ref due) = self.due {
+            task.due = Some(dates::parse_deadline(due)?);
+        }
+        Ok(task)
+    }
+}
+
+impl EntryPatch {
+    /// Applies the changes, returns whether the task was completed by them
+    pub fn apply(&self, task: &mut Task, config: &Config) -> Result<bool> {
+        if let Some(ref priority) = self.priority {
+            check_priority(config, priority)?;
+        }
+        let due = match self.due.as_deref().map(str::trim) {
+            Some("") => Some(None),
+            Some(due) => Some(Some(dates::parse_deadline(due)?)),
+            None => None,
+        };
+        if let Some(ref name) = self.name {
+            if name.trim().is_empty() {
+                return Err("empty name".into());
+            }
+            task.name = name.trim().to_owned();
+        }
+        if let Some(ref tags) = self.tags {
+            task.tags = canonical_tags(config, tags);
+        }
+        if let Some(ref priority) = self.priority {
+            task.priority = priority.to_owned();
+        }
+        if let Some(due) = due {
+            task.due = due;
+        }
+        if let Some(in_progress) = self.in_progress {
+            task.in_progress = in_progress;
+            if in_progress {
+                task.is_done = false;
+            }
+        }
+        let mut completed = false;
+        match self.done {
+            Some(true) if !task.is_done => {
+                task.in_progress = false;
+                task.stop_timer();
+                task.complete();
+                completed = true;
+            }
+            Some(false) if task.is_done => {
+                task.is_done = false;
+                task.completed_at = None;
+                task.record("reopened");
+            }
+            _ => {}
+        }
+        Ok(completed)
+    }
+}
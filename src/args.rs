@@ -1,65 +1,808 @@
-use clap::{ArgEnum, Parser};
+use std::collections::HashMap;
+
+use clap::{ArgEnum, Args, CommandFactory, Parser, Subcommand};
 
 #[derive(Debug, Parser)]
 #[clap(author, version, about)]
 pub struct Cli {
-    /// Add note(s)
-    #[clap(short, long, multiple_values = true)]
+    /// Scope the invocation to a tag (@work) or a separate board (+home)
+    #[clap(value_name = "@TAG|+BOARD")]
+    pub context: Option<String>,
+
+    /// Add note(s), `-` reads one per line from stdin
+    #[clap(short, long, multiple_values = true, hide = true)]
     pub note: Option<Vec<String>>,
 
-    /// Add a new task(s)
-    #[clap(short, long, multiple_values = true)]
+    /// Add a new task(s), `-` reads one per line from stdin
+    #[clap(short, long, multiple_values = true, hide = true)]
     pub add: Option<Vec<String>>,
 
-    /// Add a new task(s)
-    #[clap(short, long, multiple_values = true)]
+    /// Expire the new entries after a date e.g. friday, 2022-04-01 or 3d
+    #[clap(long)]
+    pub expires: Option<String>,
+
+    /// Bring the new tasks back this long after they are due once completed e.g. 1d or 2w
+    #[clap(long)]
+    pub every: Option<String>,
+
+    /// Estimate the effort of the new tasks e.g. 30m or 2h
+    #[clap(long)]
+    pub estimate: Option<String>,
+
+    /// Set a due date on the new entries e.g. friday, 2022-04-01 or 3d
+    #[clap(long)]
+    pub due: Option<String>,
+
+    /// Skip entries whose name matches an open entry
+    #[clap(long)]
+    pub unless_exists: bool,
+
+    /// Delete task(s) by id or name fragment, or those selected with --tag/--filter
+    #[clap(short, long, multiple_values = true, min_values = 0, hide = true)]
     pub delete: Option<Vec<String>>,
 
-    /// Check/uncheck task(s) as complete
-    #[clap(short, long, multiple_values = true)]
+    /// Cancel a task, with an optional reason, moving it to the archive
+    #[clap(long, value_names = &["ID", "REASON"], min_values = 1, max_values = 2)]
+    pub cancel: Option<Vec<String>>,
+
+    /// Check/uncheck task(s) as complete by id or name fragment, or those selected with --tag/--filter
+    #[clap(short, long, multiple_values = true, min_values = 0, hide = true)]
     pub check: Option<Vec<String>>,
 
-    /// Start/stop task(s)
-    #[clap(short, long, multiple_values = true)]
+    /// Start/stop task(s) by id or name fragment, or those selected with --tag/--filter
+    #[clap(short, long, multiple_values = true, min_values = 0, hide = true)]
     pub begin: Option<Vec<String>>,
 
-    /// Show tasks and sort
-    #[clap(short, long, arg_enum)]
-    pub sort: Option<SortParam>,
+    /// Tag new entries, otherwise select entries with the tag. Repeat for several tags.
+    #[clap(long)]
+    pub tag: Option<Vec<String>>,
+
+    /// Select entries matching all conditions e.g. "priority=low state=done tag=@work"
+    #[clap(long)]
+    pub filter: Option<String>,
+
+    /// Sort by age, priority, due, name or id, each optionally followed by asc or desc,
+    /// e.g. "due asc, priority desc"
+    #[clap(short, long, alias = "sort-by", value_name = "KEYS", global = true)]
+    pub sort: Option<SortOrder>,
+
+    /// Sections one after another, or side by side as a kanban board
+    #[clap(long, arg_enum, global = true)]
+    pub view: Option<View>,
 
     /// Clear all completed notes/tasks
-    #[clap(short, long)]
+    #[clap(short, long, hide = true)]
     pub tidy: bool,
 
-    /// Set a priority level
-    #[clap(short, long, arg_enum, multiple_values = true)]
-    pub priority: Option<Vec<PriorityLevel>>,
+    /// Set a priority level (low, normal, high or one defined in your config)
+    #[clap(short, long, multiple_values = true)]
+    pub priority: Option<Vec<String>>,
 
     /// Set log level
-    #[clap(short, long, arg_enum)]
+    #[clap(short, long, arg_enum, global = true)]
     pub log: Option<LogLevel>,
 
     /// Make ids sequential
     #[clap(short, long, long = "reset-ids")]
     pub re_set_ids: bool,
+
+    /// List tasks completed today, yesterday or this week
+    #[clap(long, value_name = "PERIOD", possible_values = &["today", "yesterday", "week"])]
+    pub done: Option<String>,
+
+    /// Show snoozed tasks
+    #[clap(long, global = true)]
+    pub show_snoozed: bool,
+
+    /// Leave the notes section off the board
+    #[clap(long, global = true)]
+    pub hide_notes: bool,
+
+    /// Show a single section of the board
+    #[clap(long, value_name = "SECTION", possible_values = &["todo", "in-progress", "notes"], global = true)]
+    pub only: Option<String>,
+
+    /// Print without colours or text styling
+    #[clap(long, alias = "no-colour", global = true)]
+    pub no_color: bool,
+
+    /// Print bare lines without colours, icons or table layout
+    #[clap(long, global = true)]
+    pub plain: bool,
+
+    /// Keep running and redraw the board when it changes or the terminal is resized
+    #[clap(short, long, conflicts_with_all = &["quiet", "summary"], global = true)]
+    pub watch: bool,
+
+    /// Print nothing after changing the board
+    #[clap(short, long, conflicts_with = "summary", global = true)]
+    pub quiet: bool,
+
+    /// Print a one line summary of the changes instead of the board
+    #[clap(long, global = true)]
+    pub summary: bool,
+
+    /// Delete, tidy and reset ids without asking first
+    #[clap(short, long, global = true)]
+    pub yes: bool,
+
+    /// Refuse to start tasks beyond the wip-limit instead of warning
+    #[clap(long, global = true)]
+    pub strict: bool,
+
+    #[clap(subcommand)]
+    pub command: Option<Commands>,
 }
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
-pub enum PriorityLevel {
-    Low,
-    Normal,
-    High,
-}
-
-impl ToString for PriorityLevel {
-    fn to_string(&self) -> String {
-        match &self {
-            PriorityLevel::Low => "low",
-            PriorityLevel::Normal => "normal",
-            PriorityLevel::High => "high",
+impl Cli {
+    /// Parses the command line after expanding a leading alias from `[aliases]`
+    pub fn parse_with_aliases(aliases: &HashMap<String, String>) -> Self {
+        Self::parse_from(expand_alias(std::env::args().collect(), aliases)).normalise()
+    }
+
+    /// Parses a line typed into `utd shell` as if it followed `utd` on the command line
+    pub fn try_parse_line(line: &str, aliases: &HashMap<String, String>) -> clap::Result<Self> {
+        let mut args = vec![String::from("utd")];
+        args.extend(split_words(line));
+        Self::try_parse_from(expand_alias(args, aliases)).map(Self::normalise)
+    }
+
+    /// Moves `add`, `note`, `done`, `start`, `rm` and `tidy` onto the flags they replace,
+    /// so both spellings take the same path
+    fn normalise(mut self) -> Self {
+        match self.command.take() {
+            Some(Commands::Add { entries, options }) => {
+                self.add.get_or_insert_with(Vec::new).extend(entries);
+                self.merge_entry_options(options);
+            }
+            Some(Commands::Note { entries, options }) => {
+                self.note.get_or_insert_with(Vec::new).extend(entries);
+                self.merge_entry_options(options);
+            }
+            Some(Commands::Done { ids, selection }) => {
+                self.check.get_or_insert_with(Vec::new).extend(ids);
+                self.merge_selection(selection);
+            }
+            Some(Commands::Start { ids, selection }) => {
+                self.begin.get_or_insert_with(Vec::new).extend(ids);
+                self.merge_selection(selection);
+            }
+            Some(Commands::Rm { ids, selection }) => {
+                self.delete.get_or_insert_with(Vec::new).extend(ids);
+                self.merge_selection(selection);
+            }
+            Some(Commands::Tidy) => self.tidy = true,
+            command => self.command = command,
+        }
+        self
+    }
+
+    fn merge_entry_options(&mut self, options: EntryOptions) {
+        self.priority = options.priority.or_else(|| self.priority.take());
+        self.tag = options.tag.or_else(|| self.tag.take());
+        self.due = options.due.or_else(|| self.due.take());
+        self.expires = options.expires.or_else(|| self.expires.take());
+        self.every = options.every.or_else(|| self.every.take());
+        self.estimate = options.estimate.or_else(|| self.estimate.take());
+        self.unless_exists |= options.unless_exists;
+    }
+
+    fn merge_selection(&mut self, selection: Selection) {
+        self.tag = selection.tag.or_else(|| self.tag.take());
+        self.filter = selection.filter.or_else(|| self.filter.take());
+    }
+}
+
+/// Options of the entries `utd add` and `utd note` create
+#[derive(Debug, Args)]
+pub struct EntryOptions {
+    /// Set a priority level per entry (low, normal, high or one defined in your config)
+    #[clap(short, long, multiple_values = true)]
+    pub priority: Option<Vec<String>>,
+
+    /// Tag the new entries. Repeat for several tags.
+    #[clap(long)]
+    pub tag: Option<Vec<String>>,
+
+    /// Set a due date e.g. friday, 2022-04-01 or 3d
+    #[clap(long)]
+    pub due: Option<String>,
+
+    /// Expire the new entries after a date e.g. friday, 2022-04-01 or 3d
+    #[clap(long)]
+    pub expires: Option<String>,
+
+    /// Bring the new tasks back this long after they are due once completed e.g. 1d or 2w
+    #[clap(long)]
+    pub every: Option<String>,
+
+    /// Estimate the effort of the new tasks e.g. 30m or 2h
+    #[clap(long)]
+    pub estimate: Option<String>,
+
+    /// Skip entries whose name matches an open entry
+    #[clap(long)]
+    pub unless_exists: bool,
+}
+
+/// Entries to act on when no ids or names are given
+#[derive(Debug, Args)]
+pub struct Selection {
+    /// Select entries with the tag. Repeat for several tags.
+    #[clap(long)]
+    pub tag: Option<Vec<String>>,
+
+    /// Select entries matching all conditions e.g. "priority=low state=done tag=@work"
+    #[clap(long)]
+    pub filter: Option<String>,
+}
+
+/// Replaces the first argument with its alias, unless it names a subcommand
+pub fn expand_alias(args: Vec<String>, aliases: &HashMap<String, String>) -> Vec<String> {
+    let expansion = match args.get(1) {
+        Some(name) if !is_subcommand(name) => match aliases.get(name) {
+            Some(expansion) => split_words(expansion),
+            None => return args,
+        },
+        _ => return args,
+    };
+    let mut expanded = Vec::with_capacity(args.len() + expansion.len());
+    expanded.push(args[0].to_owned());
+    expanded.extend(expansion);
+    expanded.extend(args.into_iter().skip(2));
+    expanded
+}
+
+fn is_subcommand(name: &str) -> bool {
+    Cli::command()
+        .get_subcommands()
+        .any(|f| f.get_name() == name || f.get_all_aliases().any(|alias| alias == name))
+}
+
+/// Splits `line` at whitespace outside single or double quotes
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut in_word = false;
+    for c in line.chars() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (Some(q), c) if c == q => quote = None,
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (_, c) => {
+                word.push(c);
+                in_word = true;
+            }
         }
-        .to_owned()
     }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Add task(s), `-` reads one per line from stdin
+    Add {
+        #[clap(required = true)]
+        entries: Vec<String>,
+        #[clap(flatten)]
+        options: EntryOptions,
+    },
+    /// Add note(s), `-` reads one per line from stdin
+    Note {
+        #[clap(required = true)]
+        entries: Vec<String>,
+        #[clap(flatten)]
+        options: EntryOptions,
+    },
+    /// Check/uncheck task(s) as complete by id or name fragment, or those selected with --tag/--filter
+    #[clap(alias = "check")]
+    Done {
+        ids: Vec<String>,
+        #[clap(flatten)]
+        selection: Selection,
+    },
+    /// Start/stop task(s) by id or name fragment, or those selected with --tag/--filter
+    #[clap(alias = "begin")]
+    Start {
+        ids: Vec<String>,
+        #[clap(flatten)]
+        selection: Selection,
+    },
+    /// Delete task(s) by id or name fragment, or those selected with --tag/--filter
+    #[clap(alias = "delete")]
+    Rm {
+        ids: Vec<String>,
+        #[clap(flatten)]
+        selection: Selection,
+    },
+    /// Clear all completed notes/tasks
+    Tidy,
+    /// Attach a file or URL to a task
+    Attach {
+        /// Id of the task
+        id: i64,
+        /// Path to a file or a URL
+        target: String,
+    },
+    /// Add a dated note to a task, shown by `utd show`
+    Annotate {
+        /// Id of the task
+        id: i64,
+        /// Text of the note
+        text: String,
+    },
+    /// Open the first attachment of a task
+    Open {
+        /// Id of the task
+        id: i64,
+    },
+    /// Show the details of a task
+    Show {
+        /// Id of the task
+        id: i64,
+    },
+    /// Show just the focused task, for a narrow pane while working on it
+    Focus {
+        /// Id of the task to focus on, the one focused before when left out
+        id: Option<i64>,
+        /// Stop focusing on a task
+        #[clap(long)]
+        clear: bool,
+    },
+    /// Start or stop a task's timer
+    Timer {
+        #[clap(arg_enum)]
+        action: TimerAction,
+        /// Id of the task
+        id: i64,
+    },
+    /// Archive a task and replace it with smaller follow-up tasks
+    Split {
+        /// Id of the task
+        id: i64,
+        /// Names of the new tasks
+        #[clap(required = true)]
+        parts: Vec<String>,
+    },
+    /// Turn a note into a task or a task into a note
+    Convert {
+        /// Id of the entry
+        id: i64,
+        /// What the entry should become
+        #[clap(long, arg_enum)]
+        to: EntryKind,
+    },
+    /// Merge the second task into the first one, or another board file into this one
+    Merge {
+        /// Id of the task to keep, or a board file such as another machine's .utd.json
+        #[clap(value_name = "ID|FILE")]
+        first: String,
+        /// Id of the task to merge and remove
+        second: Option<i64>,
+        /// Name of the merged task, prompted for when omitted in a terminal
+        #[clap(long)]
+        name: Option<String>,
+    },
+    /// Pin task(s) to the top of their section
+    Pin {
+        /// Id(s) of the task(s)
+        #[clap(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Unpin task(s)
+    Unpin {
+        /// Id(s) of the task(s)
+        #[clap(required = true)]
+        ids: Vec<i64>,
+    },
+    /// Hide a task from the board until a later date
+    Snooze {
+        /// Id of the task
+        id: i64,
+        /// When the task should reappear e.g. tomorrow, monday, 2022-04-01 or 3d
+        #[clap(long)]
+        until: String,
+    },
+    /// Set or clear the due date of a task
+    Due {
+        /// Id of the task
+        id: i64,
+        /// When the task is due e.g. friday, 2022-04-01 or 3d
+        #[clap(required_unless_present = "clear")]
+        date: Option<String>,
+        /// Remove the due date
+        #[clap(long, conflicts_with = "date")]
+        clear: bool,
+    },
+    /// Print where the board, config, log, archive and backups are kept
+    Paths,
+    /// Print the man page, e.g. `utd manpage > utd.1`
+    Manpage,
+    /// Run a script from the scripts directory, or list them. Each also runs as
+    /// `utd <name>`.
+    Script {
+        /// File name of the script without `.rhai`
+        name: Option<String>,
+        /// Passed to the script as `args`
+        #[clap(allow_hyphen_values = true)]
+        args: Vec<String>,
+    },
+    /// Show a view saved under `[views]` in the config, or list them
+    View {
+        /// Name of the view, e.g. today for `[views.today]`
+        name: Option<String>,
+    },
+    /// Read commands such as `add foo` or `done 3` one line at a time, with history and
+    /// tab completion, until `exit`
+    Shell,
+    /// Check the board can be read, and recover a damaged one
+    Doctor {
+        /// Rebuild a damaged board from what still parses, the last good copy or a backup
+        #[clap(long)]
+        repair: bool,
+    },
+    /// Snapshot the board into the backups directory, keeping the newest [backup] keep
+    Backup {
+        /// List the backups of this board instead
+        #[clap(long)]
+        list: bool,
+    },
+    /// Replace the board with a backup, backing up the current board first
+    Restore {
+        /// File name in the backups directory, or a path
+        #[clap(long)]
+        from: String,
+    },
+    /// Print the end of the log file
+    Logs {
+        /// How many lines to print
+        #[clap(short = 'n', long, default_value = "20")]
+        lines: usize,
+        /// Keep printing lines as they are logged
+        #[clap(short, long)]
+        follow: bool,
+    },
+    /// Serve the board over HTTP on localhost: GET/POST /tasks, GET/PATCH /tasks/:id
+    Serve {
+        #[clap(long, default_value = "7777")]
+        port: u16,
+    },
+    /// Serve the dated tasks on localhost as an iCalendar feed calendars can subscribe to
+    ServeIcs {
+        #[clap(long, default_value = "7778")]
+        port: u16,
+    },
+    /// Keep running to expire entries, add recurring tasks and send desktop reminders
+    Daemon {
+        /// How often to look at the board
+        #[clap(long, default_value = "1m")]
+        every: String,
+        /// Remind of tasks this long before they are due
+        #[clap(long, default_value = "15m")]
+        ahead: String,
+    },
+    /// Exit with 1 and print how many open tasks are due or overdue, 0 when none are
+    CheckDue {
+        /// Also count tasks due this far ahead, e.g. 24h or 2d
+        #[clap(long, default_value = "0m")]
+        within: String,
+    },
+    /// Print one line per entry, narrowed by --tag/--filter and ordered by --sort
+    List {
+        /// Line template with fields such as {id}, {name}, {priority}, {tags}, {state},
+        /// {type}, {due}, {created}, {completed}, {age}, {estimate}, {tracked},
+        /// {assignee}, {ref} and {owner}
+        #[clap(long, default_value = "{id}\\t{name}")]
+        format: String,
+    },
+    /// Print the tags in use, one per line, e.g. for shell completion
+    Tags,
+    /// List projects with how many of their tasks are done
+    Projects,
+    /// Show counts of open, done and expired tasks
+    Stats,
+    /// Verify task(s) checked by someone else, or list the review queue
+    Verify {
+        /// Id(s) of the task(s)
+        ids: Vec<i64>,
+    },
+    /// Mark a task as waiting on someone else
+    Wait {
+        /// Id of the task
+        id: i64,
+        /// Who the task is delegated to or waiting on
+        #[clap(long)]
+        on: Option<String>,
+        /// Ticket or message it waits on, e.g. JIRA-123
+        #[clap(long = "ref")]
+        external_ref: Option<String>,
+        /// Stop waiting
+        #[clap(long)]
+        clear: bool,
+    },
+    /// List waiting tasks due a follow-up and bump, ping or snooze them
+    Followup,
+    /// Walk through stale open tasks to keep, complete, delete or snooze each
+    Review {
+        /// Tasks untouched for this long are stale, e.g. 2w (defaults to [age] stale-after)
+        #[clap(long)]
+        older_than: Option<String>,
+    },
+    /// List open tasks nobody has touched for a while, longest untouched first
+    Stale {
+        /// Tasks untouched for this long are stale, e.g. 2w (defaults to [age] stale-after)
+        #[clap(long)]
+        older_than: Option<String>,
+    },
+    /// Suggest the open task to start next, scored on priority, age, due date and blockers
+    Next,
+    /// Lay open tasks into a timetable for the rest of today, by estimate and priority
+    Plan {
+        /// Working hours, e.g. 9-17 or 8:30-16:30
+        #[clap(long, default_value = "9-17")]
+        hours: String,
+        /// Set the planned start times without asking
+        #[clap(long)]
+        accept: bool,
+    },
+    /// Log an interruption to the current focus session
+    Interrupt {
+        /// What interrupted you
+        note: String,
+        /// Id of the task being worked on, defaults to the one with a running timer
+        #[clap(long)]
+        id: Option<i64>,
+    },
+    /// Summarise the journal
+    Report {
+        #[clap(arg_enum)]
+        kind: ReportKind,
+        /// Number of days to cover
+        #[clap(long, default_value = "7")]
+        days: i64,
+    },
+    /// Print what was done, what is next and what is blocked, ready to paste
+    Standup {
+        /// Format as Markdown lists
+        #[clap(long)]
+        markdown: bool,
+    },
+    /// Post the open entries, or the standup, to a Slack or Discord channel
+    Post {
+        #[clap(arg_enum)]
+        service: ChatService,
+        /// Incoming webhook of the channel, the one set under [webhooks] when left out
+        #[clap(long)]
+        webhook_url: Option<String>,
+        /// Post the standup instead of the board
+        #[clap(long)]
+        standup: bool,
+    },
+    /// Email the overdue, due today and in progress tasks, e.g. from cron each morning
+    Digest {
+        /// Recipient, the `to` address under [smtp] when left out
+        #[clap(long)]
+        email: Option<String>,
+        /// Print the digest instead of sending it
+        #[clap(long)]
+        print: bool,
+    },
+    /// Keep the board in line with a hosted task list, both ways
+    Sync {
+        #[clap(arg_enum)]
+        provider: SyncProvider,
+    },
+    /// Mirror another tool's changes into the board, run as its hook
+    Bridge {
+        #[clap(arg_enum)]
+        tool: BridgeTool,
+    },
+    /// Show the board of one day, starting today's with yesterday's unfinished tasks
+    Journal {
+        /// Day of the journal e.g. yesterday or 2022-04-01, today when left out
+        day: Option<String>,
+    },
+    /// Chart how many tasks were open on each of the last days
+    Burndown {
+        /// Number of days to cover
+        #[clap(long, default_value = "14")]
+        days: i64,
+    },
+    /// Make a task wait until other tasks are done
+    Block {
+        /// Id of the task
+        id: i64,
+        /// Id(s) of the tasks to finish first
+        #[clap(long, required = true, multiple_values = true)]
+        on: Vec<i64>,
+        /// Remove these dependencies instead
+        #[clap(long)]
+        remove: bool,
+    },
+    /// Draw tasks and the tasks blocking them
+    Graph {
+        #[clap(long, arg_enum, default_value = "dot")]
+        format: GraphFormat,
+        /// File to write, stdout when left out
+        #[clap(short, long)]
+        output: Option<std::path::PathBuf>,
+    },
+    /// Copy an entry as new open entries
+    Dup {
+        /// Id of the entry
+        id: i64,
+        /// Number of copies
+        #[clap(long, default_value = "1")]
+        count: u32,
+    },
+    /// Set, show or clear the context tag scoping every board
+    Context {
+        #[clap(subcommand)]
+        action: ContextAction,
+    },
+    /// Scope the board to a project, or show statistics per project
+    Project {
+        #[clap(subcommand)]
+        action: ProjectAction,
+    },
+    /// Export the board for other tools
+    Export {
+        #[clap(long, arg_enum)]
+        format: ExportFormat,
+        /// File to write, stdout when left out
+        #[clap(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// Only export entries matching all conditions e.g. "tag=@clientA state=open"
+        #[clap(long)]
+        filter: Option<String>,
+    },
+    /// Import tasks from another tool
+    Import {
+        #[clap(subcommand)]
+        source: ImportSource,
+    },
+    /// Set, list or remove goals progressed by completing tasks
+    Goal {
+        #[clap(subcommand)]
+        action: Option<GoalAction>,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ImportSource {
+    /// Open issues of a GitHub repository, authenticated with $GITHUB_TOKEN when set
+    Github {
+        /// Repository as owner/repo
+        repo: String,
+        /// Only issues assigned to this login, or `me`
+        #[clap(long)]
+        assignee: Option<String>,
+    },
+    /// Cards of a Trello board exported as JSON
+    Trello {
+        /// Path to the board export
+        export: std::path::PathBuf,
+    },
+    /// Entries exported with `utd export --format json`, skipping those already here
+    Json {
+        /// Path to the export
+        file: std::path::PathBuf,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ContextAction {
+    /// Only show entries with this tag, and tag new entries with it
+    Set {
+        /// Tag of the context, e.g. work
+        name: String,
+    },
+    /// Show the whole board again
+    Clear,
+    /// Print the active context
+    Show,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ProjectAction {
+    /// Only show entries of this project, and file new entries under it
+    Switch {
+        /// Name of the project, e.g. release-2
+        name: String,
+    },
+    /// Show every project again
+    Clear,
+    /// Open and done tasks and the average time to finish one, per project
+    Stats,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum GoalAction {
+    /// Set a goal of completing a number of tasks by a date
+    Set {
+        /// Description of the goal
+        name: String,
+        /// Number of tasks to complete
+        #[clap(long)]
+        target: u32,
+        /// Deadline e.g. sunday, 2022-04-01 or 1w
+        #[clap(long)]
+        by: String,
+        /// Only count tasks with this tag
+        #[clap(long)]
+        tag: Option<String>,
+    },
+    /// List goals and their progress
+    List,
+    /// Remove a goal
+    Remove {
+        /// Description of the goal
+        name: String,
+    },
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum EntryKind {
+    Task,
+    Note,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum ExportFormat {
+    /// Org-mode headings for org-agenda
+    Org,
+    /// A single HTML page drawn with the theme colours
+    Html,
+    /// Entries as stored, for `utd import json`
+    Json,
+    /// REM lines of dated tasks for Remind
+    Remind,
+    /// Lines of dated tasks for the when calendar
+    When,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum ChatService {
+    Slack,
+    Discord,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum SyncProvider {
+    /// Google Tasks, the lists in the Gmail and Calendar sidebar
+    Google,
+    /// Microsoft To Do, the tasks of Outlook
+    Microsoft,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum BridgeTool {
+    /// Taskwarrior's on-add and on-modify hooks
+    Taskwarrior,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum ReportKind {
+    /// Timed work against interruptions per day
+    Focus,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum TimerAction {
+    Start,
+    Stop,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
@@ -72,7 +815,80 @@ pub enum LogLevel {
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
-pub enum SortParam {
+pub enum GraphFormat {
+    /// Graphviz, e.g. `utd graph | dot -Tsvg > graph.svg`
+    Dot,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, ArgEnum)]
+pub enum View {
+    /// Sections one after another
+    Board,
+    /// Sections side by side
+    Kanban,
+}
+
+/// Keys to sort by, the first deciding and each later one breaking ties
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SortOrder(pub Vec<SortKey>);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SortKey {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SortField {
     Age,
     Priority,
+    /// Undated entries come last in either direction
+    Due,
+    Name,
+    Id,
+}
+
+impl SortField {
+    /// A field without asc or desc sorts the oldest, most important and earliest due first
+    fn descending_by_default(self) -> bool {
+        matches!(self, SortField::Age | SortField::Priority)
+    }
+}
+
+impl std::str::FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split(',')
+            .map(|key| {
+                let key = key.to_lowercase();
+                let mut words = key.split_whitespace();
+                let field = match words.next() {
+                    Some("age") => SortField::Age,
+                    Some("priority") => SortField::Priority,
+                    Some("due") => SortField::Due,
+                    Some("name") => SortField::Name,
+                    Some("id") => SortField::Id,
+                    Some(other) => {
+                        return Err(format!(
+                            "expected age, priority, due, name or id to sort by, found {other}"
+                        ))
+                    }
+                    None => return Err(format!("expected a field to sort by in {s:?}")),
+                };
+                let descending = match words.next() {
+                    None => field.descending_by_default(),
+                    Some("asc") => false,
+                    Some("desc") => true,
+                    Some(other) => return Err(format!("expected asc or desc, found {other}")),
+                };
+                match words.next() {
+                    Some(other) => Err(format!("expected a comma before {other}")),
+                    None => Ok(SortKey { field, descending }),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(SortOrder(keys))
+    }
 }
@@ -0,0 +1,55 @@
+use chrono::{DateTime, Utc};
+
+use crate::Task;
+
+/// How many tasks were open at each of `instants`
+pub fn open_counts(tasks: &[Task], instants: &[DateTime<Utc>]) -> Vec<usize> {
+    instants
+        .iter()
+        .map(|at| {
+            tasks
+                .iter()
+                .filter(|f| f.is_task && f.created() <= *at)
+                .filter(|f| !matches!(f.closed_at(), Some(closed) if closed <= *at))
+                .count()
+        })
+        .collect()
+}
+
+/// Vertical bars `height` rows tall, top row first, with the scale on the left.
+///
+/// Bars are drawn with eighth blocks, or `#` when `ascii` is set.
+pub fn bar_chart(values: &[usize], height: usize, ascii: bool) -> Vec<String> {
+    const BLOCKS: [char; 9] = [' ', '▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    let label_width = max.to_string().len();
+    let mut lines: Vec<String> = (0..height)
+        .rev()
+        .map(|row| {
+            let label = match row {
+                row if row + 1 == height => max.to_string(),
+                0 => String::from("0"),
+                _ => String::new(),
+            };
+            let bars: String = values
+                .iter()
+                .flat_map(|&value| {
+                    let filled = (value * height * 8 / max).saturating_sub(row * 8).min(8);
+                    let block = match ascii {
+                        true if filled >= 4 => '#',
+                        true => ' ',
+                        false => BLOCKS[filled],
+                    };
+                    [block, block, ' ']
+                })
+                .collect();
+            format!("{label:>label_width$} |{}", bars.trim_end())
+        })
+        .collect();
+    lines.push(format!(
+        "{:>label_width$} +{}",
+        "",
+        "-".repeat(values.len() * 3)
+    ));
+    lines
+}
@@ -0,0 +1,189 @@
+use std::fmt;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+/// Why a configured colour could not be used
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ColourError {
+    Empty,
+    InvalidHex(String),
+    UnknownName(String),
+}
+
+impl fmt::Display for ColourError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColourError::Empty => write!(f, "empty colour"),
+            ColourError::InvalidHex(value) => {
+                write!(f, "invalid colour {value:?}, expected #rgb or #rrggbb")
+            }
+            ColourError::UnknownName(value) => write!(f, "unknown colour name {value:?}"),
+        }
+    }
+}
+
+impl std::error::Error for ColourError {}
+
+/// Terminal colour names and their usual xterm values
+const NAMED: &[(&str, (u8, u8, u8))] = &[
+    ("black", (0, 0, 0)),
+    ("red", (205, 0, 0)),
+    ("green", (0, 205, 0)),
+    ("yellow", (205, 205, 0)),
+    ("blue", (0, 0, 238)),
+    ("magenta", (205, 0, 205)),
+    ("cyan", (0, 205, 205)),
+    ("white", (229, 229, 229)),
+    ("grey", (127, 127, 127)),
+    ("gray", (127, 127, 127)),
+    ("bright-black", (127, 127, 127)),
+    ("bright-red", (255, 0, 0)),
+    ("bright-green", (0, 255, 0)),
+    ("bright-yellow", (255, 255, 0)),
+    ("bright-blue", (92, 92, 255)),
+    ("bright-magenta", (255, 0, 255)),
+    ("bright-cyan", (0, 255, 255)),
+    ("bright-white", (255, 255, 255)),
+];
+
+/// Resolves `#rrggbb`, `#rgb` or a name such as `red` or `bright-blue` to `(red, green, blue)`
+pub fn parse_colour(value: &str) -> Result<(u8, u8, u8), ColourError> {
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(ColourError::Empty);
+    }
+    match value.strip_prefix('#') {
+        Some(hex) => parse_hex(hex).ok_or_else(|| ColourError::InvalidHex(value.to_owned())),
+        None => {
+            let name = value.to_lowercase().replace('_', "-");
+            NAMED
+                .iter()
+                .find(|(f, _)| *f == name)
+                .map(|(_, rgb)| *rgb)
+                .ok_or_else(|| ColourError::UnknownName(value.to_owned()))
+        }
+    }
+}
+
+fn parse_hex(hex: &str) -> Option<(u8, u8, u8)> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    match hex.len() {
+        3 => {
+            // each digit is doubled, #fa0 is #ffaa00
+            let digit = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|d| d * 17);
+            Some((digit(0)?, digit(1)?, digit(2)?))
+        }
+        6 => {
+            let pair = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+            Some((pair(0)?, pair(2)?, pair(4)?))
+        }
+        _ => None,
+    }
+}
+
+/// Colours the terminal can display
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColourSupport {
+    TrueColour,
+    Ansi256,
+    Basic,
+    /// No styling at all, for `NO_COLOR`, pipes and `--no-color`
+    None,
+}
+
+impl ColourSupport {
+    /// Reads `$NO_COLOR`, `$COLORTERM` and `$TERM`, assuming the basic palette when none
+    /// says more. Output that isn't a terminal is never styled.
+    pub fn detect() -> Self {
+        if std::env::var_os("NO_COLOR").is_some_and(|f| !f.is_empty())
+            || !std::io::stdout().is_terminal()
+        {
+            return ColourSupport::None;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        let term = std::env::var("TERM").unwrap_or_default();
+        if matches!(colorterm.as_str(), "truecolor" | "24bit")
+            || term.ends_with("-direct")
+            || term.contains("truecolor")
+            // Windows Terminal supports truecolour but sets neither variable
+            || std::env::var_os("WT_SESSION").is_some()
+        {
+            ColourSupport::TrueColour
+        } else if term.contains("256color") {
+            ColourSupport::Ansi256
+        } else {
+            ColourSupport::Basic
+        }
+    }
+
+    /// The nearest colour this terminal can show to `(red, green, blue)`
+    pub fn colour(self, (r, g, b): (u8, u8, u8)) -> Option<ansi_term::Colour> {
+        match self {
+            ColourSupport::TrueColour => Some(ansi_term::Colour::RGB(r, g, b)),
+            ColourSupport::Ansi256 => Some(ansi_term::Colour::Fixed(nearest_256((r, g, b)))),
+            ColourSupport::Basic => Some(nearest_basic((r, g, b))),
+            ColourSupport::None => None,
+        }
+    }
+}
+
+static SUPPORT: OnceLock<ColourSupport> = OnceLock::new();
+
+/// Support detected once for the whole process
+pub fn colour_support() -> ColourSupport {
+    *SUPPORT.get_or_init(ColourSupport::detect)
+}
+
+/// Overrides detection, e.g. for `--no-color`. Has no effect once anything was styled.
+pub fn set_colour_support(support: ColourSupport) {
+    let _ = SUPPORT.set(support);
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let channel = |x: u8, y: u8| (i32::from(x) - i32::from(y)).pow(2) as u32;
+    channel(a.0, b.0) + channel(a.1, b.1) + channel(a.2, b.2)
+}
+
+/// Index into the xterm 6x6x6 cube or grey ramp, whichever is closer
+fn nearest_256(rgb: (u8, u8, u8)) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| {
+        (0..LEVELS.len())
+            .min_by_key(|&i| (i32::from(LEVELS[i]) - i32::from(v)).abs())
+            .unwrap_or(0)
+    };
+    let (r, g, b) = (level(rgb.0), level(rgb.1), level(rgb.2));
+    let cube = (LEVELS[r], LEVELS[g], LEVELS[b]);
+    let cube_index = 16 + 36 * r + 6 * g + b;
+
+    let average = (u32::from(rgb.0) + u32::from(rgb.1) + u32::from(rgb.2)) / 3;
+    let grey_step = (average.saturating_sub(8) / 10).min(23);
+    let grey_value = (8 + grey_step * 10) as u8;
+    let grey = (grey_value, grey_value, grey_value);
+    if distance(rgb, grey) < distance(rgb, cube) {
+        232 + grey_step as u8
+    } else {
+        cube_index as u8
+    }
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> ansi_term::Colour {
+    use ansi_term::Colour::*;
+    let palette = [
+        (Black, (0, 0, 0)),
+        (Red, (205, 0, 0)),
+        (Green, (0, 205, 0)),
+        (Yellow, (205, 205, 0)),
+        (Blue, (0, 0, 238)),
+        (Purple, (205, 0, 205)),
+        (Cyan, (0, 205, 205)),
+        (White, (229, 229, 229)),
+    ];
+    palette
+        .iter()
+        .min_by_key(|(_, value)| distance(rgb, *value))
+        .map(|(colour, _)| *colour)
+        .unwrap_or(White)
+}
@@ -1,9 +1,16 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
 use std::io::Read;
 
+use clap::ArgEnum;
 use serde::Deserialize;
 use serde::Serialize;
 use tracing::error;
 
+use crate::args::{ChatService, SortOrder, View};
+use crate::{icons, parse_colour, ColourError, TaskEvent, Translations};
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Config {
@@ -11,7 +18,71 @@ pub struct Config {
     #[serde(rename = "disable-title")]
     pub disable_title: Option<bool>,
     pub tags: Option<Tags>,
+    pub pins: Option<Pins>,
+    pub expired: Option<Expired>,
+    pub due: Option<Due>,
+    pub layout: Option<Layout>,
+    pub age: Option<Age>,
+    /// `auto` pages boards taller than the terminal, `always` or `never`
+    pub paging: Option<String>,
+    /// `json`, or `jsonl` to append changes to the board instead of rewriting it
+    pub storage: Option<String>,
+    /// `nerd`, `unicode` or `ascii` glyphs for icons left unset, `auto` to detect
+    pub icons: Option<String>,
+    /// Priority of new entries given none, e.g. `low` for an inbox to triage
+    #[serde(rename = "default-priority", alias = "default_priority")]
+    pub default_priority: Option<String>,
+    /// Tags of new entries given none, e.g. `["@inbox"]`
+    #[serde(rename = "default-tags", alias = "default_tags")]
+    pub default_tags: Option<Vec<String>>,
+    /// Order of the board when `--sort` is left out, e.g. `priority` or `due asc, age desc`
+    #[serde(rename = "default-sort", alias = "default_sort")]
+    pub default_sort: Option<String>,
+    /// `board`, or `kanban` for the sections side by side, when `--view` is left out
+    #[serde(rename = "default-view", alias = "default_view")]
+    pub default_view: Option<String>,
+    /// Counts of done, in progress and pending tasks with a progress bar below the board
+    pub footer: Option<bool>,
+    /// Leave the notes section off the board
+    #[serde(rename = "hide-notes")]
+    pub hide_notes: Option<bool>,
+    /// Show just this section, `todo`, `in-progress` or `notes`
+    pub only: Option<String>,
     pub sections: Option<Sections>,
+    pub logs: Option<Logs>,
+    /// Names that expand to longer invocations, e.g. `today = "--sort priority"`
+    pub aliases: Option<HashMap<String, String>>,
+    pub followup: Option<Followup>,
+    pub webhooks: Option<Webhooks>,
+    pub smtp: Option<Smtp>,
+    /// Hosted task lists `utd sync` keeps the boards in line with
+    pub sync: Option<SyncProviders>,
+    pub backup: Option<Backup>,
+    /// Weights `utd next` scores open tasks with
+    pub next: Option<Next>,
+    /// Boards kept under a name, shown with `utd view <name>`
+    pub views: Option<HashMap<String, SavedView>>,
+    pub user: Option<String>,
+    /// Language of the board e.g. `de`, taken from `$LANG` when unset
+    pub locale: Option<String>,
+    /// Text replacing any translatable string, by key e.g. `todo = "backlog"`
+    pub strings: Option<HashMap<String, String>>,
+    /// Zone dates are shown and read in e.g. `Europe/Berlin`, the system one when unset
+    pub timezone: Option<String>,
+    #[serde(rename = "verify-completions")]
+    pub verify_completions: Option<bool>,
+    /// Most tasks in progress at once, `--begin` warns beyond it
+    #[serde(rename = "wip-limit", alias = "wip_limit")]
+    pub wip_limit: Option<usize>,
+    /// Colour of the in-progress count once it reaches the limit
+    #[serde(rename = "wip-colour", alias = "wip_colour")]
+    pub wip_colour: Option<String>,
+    /// Renumber entries after deletes and `--tidy`, as `--reset-ids` does
+    #[serde(rename = "auto-reid", alias = "auto_reid")]
+    pub auto_reid: Option<bool>,
+    pub priorities: Option<Vec<Priority>>,
+    #[serde(rename = "time-tracking")]
+    pub time_tracking: Option<TimeTracking>,
 }
 
 impl Default for Config {
@@ -20,7 +91,539 @@ impl Default for Config {
             borders: Some(String::from("empty")),
             disable_title: Some(false),
             tags: Some(Tags::default()),
+            pins: Some(Pins::default()),
+            expired: Some(Expired::default()),
+            due: Some(Due::default()),
+            layout: Some(Layout::default()),
+            age: Some(Age::default()),
+            paging: Some(String::from("auto")),
+            storage: Some(String::from("json")),
+            icons: Some(String::from("auto")),
+            default_priority: None,
+            default_tags: None,
+            default_sort: None,
+            default_view: Some(String::from("board")),
+            footer: Some(false),
+            hide_notes: Some(false),
+            only: None,
             sections: Some(Sections::default()),
+            logs: Some(Logs::default()),
+            aliases: None,
+            followup: Some(Followup::default()),
+            webhooks: None,
+            smtp: None,
+            sync: None,
+            backup: Some(Backup::default()),
+            next: Some(Next::default()),
+            views: None,
+            user: None,
+            locale: None,
+            strings: None,
+            timezone: None,
+            verify_completions: Some(false),
+            wip_limit: None,
+            wip_colour: Some(String::from("#fb4934")),
+            auto_reid: Some(false),
+            priorities: None,
+            time_tracking: Some(TimeTracking::default()),
+        }
+    }
+}
+
+impl Config {
+    /// Name recorded as the owner/checker of entries, falling back to the login name
+    pub fn user(&self) -> String {
+        match self.user.as_ref() {
+            Some(user) => user.to_owned(),
+            None => std::env::var("USER")
+                .or_else(|_| std::env::var("USERNAME"))
+                .unwrap_or_else(|_| String::from("unknown")),
+        }
+    }
+
+    /// Checks every configured colour resolves, so mistakes surface when the config is read
+    pub fn validate_colours(&self) -> std::result::Result<(), ColourError> {
+        let tags = self.tags.as_ref().cloned().unwrap_or_default();
+        let pins = self.pins.as_ref().cloned().unwrap_or_default();
+        let expired = self.expired.as_ref().cloned().unwrap_or_default();
+        let due = self.due.as_ref().cloned().unwrap_or_default();
+        let sections = self.sections.as_ref().cloned().unwrap_or_default();
+        let mut colours = vec![
+            tags.colour().to_owned(),
+            pins.colour().to_owned(),
+            expired.colour().to_owned(),
+            due.overdue_colour().to_owned(),
+            due.today_colour().to_owned(),
+        ];
+        colours.extend(tags.colours.iter().flat_map(|f| f.values().cloned()));
+        let age = self.age.as_ref().cloned().unwrap_or_default();
+        colours.extend([age.colour(), age.stale_colour(), age.ancient_colour()].map(str::to_owned));
+        colours.extend(self.priority_levels().into_iter().filter_map(|f| f.colour));
+        colours.push(sections.title.unwrap_or_default().title_colour().to_owned());
+        colours.extend(section_colours(&sections.todo.unwrap_or_default()));
+        colours.extend(section_colours(&sections.in_progress.unwrap_or_default()));
+        colours.extend(section_colours(&sections.notes.unwrap_or_default()));
+        for colour in colours.iter() {
+            parse_colour(colour)?;
+        }
+        Ok(())
+    }
+
+    pub fn translations(&self) -> Translations {
+        Translations::new(
+            &crate::language(self.locale.as_deref()),
+            self.strings.clone().unwrap_or_default(),
+        )
+    }
+
+    pub fn default_sort(&self) -> Result<Option<SortOrder>> {
+        match self.default_sort.as_deref() {
+            Some(sort) => Ok(Some(
+                sort.parse().map_err(|e| format!("{e} for default-sort"))?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// The view `name` from `[views]`
+    pub fn saved_view(&self, name: &str) -> Result<SavedView> {
+        match self.views.as_ref().and_then(|f| f.get(name)) {
+            Some(view) => Ok(view.clone()),
+            None => Err(format!("no view {name} in [views], `utd view` lists them").into()),
+        }
+    }
+
+    pub fn default_view(&self) -> Result<View> {
+        let view = self.default_view.as_deref().unwrap_or("board");
+        Ok(View::from_str(view, true)
+            .map_err(|_| format!("expected board or kanban for default-view, found {view}"))?)
+    }
+
+    pub fn paging(&self) -> &str {
+        match self.paging.as_ref() {
+            Some(c) => c,
+            None => "auto",
+        }
+    }
+
+    pub fn icons(&self) -> &str {
+        match self.icons.as_ref() {
+            Some(c) => c,
+            None => "auto",
+        }
+    }
+
+    pub fn storage(&self) -> &str {
+        match self.storage.as_ref() {
+            Some(c) => c,
+            None => "json",
+        }
+    }
+
+    pub fn verify_completions(&self) -> bool {
+        self.verify_completions.unwrap_or(false)
+    }
+
+    pub fn wip_colour(&self) -> &str {
+        match self.wip_colour.as_ref() {
+            Some(c) => c,
+            None => "#fb4934",
+        }
+    }
+
+    pub fn auto_reid(&self) -> bool {
+        self.auto_reid.unwrap_or(false)
+    }
+
+    /// Priority levels ordered from the most to the least important
+    pub fn priority_levels(&self) -> Vec<Priority> {
+        match self.priorities.as_ref() {
+            Some(levels) if !levels.is_empty() => levels.to_vec(),
+            _ => ["high", "normal", "low"]
+                .into_iter()
+                .map(|name| Priority {
+                    name: name.to_owned(),
+                    colour: None,
+                })
+                .collect(),
+        }
+    }
+
+    /// The `default-priority` when it names a level, else `normal` or the middle level
+    pub fn default_priority(&self) -> String {
+        let levels = self.priority_levels();
+        let configured = self
+            .default_priority
+            .as_ref()
+            .filter(|name| levels.iter().any(|f| f.name == **name));
+        if let Some(name) = configured {
+            return name.to_owned();
+        }
+        match levels.iter().find(|f| f.name == "normal") {
+            Some(level) => level.name.to_owned(),
+            None => levels[levels.len() / 2].name.to_owned(),
+        }
+    }
+
+    /// Higher scores are more important, unknown levels score 0
+    pub fn priority_score(&self, priority: &str) -> usize {
+        let levels = self.priority_levels();
+        match levels.iter().position(|f| f.name == priority) {
+            Some(index) => levels.len() - index,
+            None => 0,
+        }
+    }
+
+    /// Entry text as it should appear in the log file, honouring `[logs] redact`
+    pub fn redact(&self, text: &str) -> String {
+        let logs = self.logs.as_ref().cloned().unwrap_or_default();
+        match logs.redact() {
+            "hash" => {
+                let mut hasher = DefaultHasher::new();
+                text.hash(&mut hasher);
+                format!("#{:016x}", hasher.finish())
+            }
+            "truncate" => {
+                let mut redacted: String = text.chars().take(3).collect();
+                if text.chars().count() > 3 {
+                    redacted.push('…');
+                }
+                redacted
+            }
+            _ => text.to_owned(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Priority {
+    pub name: String,
+    pub colour: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeTracking {
+    pub enabled: Option<bool>,
+    #[serde(rename = "auto-timer")]
+    pub auto_timer: Option<bool>,
+}
+
+impl Default for TimeTracking {
+    fn default() -> Self {
+        Self {
+            enabled: Some(false),
+            auto_timer: Some(true),
+        }
+    }
+}
+
+impl TimeTracking {
+    pub fn enabled(&self) -> bool {
+        self.enabled.unwrap_or(false)
+    }
+
+    /// Start timers with `--begin` and stop them with `--check`
+    pub fn auto_timer(&self) -> bool {
+        self.enabled() && self.auto_timer.unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Logs {
+    pub redact: Option<String>,
+    /// `file`, or `stderr` to keep nothing on disk
+    pub destination: Option<String>,
+    /// Log file, `utd-log` in the data directory when unset
+    pub path: Option<String>,
+    /// Size in KiB at which the log file is rotated
+    #[serde(rename = "max-size")]
+    pub max_size: Option<u64>,
+    /// Rotated files kept besides the current one
+    #[serde(rename = "max-files")]
+    pub max_files: Option<usize>,
+}
+
+impl Default for Logs {
+    fn default() -> Self {
+        Self {
+            redact: Some(String::from("none")),
+            destination: Some(String::from("file")),
+            path: None,
+            max_size: Some(1024),
+            max_files: Some(5),
+        }
+    }
+}
+
+impl Logs {
+    pub fn redact(&self) -> &str {
+        match self.redact.as_ref() {
+            Some(c) => c,
+            None => "none",
+        }
+    }
+
+    pub fn destination(&self) -> &str {
+        match self.destination.as_ref() {
+            Some(c) => c,
+            None => "file",
+        }
+    }
+
+    pub fn path(&self) -> std::path::PathBuf {
+        match self.path.as_ref() {
+            Some(c) => std::path::PathBuf::from(c),
+            None => crate::data_dir().join("utd-log"),
+        }
+    }
+
+    pub fn max_size(&self) -> u64 {
+        self.max_size.unwrap_or(1024)
+    }
+
+    pub fn max_files(&self) -> usize {
+        self.max_files.unwrap_or(5)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Followup {
+    /// How long a task may wait before it is due a follow-up, e.g. `3d`
+    pub after: Option<String>,
+    /// URL that pings are posted to as JSON
+    pub webhook: Option<String>,
+}
+
+impl Default for Followup {
+    fn default() -> Self {
+        Self {
+            after: Some(String::from("3d")),
+            webhook: None,
+        }
+    }
+}
+
+impl Followup {
+    pub fn after(&self) -> &str {
+        match self.after.as_ref() {
+            Some(c) => c,
+            None => "3d",
+        }
+    }
+}
+
+/// URLs that the affected task is posted to as JSON on each event
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Webhooks {
+    pub added: Option<String>,
+    pub completed: Option<String>,
+    pub started: Option<String>,
+    /// Channels `utd post` writes to
+    pub slack: Option<String>,
+    pub discord: Option<String>,
+}
+
+impl Webhooks {
+    pub fn url(&self, event: TaskEvent) -> Option<&str> {
+        match event {
+            TaskEvent::Added => self.added.as_deref(),
+            TaskEvent::Completed => self.completed.as_deref(),
+            TaskEvent::Started => self.started.as_deref(),
+        }
+    }
+
+    pub fn chat(&self, service: ChatService) -> Option<&str> {
+        match service {
+            ChatService::Slack => self.slack.as_deref(),
+            ChatService::Discord => self.discord.as_deref(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncProviders {
+    pub google: Option<OAuthApp>,
+    pub microsoft: Option<OAuthApp>,
+}
+
+/// An app registered with a provider to sign in with
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OAuthApp {
+    #[serde(rename = "client-id")]
+    pub client_id: Option<String>,
+    #[serde(rename = "client-secret")]
+    pub client_secret: Option<String>,
+    /// Task list of the main board, other boards get lists named `<list>-<board>`
+    pub list: Option<String>,
+    /// Microsoft directory to sign in to, `common` for any account
+    pub tenant: Option<String>,
+}
+
+impl OAuthApp {
+    pub fn client_id(&self) -> std::result::Result<&str, &'static str> {
+        self.client_id
+            .as_deref()
+            .ok_or("set the client-id of your app under [sync]")
+    }
+
+    pub fn list(&self) -> &str {
+        match self.list.as_ref() {
+            Some(c) => c,
+            None => "utd",
+        }
+    }
+
+    pub fn tenant(&self) -> &str {
+        match self.tenant.as_ref() {
+            Some(c) => c,
+            None => "common",
+        }
+    }
+}
+
+/// Mail server `utd digest` sends through
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Smtp {
+    pub host: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    /// Read from `$UTD_SMTP_PASSWORD` when unset
+    pub password: Option<String>,
+    /// Sender address, the user when unset
+    pub from: Option<String>,
+    /// Recipient when `--email` is left out
+    pub to: Option<String>,
+    /// `starttls`, `tls` for a TLS connection from the start, or `none`
+    pub security: Option<String>,
+}
+
+impl Smtp {
+    pub fn security(&self) -> &str {
+        match self.security.as_ref() {
+            Some(c) => c,
+            None => "starttls",
+        }
+    }
+
+    pub fn password(&self) -> Option<String> {
+        self.password
+            .clone()
+            .or_else(|| std::env::var("UTD_SMTP_PASSWORD").ok())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Backup {
+    /// Back up the board on the first run of each day
+    pub auto: Option<bool>,
+    /// How many backups of each board to keep
+    pub keep: Option<usize>,
+    /// How many snapshots taken before destructive commands to keep, 0 to take none
+    pub snapshots: Option<usize>,
+}
+
+impl Default for Backup {
+    fn default() -> Self {
+        Self {
+            auto: Some(false),
+            keep: Some(10),
+            snapshots: Some(20),
+        }
+    }
+}
+
+impl Backup {
+    pub fn auto(&self) -> bool {
+        self.auto.unwrap_or(false)
+    }
+
+    pub fn keep(&self) -> usize {
+        self.keep.unwrap_or(10).max(1)
+    }
+
+    pub fn snapshots(&self) -> usize {
+        self.snapshots.unwrap_or(20)
+    }
+}
+
+/// Weights of what makes a task the best to do next, each measured from 0 to 1
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Next {
+    pub priority: Option<f64>,
+    pub age: Option<f64>,
+    pub due: Option<f64>,
+    /// Counts for blocked and waiting tasks, usually against them
+    pub blocked: Option<f64>,
+}
+
+impl Default for Next {
+    fn default() -> Self {
+        Self {
+            priority: Some(3.0),
+            age: Some(1.0),
+            due: Some(4.0),
+            blocked: Some(-10.0),
+        }
+    }
+}
+
+impl Next {
+    pub fn priority(&self) -> f64 {
+        self.priority.unwrap_or(3.0)
+    }
+
+    pub fn age(&self) -> f64 {
+        self.age.unwrap_or(1.0)
+    }
+
+    pub fn due(&self) -> f64 {
+        self.due.unwrap_or(4.0)
+    }
+
+    pub fn blocked(&self) -> f64 {
+        self.blocked.unwrap_or(-10.0)
+    }
+}
+
+/// A filter, sort order and layout kept together under `[views.<name>]`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedView {
+    /// Conditions as for `--filter`, e.g. "state=open tag=@work"
+    pub filter: Option<String>,
+    /// Keys as for `--sort`, e.g. "due asc, priority desc"
+    pub sort: Option<String>,
+    /// `board` or `kanban`
+    pub layout: Option<String>,
+}
+
+impl SavedView {
+    pub fn sort(&self) -> Result<Option<SortOrder>> {
+        match self.sort.as_deref() {
+            Some(sort) => Ok(Some(
+                sort.parse()
+                    .map_err(|e| format!("{e} for the sort of a view"))?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    pub fn layout(&self) -> Result<Option<View>> {
+        match self.layout.as_deref() {
+            Some(layout) => Ok(Some(View::from_str(layout, true).map_err(|_| {
+                format!("expected board or kanban for the layout of a view, found {layout}")
+            })?)),
+            None => Ok(None),
         }
     }
 }
@@ -28,6 +631,7 @@ impl Default for Config {
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Tags {
+    pub prefix: Option<String>,
     pub colour: Option<String>,
     pub underline: Option<bool>,
     pub bold: Option<bool>,
@@ -35,22 +639,106 @@ pub struct Tags {
     pub icon: Option<String>,
     #[serde(rename = "icon-suffix")]
     pub icon_suffix: Option<bool>,
+    /// Shorthand tag names mapped to the tag stored in their place
+    pub aliases: Option<HashMap<String, String>>,
+    /// Colours for single tags, keyed with or without the prefix
+    pub colours: Option<HashMap<String, String>>,
+    /// Replace likely typos of existing tags without asking
+    #[serde(rename = "auto-correct")]
+    pub auto_correct: Option<bool>,
+    /// Colour tags like the priority of their task, unless in `[tags.colours]`
+    #[serde(rename = "inherit-priority-colour", alias = "inherit_priority_colour")]
+    pub inherit_priority_colour: Option<bool>,
 }
 
 impl Default for Tags {
     fn default() -> Self {
         Self {
+            prefix: Some(String::from("@")),
             colour: Some(String::from("#689d6a")),
             underline: Some(false),
             bold: Some(false),
             italic: Some(false),
-            icon: Some(String::default()),
+            icon: None,
             icon_suffix: Some(false),
+            aliases: None,
+            colours: None,
+            auto_correct: Some(false),
+            inherit_priority_colour: Some(false),
         }
     }
 }
 
 impl Tags {
+    /// The tag `tag` is stored as, following aliases
+    pub fn canonical(&self, tag: &str) -> String {
+        match self.aliases.as_ref().and_then(|f| f.get(tag)) {
+            Some(canonical) => canonical.trim_start_matches(self.prefix()).to_owned(),
+            None => tag.to_owned(),
+        }
+    }
+
+    /// The colour of `tag`, its own from `[tags.colours]` or the shared one
+    pub fn colour_for(&self, tag: &str) -> &str {
+        match self.own_colour(tag) {
+            Some(c) => c,
+            None => self.colour(),
+        }
+    }
+
+    /// The colour `[tags.colours]` gives `tag`, if any
+    pub fn own_colour(&self, tag: &str) -> Option<&str> {
+        let colours = self.colours.as_ref()?;
+        colours
+            .get(&self.label(tag))
+            .or_else(|| colours.get(&format!("{}{}", self.prefix(), tag)))
+            .or_else(|| colours.get(tag))
+            .map(String::as_str)
+    }
+
+    pub fn auto_correct(&self) -> bool {
+        self.auto_correct.unwrap_or(false)
+    }
+
+    pub fn inherit_priority_colour(&self) -> bool {
+        self.inherit_priority_colour.unwrap_or(false)
+    }
+
+    /// Canonical tags in their original order, without duplicates
+    pub fn canonicalise(&self, tags: &[String]) -> Vec<String> {
+        let mut canonical: Vec<String> = Vec::with_capacity(tags.len());
+        for tag in tags.iter().map(|f| self.canonical(f)) {
+            if !canonical.contains(&tag) {
+                canonical.push(tag);
+            }
+        }
+        canonical
+    }
+
+    /// Character introducing a tag in entry text
+    pub fn prefix(&self) -> char {
+        self.prefix
+            .as_ref()
+            .and_then(|f| f.chars().next())
+            .unwrap_or('@')
+    }
+
+    /// A tag as it is typed, quoted when it holds spaces
+    pub fn label(&self, tag: &str) -> String {
+        if tag.contains(char::is_whitespace) {
+            format!("{}\"{}\"", self.prefix(), tag)
+        } else {
+            format!("{}{}", self.prefix(), tag)
+        }
+    }
+
+    pub fn labels(&self, tags: &[String]) -> String {
+        tags.iter()
+            .map(|f| self.label(f))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
     pub fn colour(&self) -> &str {
         match self.colour.as_ref() {
             Some(c) => c,
@@ -74,7 +762,230 @@ impl Tags {
     pub fn icon(&self) -> &str {
         match self.icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().tag,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Layout {
+    /// Widest an entry may be, in columns, before it is wrapped or truncated
+    #[serde(rename = "max-title-width")]
+    pub max_title_width: Option<usize>,
+    /// `wrap` long entries onto more lines or `truncate` them with an ellipsis
+    pub overflow: Option<String>,
+    /// `lines`, or `columns` to give the id, priority, age and tags of tasks their own column
+    pub mode: Option<String>,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            max_title_width: None,
+            overflow: Some(String::from("wrap")),
+            mode: Some(String::from("lines")),
+        }
+    }
+}
+
+impl Layout {
+    pub fn truncate(&self) -> bool {
+        matches!(self.overflow.as_deref(), Some("truncate"))
+    }
+
+    pub fn columns(&self) -> bool {
+        matches!(self.mode.as_deref(), Some("columns"))
+    }
+}
+
+/// How long open tasks have been on the board, coloured by staleness
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Age {
+    pub show: Option<bool>,
+    pub colour: Option<String>,
+    #[serde(rename = "stale-after")]
+    pub stale_after: Option<String>,
+    #[serde(rename = "stale-colour")]
+    pub stale_colour: Option<String>,
+    /// Marks open tasks untouched for longer than `stale-after`
+    #[serde(rename = "stale-icon")]
+    pub stale_icon: Option<String>,
+    #[serde(rename = "ancient-after")]
+    pub ancient_after: Option<String>,
+    #[serde(rename = "ancient-colour")]
+    pub ancient_colour: Option<String>,
+}
+
+impl Default for Age {
+    fn default() -> Self {
+        Self {
+            show: Some(true),
+            colour: Some(String::from("#928374")),
+            stale_after: Some(String::from("1w")),
+            stale_colour: Some(String::from("#d79921")),
+            stale_icon: None,
+            ancient_after: Some(String::from("4w")),
+            ancient_colour: Some(String::from("#cc241d")),
+        }
+    }
+}
+
+impl Age {
+    pub fn show(&self) -> bool {
+        self.show.unwrap_or(true)
+    }
+
+    pub fn colour(&self) -> &str {
+        match self.colour.as_ref() {
+            Some(c) => c,
+            None => "#928374",
+        }
+    }
+
+    pub fn stale_after(&self) -> &str {
+        match self.stale_after.as_ref() {
+            Some(c) => c,
+            None => "1w",
+        }
+    }
+
+    pub fn stale_colour(&self) -> &str {
+        match self.stale_colour.as_ref() {
+            Some(c) => c,
+            None => "#d79921",
+        }
+    }
+
+    pub fn stale_icon(&self) -> &str {
+        match self.stale_icon.as_ref() {
+            Some(c) => c,
+            None => icons().stale,
+        }
+    }
+
+    pub fn ancient_after(&self) -> &str {
+        match self.ancient_after.as_ref() {
+            Some(c) => c,
+            None => "4w",
+        }
+    }
+
+    pub fn ancient_colour(&self) -> &str {
+        match self.ancient_colour.as_ref() {
+            Some(c) => c,
+            None => "#cc241d",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Pins {
+    pub colour: Option<String>,
+    pub icon: Option<String>,
+}
+
+impl Default for Pins {
+    fn default() -> Self {
+        Self {
+            colour: Some(String::from("#fabd2f")),
+            icon: None,
+        }
+    }
+}
+
+impl Pins {
+    pub fn colour(&self) -> &str {
+        match self.colour.as_ref() {
+            Some(c) => c,
+            None => "#fabd2f",
+        }
+    }
+
+    pub fn icon(&self) -> &str {
+        match self.icon.as_ref() {
+            Some(c) => c,
+            None => icons().pin,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Expired {
+    pub colour: Option<String>,
+    pub icon: Option<String>,
+    /// How long expired entries stay on the board before moving to the archive
+    #[serde(rename = "archive-after", alias = "archive_after")]
+    pub archive_after: Option<String>,
+}
+
+impl Default for Expired {
+    fn default() -> Self {
+        Self {
+            colour: Some(String::from("#928374")),
+            icon: None,
+            archive_after: Some(String::from("1d")),
+        }
+    }
+}
+
+impl Expired {
+    pub fn colour(&self) -> &str {
+        match self.colour.as_ref() {
+            Some(c) => c,
+            None => "#928374",
+        }
+    }
+
+    pub fn icon(&self) -> &str {
+        match self.icon.as_ref() {
+            Some(c) => c,
+            None => icons().expired,
+        }
+    }
+
+    pub fn archive_after(&self) -> &str {
+        match self.archive_after.as_ref() {
+            Some(c) => c,
+            None => "1d",
+        }
+    }
+}
+
+/// Highlighting of entries by due date
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Due {
+    #[serde(rename = "overdue-colour")]
+    pub overdue_colour: Option<String>,
+    #[serde(rename = "today-colour")]
+    pub today_colour: Option<String>,
+}
+
+impl Default for Due {
+    fn default() -> Self {
+        Self {
+            overdue_colour: Some(String::from("#fb4934")),
+            today_colour: Some(String::from("#83a598")),
+        }
+    }
+}
+
+impl Due {
+    pub fn overdue_colour(&self) -> &str {
+        match self.overdue_colour.as_ref() {
+            Some(c) => c,
+            None => "#fb4934",
+        }
+    }
+
+    pub fn today_colour(&self) -> &str {
+        match self.today_colour.as_ref() {
+            Some(c) => c,
+            None => "#83a598",
         }
     }
 }
@@ -110,6 +1021,14 @@ pub struct Title {
     #[serde(rename = "icon-suffix")]
     pub icon_suffix: Option<bool>,
     pub colour: Option<String>,
+    /// Shown instead of a random greeting
+    pub text: Option<String>,
+    /// Shell command whose first line of output is the title, e.g. `date +%A`
+    pub command: Option<String>,
+    /// Greetings the title is picked from at random
+    pub greetings: Option<Vec<String>>,
+    /// `false` leaves just the icon when no text or command is set
+    pub greeting: Option<bool>,
 }
 
 impl Default for Title {
@@ -118,13 +1037,23 @@ impl Default for Title {
             underline: Some(false),
             bold: Some(false),
             italic: Some(false),
-            icon: Some(String::default()),
+            icon: None,
             icon_suffix: Some(false),
             colour: Some(String::from("#ebdbb2")),
+            text: None,
+            command: None,
+            greetings: None,
+            greeting: Some(true),
         }
     }
 }
 
+impl Title {
+    pub fn greeting(&self) -> bool {
+        self.greeting.unwrap_or(true)
+    }
+}
+
 impl Configurable for Title {
     fn indent_spaces(&self) -> u8 {
         unimplemented!()
@@ -164,7 +1093,7 @@ impl Configurable for Title {
     fn title_icon(&self) -> &str {
         match self.icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().title,
         }
     }
 
@@ -297,14 +1226,14 @@ impl Configurable for Todo {
     fn title_icon(&self) -> &str {
         match self.title_icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().todo_title,
         }
     }
 
     fn entry_icon(&self) -> &str {
         match self.entry_icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().todo_entry,
         }
     }
 
@@ -345,7 +1274,7 @@ impl Configurable for Todo {
     fn completed_icon(&self) -> &str {
         match self.completed_icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().todo_completed,
         }
     }
 }
@@ -422,14 +1351,14 @@ impl Configurable for InProgress {
     fn title_icon(&self) -> &str {
         match self.title_icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().in_progress_title,
         }
     }
 
     fn entry_icon(&self) -> &str {
         match self.entry_icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().in_progress_entry,
         }
     }
     fn colour_low(&self) -> &str {
@@ -514,9 +1443,9 @@ impl Default for Notes {
             title_bold: Some(false),
             title_italic: Some(false),
             title_underline: Some(false),
-            title_icon: Some(String::default()),
+            title_icon: None,
             title_icon_suffix: Some(false),
-            entry_icon: Some(String::default()),
+            entry_icon: None,
             entry_icon_suffix: Some(false),
             entry_bold: Some(false),
             entry_italic: Some(false),
@@ -525,7 +1454,7 @@ impl Default for Notes {
             colour_normal: Some("#ebdbb2".to_owned()),
             colour_high: Some("#ebdbb2".to_owned()),
             colour_completed: Some("#458588".to_owned()),
-            completed_icon: Some(String::default()),
+            completed_icon: None,
         }
     }
 }
@@ -567,14 +1496,14 @@ impl Configurable for Notes {
     fn title_icon(&self) -> &str {
         match self.title_icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().notes_title,
         }
     }
 
     fn entry_icon(&self) -> &str {
         match self.entry_icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().notes_entry,
         }
     }
     fn colour_low(&self) -> &str {
@@ -615,10 +1544,23 @@ impl Configurable for Notes {
     fn completed_icon(&self) -> &str {
         match self.completed_icon.as_ref() {
             Some(c) => c,
-            None => "",
+            None => icons().notes_completed,
         }
     }
 }
+fn section_colours(section: &impl Configurable) -> Vec<String> {
+    [
+        section.title_colour(),
+        section.colour_low(),
+        section.colour_normal(),
+        section.colour_high(),
+        section.colour_completed(),
+    ]
+    .iter()
+    .map(|f| f.to_string())
+    .collect()
+}
+
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 pub fn read_config_file(retry: bool) -> Result<Config> {
     let op = std::fs::OpenOptions::new()
@@ -626,15 +1568,25 @@ pub fn read_config_file(retry: bool) -> Result<Config> {
         .open(get_config_file_path(retry));
     match op {
         Ok(contents) => {
+            let key = cache_key(&contents, retry);
+            if let Some(config) = key.and_then(read_cached_config) {
+                return Ok(config);
+            }
             let mut buf_reader = std::io::BufReader::new(contents);
             let mut contents = String::new();
             match buf_reader.read_to_string(&mut contents) {
-                Ok(_) => Ok(toml::from_str(&contents)?),
+                Ok(_) => {
+                    let config = parse_config(&contents)?;
+                    if let Some(key) = key {
+                        write_cached_config(key, &config);
+                    }
+                    Ok(config)
+                }
                 Err(e) => {
                     error!("{}, using default configuration", e);
                     let file = include_bytes!("../config.toml");
                     let contents = String::from_utf8_lossy(file);
-                    Ok(toml::from_str(&contents)?)
+                    parse_config(&contents)
                 }
             }
         }
@@ -645,7 +1597,7 @@ pub fn read_config_file(retry: bool) -> Result<Config> {
                 } else {
                     let file = include_bytes!("../config.toml");
                     let contents = String::from_utf8_lossy(file);
-                    Ok(toml::from_str(&contents)?)
+                    parse_config(&contents)
                 }
             }
             _ => {
@@ -656,6 +1608,77 @@ pub fn read_config_file(retry: bool) -> Result<Config> {
     }
 }
 
+fn parse_config(contents: &str) -> Result<Config> {
+    let config: Config = toml::from_str(contents)?;
+    config.validate_colours()?;
+    Ok(config)
+}
+
+/// A parsed and validated config, valid while the file keeps the same key
+#[derive(Serialize, Deserialize)]
+struct CachedConfig {
+    key: u64,
+    config: Config,
+}
+
+fn cache_path() -> std::path::PathBuf {
+    let mut path = crate::data_dir();
+    path.push(".config-cache");
+    path
+}
+
+/// Hash of the config file's path, size and modification time, and of this version of utd
+fn cache_key(file: &std::fs::File, retry: bool) -> Option<u64> {
+    let metadata = file.metadata().ok()?;
+    let modified = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    let mut hasher = DefaultHasher::new();
+    get_config_file_path(retry).hash(&mut hasher);
+    metadata.len().hash(&mut hasher);
+    modified.hash(&mut hasher);
+    env!("CARGO_PKG_VERSION").hash(&mut hasher);
+    Some(hasher.finish())
+}
+
+fn read_cached_config(key: u64) -> Option<Config> {
+    let bytes = std::fs::read(cache_path()).ok()?;
+    let cached: CachedConfig = bincode::deserialize(&bytes).ok()?;
+    (cached.key == key).then_some(cached.config)
+}
+
+/// Best effort, a missing cache only costs parsing the config again. Only the user can
+/// read it, as the config may hold passwords and client secrets.
+fn write_cached_config(key: u64, config: &Config) {
+    let cached = CachedConfig {
+        key,
+        config: config.clone(),
+    };
+    match bincode::serialize(&cached) {
+        Ok(bytes) => {
+            if let Err(e) = crate::write_private(&cache_path(), bytes) {
+                error!("unable to cache config: {}", e);
+            }
+        }
+        Err(e) => error!("unable to cache config: {}", e),
+    }
+}
+
+/// The config file read, `utd/config.toml` or else the older `utd.toml`, if either exists
+pub fn config_file_path() -> Option<std::path::PathBuf> {
+    [false, true]
+        .into_iter()
+        .map(get_config_file_path)
+        .find(|f| f.exists())
+}
+
+/// Where `utd script` looks for scripts, `scripts` next to the config file
+pub fn scripts_dir() -> std::path::PathBuf {
+    get_config_file_path(false).with_file_name("scripts")
+}
+
 fn get_config_file_path(retry: bool) -> std::path::PathBuf {
     use directories::ProjectDirs;
     let dirs = ProjectDirs::from("org", "Ugly Todo", "utd").unwrap();
@@ -1,6 +1,9 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::args::PriorityLevel;
+use crate::Config;
 
 pub type Tasks = Vec<Task>;
 
@@ -9,7 +12,9 @@ pub type Tasks = Vec<Task>;
 pub struct Task {
     pub id: i64,
     pub name: String,
-    pub tags: String,
+    /// Tag names without their prefix
+    #[serde(deserialize_with = "tags_from_text_or_list")]
+    pub tags: Vec<String>,
     #[serde(rename = "is_task")]
     pub is_task: bool,
     #[serde(rename = "is_done")]
@@ -18,38 +23,1217 @@ pub struct Task {
     #[serde(rename = "in_progress")]
     pub in_progress: bool,
     pub priority: String,
+    #[serde(default)]
+    pub attachments: Vec<String>,
+    #[serde(default)]
+    pub owner: Option<String>,
+    #[serde(rename = "checked_by", default)]
+    pub checked_by: Option<String>,
+    /// Seconds tracked on stopped timers
+    #[serde(rename = "time_spent", default)]
+    pub time_spent: u64,
+    /// Unix time at which the running timer was started
+    #[serde(rename = "timer_started", default)]
+    pub timer_started: Option<u64>,
+    #[serde(rename = "snoozed_until", default)]
+    pub snoozed_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub pinned: bool,
+    /// Id of the task this one was split from
+    #[serde(rename = "split_from", default)]
+    pub split_from: Option<i64>,
+    /// Ids of the tasks this one was split into
+    #[serde(rename = "split_into", default)]
+    pub split_into: Vec<i64>,
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    #[serde(rename = "expires_at", default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub expired: bool,
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(rename = "cancel_reason", default)]
+    pub cancel_reason: Option<String>,
+    /// Set while the task waits on someone else
+    #[serde(rename = "waiting_since", default)]
+    pub waiting_since: Option<DateTime<Utc>>,
+    /// Who the task is delegated to or waiting on
+    #[serde(default)]
+    pub assignee: Option<String>,
+    /// Ticket or message the task is waiting on, e.g. `JIRA-123`
+    #[serde(rename = "external_ref", default)]
+    pub external_ref: Option<String>,
+    /// Longer description shown by `utd show`
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(rename = "completed_at", default)]
+    pub completed_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub due: Option<DateTime<Utc>>,
+    /// How long after a recurring task is due its next occurrence is, e.g. `1w`
+    #[serde(default)]
+    pub recur: Option<String>,
+    /// Set once the next occurrence of a completed recurring task was added
+    #[serde(default)]
+    pub recurred: bool,
+    /// Ids of the tasks that have to be done first
+    #[serde(rename = "depends_on", default)]
+    pub depends_on: Vec<i64>,
+    /// Expected effort in seconds
+    #[serde(default)]
+    pub estimate: Option<u64>,
+    /// Dated notes, only ever appended to
+    #[serde(default)]
+    pub annotations: Vec<Annotation>,
+    /// Project the task belongs to, written `+name` in the entry text
+    #[serde(default)]
+    pub project: Option<String>,
+    /// When the task is planned to be worked on, set by accepting `utd plan`
+    #[serde(default)]
+    pub scheduled: Option<DateTime<Utc>>,
+}
+
+/// The task `utd focus` shows, kept by timestamp so renumbering doesn't lose it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Focus {
+    pub task: String,
+    pub since: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub at: DateTime<Utc>,
+    pub event: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub at: DateTime<Utc>,
+    pub text: String,
+}
+
+/// A target number of tasks to complete by a date
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Goal {
+    pub name: String,
+    /// Only tasks with this tag count towards the goal, any task when unset
+    #[serde(default)]
+    pub tag: Option<String>,
+    pub target: u32,
+    #[serde(default)]
+    pub progress: u32,
+    pub by: DateTime<Utc>,
+    pub created: DateTime<Utc>,
+}
+
+impl Goal {
+    pub fn counts(&self, task: &Task) -> bool {
+        match self.tag {
+            Some(ref tag) => task.tags.contains(tag),
+            None => task.is_task,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        Utc::now() <= self.by
+    }
+
+    pub fn is_met(&self) -> bool {
+        self.progress >= self.target
+    }
+
+    /// e.g. `[███░░░░░░░] 3/10`
+    pub fn progress_bar(&self, width: usize) -> String {
+        let filled = match self.target {
+            0 => width,
+            target => (width * self.progress.min(target) as usize) / target as usize,
+        };
+        format!(
+            "[{}{}] {}/{}",
+            "█".repeat(filled),
+            "░".repeat(width - filled),
+            self.progress,
+            self.target
+        )
+    }
 }
 
 impl Task {
     pub fn new(
         name: &str,
-        tags: &str,
+        tags: &[String],
         is_task: bool,
         id: i64,
-        priority: PriorityLevel,
+        priority: &str,
         timestamp: u128,
     ) -> Self {
         Self {
             id,
             name: name.trim().replace("  ", ""),
-            tags: tags.to_string(),
+            tags: tags.to_vec(),
             is_task,
             is_done: false,
             timestamp: timestamp.to_string(),
             in_progress: false,
             priority: priority.to_string(),
+            attachments: Vec::new(),
+            owner: None,
+            checked_by: None,
+            time_spent: 0,
+            timer_started: None,
+            snoozed_until: None,
+            pinned: false,
+            split_from: None,
+            split_into: Vec::new(),
+            history: Vec::new(),
+            expires_at: None,
+            expired: false,
+            cancelled: false,
+            cancel_reason: None,
+            waiting_since: None,
+            assignee: None,
+            external_ref: None,
+            body: None,
+            completed_at: None,
+            due: None,
+            recur: None,
+            recurred: false,
+            depends_on: Vec::new(),
+            estimate: None,
+            project: None,
+            scheduled: None,
+            annotations: Vec::new(),
         }
     }
-    pub fn priority_score(&self) -> u8 {
-        match self.priority.as_str() {
-            "normal" => 1,
-            "low" => 0,
-            "high" => 2,
-            _ => unreachable!(),
-        }
+    pub fn priority_score(&self, config: &Config) -> usize {
+        config.priority_score(&self.priority)
     }
 
     pub fn timestamp(&self) -> u128 {
         self.timestamp.parse().unwrap()
     }
+
+    /// When the entry was added
+    pub fn created(&self) -> DateTime<Utc> {
+        let nanos = u64::try_from(self.timestamp()).unwrap_or(u64::MAX);
+        DateTime::from(UNIX_EPOCH + std::time::Duration::from_nanos(nanos))
+    }
+
+    pub fn record(&mut self, event: impl Into<String>) {
+        self.history.push(HistoryEntry {
+            at: Utc::now(),
+            event: event.into(),
+        });
+    }
+
+    /// A fresh open copy with the same name, tags, priority, attachments and description
+    pub fn duplicate(&self, id: i64, timestamp: u128) -> Self {
+        let mut copy = Task::new(
+            &self.name,
+            &self.tags,
+            self.is_task,
+            id,
+            &self.priority,
+            timestamp,
+        );
+        copy.attachments = self.attachments.clone();
+        copy.body = self.body.clone();
+        copy.owner = self.owner.clone();
+        copy.record(format!("duplicated from {}", self.id));
+        copy
+    }
+
+    /// Points the ids this entry refers to at their numbers in `ids`, dropping the ones
+    /// it lacks
+    pub fn renumber_references(&mut self, ids: &HashMap<i64, i64>) {
+        self.split_from = self.split_from.and_then(|id| ids.get(&id).copied());
+        self.split_into = self
+            .split_into
+            .iter()
+            .filter_map(|id| ids.get(id).copied())
+            .collect();
+        self.depends_on = self
+            .depends_on
+            .iter()
+            .filter_map(|id| ids.get(id).copied())
+            .collect();
+    }
+
+    /// The next occurrence of a completed recurring task, due one interval after this one
+    /// was due (or completed), skipping occurrences already in the past
+    pub fn next_occurrence(&self, id: i64, timestamp: u128) -> Option<Task> {
+        if !self.is_done || self.recurred {
+            return None;
+        }
+        let every = crate::dates::parse_offset(self.recur.as_deref()?).ok()?;
+        if every <= chrono::Duration::zero() {
+            return None;
+        }
+        let mut due = match self.due {
+            Some(due) => due + every,
+            None => self.completed_at.unwrap_or_else(Utc::now) + every,
+        };
+        while due < Utc::now() {
+            due += every;
+        }
+        let mut next = self.duplicate(id, timestamp);
+        next.recur = self.recur.clone();
+        next.estimate = self.estimate;
+        next.due = Some(due);
+        next.history.clear();
+        next.record(format!("recurs from {}", self.id));
+        Some(next)
+    }
+
+    /// Folds `other` into this task: tags, attachments and tracked time are combined
+    /// and the earliest creation time is kept
+    pub fn absorb(&mut self, other: Task) {
+        if other.timestamp() < self.timestamp() {
+            self.timestamp = other.timestamp.to_owned();
+        }
+        for tag in other.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        for attachment in other.attachments {
+            if !self.attachments.contains(&attachment) {
+                self.attachments.push(attachment);
+            }
+        }
+        self.time_spent += other.time_spent;
+        self.in_progress |= other.in_progress;
+        self.is_done = self.is_done && other.is_done && !self.in_progress;
+        self.pinned |= other.pinned;
+        self.history.extend(other.history);
+        self.annotations.extend(other.annotations);
+        self.annotations.sort_by_key(|f| f.at);
+        self.record(format!("merged {}. {}", other.id, other.name));
+    }
+
+    /// Moves an open entry past its expiry date to the expired state, returns whether it did
+    pub fn expire_if_due(&mut self) -> bool {
+        match self.expires_at {
+            Some(at) if !self.expired && !self.is_done && at <= Utc::now() => {
+                self.expired = true;
+                self.in_progress = false;
+                self.stop_timer();
+                self.record("expired");
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn cancel(&mut self, reason: Option<&str>) {
+        self.cancelled = true;
+        self.in_progress = false;
+        self.stop_timer();
+        self.cancel_reason = reason.map(str::to_owned);
+        match reason {
+            Some(reason) => self.record(format!("cancelled: {reason}")),
+            None => self.record("cancelled"),
+        }
+    }
+
+    pub fn complete(&mut self) {
+        self.is_done = true;
+        self.completed_at = Some(Utc::now());
+        self.record("completed");
+    }
+
+    pub fn is_waiting(&self) -> bool {
+        self.waiting_since.is_some()
+    }
+
+    /// When the task was last changed, or added if it never was
+    pub fn last_activity(&self) -> DateTime<Utc> {
+        self.history
+            .iter()
+            .map(|f| f.at)
+            .chain(std::iter::once(self.created()))
+            .max()
+            .unwrap_or_else(|| self.created())
+    }
+
+    /// When the task stopped being open: completed, cancelled or expired
+    pub fn closed_at(&self) -> Option<DateTime<Utc>> {
+        match self.completed_at {
+            Some(at) => Some(at),
+            None if self.is_done || self.cancelled || self.expired => Some(self.last_activity()),
+            None => None,
+        }
+    }
+
+    /// How far along the entry is: 0 open, 1 started, 2 finished in any way
+    pub fn progress(&self) -> u8 {
+        if self.is_done || self.cancelled || self.expired {
+            2
+        } else if self.in_progress {
+            1
+        } else {
+            0
+        }
+    }
+
+    /// Unfinished tasks among `tasks` that this one depends on
+    pub fn blockers<'a>(&self, tasks: &'a [Task]) -> Vec<&'a Task> {
+        tasks
+            .iter()
+            .filter(|f| self.depends_on.contains(&f.id) && f.progress() < 2)
+            .collect()
+    }
+
+    /// How strongly `utd next` suggests the task: its priority, age and closeness to its due
+    /// date, each from 0 to 1, and whether it is held up, weighed by the `[next]` config
+    pub fn next_score(&self, tasks: &[Task], config: &Config) -> f64 {
+        let weights = config.next.as_ref().cloned().unwrap_or_default();
+        let now = Utc::now();
+        let levels = config.priority_levels().len().max(1) as f64;
+        let priority = self.priority_score(config) as f64 / levels;
+        let age = ((now - self.created()).num_hours() as f64 / (28.0 * 24.0)).clamp(0.0, 1.0);
+        let due = match self.due {
+            Some(due) => (1.0 - (due - now).num_hours() as f64 / (7.0 * 24.0)).clamp(0.0, 1.0),
+            None => 0.0,
+        };
+        let blocked = if self.is_waiting() || !self.blockers(tasks).is_empty() {
+            1.0
+        } else {
+            0.0
+        };
+        weights.priority() * priority
+            + weights.age() * age
+            + weights.due() * due
+            + weights.blocked() * blocked
+    }
+
+    /// Open and past its due date
+    pub fn is_overdue(&self) -> bool {
+        !self.is_done && matches!(self.due, Some(due) if due < Utc::now())
+    }
+
+    /// Open and due later today
+    pub fn is_due_today(&self) -> bool {
+        !self.is_done
+            && !self.is_overdue()
+            && matches!(self.due, Some(due) if crate::dates::to_local(&due).date_naive() == crate::dates::today())
+    }
+
+    /// An open task nobody has touched for `threshold`, snoozed ones aside
+    pub fn is_stale(&self, threshold: chrono::Duration) -> bool {
+        self.is_task
+            && !self.is_done
+            && !self.expired
+            && !self.is_snoozed()
+            && Utc::now() - self.last_activity() >= threshold
+    }
+
+    /// Hidden from the board until the snooze date passes
+    pub fn is_snoozed(&self) -> bool {
+        matches!(self.snoozed_until, Some(until) if until > Utc::now())
+    }
+
+    pub fn start_timer(&mut self) {
+        if self.timer_started.is_none() {
+            self.timer_started = Some(unix_time());
+        }
+    }
+
+    /// Stops the running timer, returning the seconds it ran for
+    pub fn stop_timer(&mut self) -> Option<u64> {
+        let started = self.timer_started.take()?;
+        let seconds = unix_time().saturating_sub(started);
+        self.time_spent += seconds;
+        Some(seconds)
+    }
+
+    /// Items of the description written as `- [ ] item` or `- [x] item`, with whether
+    /// each is ticked
+    pub fn checklist(&self) -> Vec<(bool, String)> {
+        let body = match self.body {
+            Some(ref body) => body,
+            None => return Vec::new(),
+        };
+        body.lines()
+            .filter_map(|line| {
+                let item = line.trim_start().strip_prefix("- [")?;
+                match item.split_once("] ")? {
+                    (" ", text) => Some((false, text.trim().to_owned())),
+                    ("x" | "X", text) => Some((true, text.trim().to_owned())),
+                    _ => None,
+                }
+            })
+            .collect()
+    }
+
+    /// Estimated seconds still to go on an open task, less the time tracked on it
+    pub fn remaining(&self) -> Option<u64> {
+        match self.estimate {
+            Some(estimate) if !self.is_done => Some(estimate.saturating_sub(self.tracked())),
+            _ => None,
+        }
+    }
+
+    /// Tracked seconds, including the running timer
+    pub fn tracked(&self) -> u64 {
+        match self.timer_started {
+            Some(started) => self.time_spent + unix_time().saturating_sub(started),
+            None => self.time_spent,
+        }
+    }
+}
+
+/// Older state files store tags as one string such as `"@work @home"`
+fn tags_from_text_or_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StoredTags {
+        Text(String),
+        List(Vec<String>),
+    }
+    Ok(match StoredTags::deserialize(deserializer)? {
+        StoredTags::Text(text) => {
+            let mut tags = Vec::new();
+            // written with `@` before the prefix could be changed
+            for word in text.split_whitespace() {
+                push_tag(&mut tags, word, '@');
+            }
+            tags
+        }
+        StoredTags::List(list) => list,
+    })
+}
+
+/// Adds `tag` without its prefix, unless that leaves nothing or `tags` has it already
+pub fn push_tag(tags: &mut Vec<String>, tag: &str, prefix: char) {
+    let tag = tag.trim().trim_start_matches(prefix).trim();
+    if !tag.is_empty() && !tags.iter().any(|f| f == tag) {
+        tags.push(tag.to_owned());
+    }
+}
+
+/// Numbers the tasks 1, 2, 3… in board order, carrying the references between them
+/// along. References to tasks no longer on the board are dropped.
+pub fn resequence(tasks: &mut [Task]) {
+    let ids: HashMap<i64, i64> = tasks
+        .iter()
+        .enumerate()
+        .map(|(index, f)| (f.id, index as i64 + 1))
+        .collect();
+    for (index, task) in tasks.iter_mut().enumerate() {
+        task.id = index as i64 + 1;
+        task.renumber_references(&ids);
+    }
+}
+
+/// Whether the task `from` depends on `to`, directly or through other tasks
+pub fn depends_on(tasks: &[Task], from: i64, to: i64) -> bool {
+    let mut pending = vec![from];
+    let mut seen = Vec::new();
+    while let Some(id) = pending.pop() {
+        if id == to {
+            return true;
+        }
+        if seen.contains(&id) {
+            continue;
+        }
+        seen.push(id);
+        if let Some(task) = tasks.iter().find(|f| f.id == id) {
+            pending.extend(task.depends_on.iter().copied());
+        }
+    }
+    false
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskEvent {
+    Added,
+    Completed,
+    Started,
+}
+
+impl TaskEvent {
+    pub fn name(self) -> &'static str {
+        match self {
+            TaskEvent::Added => "added",
+            TaskEvent::Completed => "completed",
+            TaskEvent::Started => "started",
+        }
+    }
+}
+
+/// Entries added, completed or started between two versions of a board
+pub fn task_events<'a>(before: &[Task], after: &'a [Task]) -> Vec<(TaskEvent, &'a Task)> {
+    let mut events = Vec::new();
+    for task in after.iter() {
+        // timestamps identify entries across id changes
+        match before.iter().find(|f| f.timestamp == task.timestamp) {
+            None => events.push((TaskEvent::Added, task)),
+            Some(old) if task.is_done && !old.is_done => events.push((TaskEvent::Completed, task)),
+            Some(old) if task.in_progress && !old.in_progress => {
+                events.push((TaskEvent::Started, task))
+            }
+            Some(_) => {}
+        }
+    }
+    events
+}
+
+/// What [`merge_boards`] changed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MergeReport {
+    /// Entries only on the other board
+    pub added: usize,
+    /// Entries on both boards that were further along on the other one
+    pub updated: usize,
+    /// Added entries whose id was taken and were given a new one
+    pub renumbered: usize,
+}
+
+/// One line of a JSON Lines board: an entry as it now is, or the removal of one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+#[allow(clippy::large_enum_variant)]
+pub enum Record {
+    Put { task: Task },
+    Delete { timestamp: String },
+}
+
+/// The board left by applying `records` in order. Entries stay where they were first put.
+pub fn replay(records: impl IntoIterator<Item = Record>) -> Tasks {
+    let mut slots: Vec<Option<Task>> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for record in records {
+        match record {
+            Record::Put { task } => match positions.get(&task.timestamp) {
+                Some(&position) => slots[position] = Some(task),
+                None => {
+                    positions.insert(task.timestamp.to_owned(), slots.len());
+                    slots.push(Some(task));
+                }
+            },
+            Record::Delete { timestamp } => {
+                if let Some(position) = positions.remove(&timestamp) {
+                    slots[position] = None;
+                }
+            }
+        }
+    }
+    slots.into_iter().flatten().collect()
+}
+
+/// Records turning `before` into `after`, or `None` when entries were reordered,
+/// which records can't express
+pub fn record_changes(before: &[Task], after: &[Task]) -> Option<Vec<Record>> {
+    let old: HashMap<&str, &Task> = before.iter().map(|f| (f.timestamp.as_str(), f)).collect();
+    let new: HashMap<&str, &Task> = after.iter().map(|f| (f.timestamp.as_str(), f)).collect();
+    // kept entries must keep their order, and new ones can only go after them
+    let kept_before = before
+        .iter()
+        .filter(|f| new.contains_key(f.timestamp.as_str()));
+    let kept_after = after
+        .iter()
+        .filter(|f| old.contains_key(f.timestamp.as_str()));
+    if !kept_before
+        .map(|f| &f.timestamp)
+        .eq(kept_after.map(|f| &f.timestamp))
+    {
+        return None;
+    }
+    let first_new = after
+        .iter()
+        .position(|f| !old.contains_key(f.timestamp.as_str()))
+        .unwrap_or(after.len());
+    if after[first_new..]
+        .iter()
+        .any(|f| old.contains_key(f.timestamp.as_str()))
+    {
+        return None;
+    }
+    let mut records: Vec<Record> = before
+        .iter()
+        .filter(|f| !new.contains_key(f.timestamp.as_str()))
+        .map(|f| Record::Delete {
+            timestamp: f.timestamp.to_owned(),
+        })
+        .collect();
+    for task in after {
+        let changed = match old.get(task.timestamp.as_str()) {
+            Some(previous) => {
+                serde_json::to_value(previous).ok() != serde_json::to_value(task).ok()
+            }
+            None => true,
+        };
+        if changed {
+            records.push(Record::Put { task: task.clone() });
+        }
+    }
+    Some(records)
+}
+
+/// Combines `other`, e.g. the board from another machine, into `tasks`.
+///
+/// Entries created at the same instant are copies of one entry and are kept once,
+/// as whichever copy is further along (done beats started beats open, then the most
+/// recently changed), with the history and annotations of both.
+pub fn merge_boards(tasks: &mut Tasks, other: Tasks) -> MergeReport {
+    let mut report = MergeReport::default();
+    let mut next_id = tasks.iter().map(|f| f.id).max().unwrap_or(0);
+    let mut taken: HashSet<i64> = tasks.iter().map(|f| f.id).collect();
+    // the id each entry of `other` ends up with, for the references between them
+    let mut ids = HashMap::new();
+    let mut numbers = Vec::new();
+    for entry in other.iter() {
+        let id = match tasks.iter().find(|f| f.timestamp == entry.timestamp) {
+            Some(existing) => existing.id,
+            None if taken.contains(&entry.id) => {
+                next_id += 1;
+                report.renumbered += 1;
+                next_id
+            }
+            None => entry.id,
+        };
+        next_id = next_id.max(id);
+        taken.insert(id);
+        ids.insert(entry.id, id);
+        numbers.push(id);
+    }
+    for (mut entry, id) in other.into_iter().zip(numbers) {
+        entry.id = id;
+        entry.renumber_references(&ids);
+        match tasks.iter_mut().find(|f| f.timestamp == entry.timestamp) {
+            Some(existing) => {
+                let ahead = (entry.progress(), entry.last_activity())
+                    > (existing.progress(), existing.last_activity());
+                let mut history = existing.history.clone();
+                for event in entry.history.iter() {
+                    if !history
+                        .iter()
+                        .any(|f| f.at == event.at && f.event == event.event)
+                    {
+                        history.push(event.clone());
+                    }
+                }
+                history.sort_by_key(|f| f.at);
+                let mut annotations = existing.annotations.clone();
+                for annotation in entry.annotations.iter() {
+                    if !annotations.contains(annotation) {
+                        annotations.push(annotation.clone());
+                    }
+                }
+                annotations.sort_by_key(|f| f.at);
+                if ahead {
+                    *existing = entry;
+                    report.updated += 1;
+                }
+                existing.history = history;
+                existing.annotations = annotations;
+            }
+            None => {
+                tasks.push(entry);
+                report.added += 1;
+            }
+        }
+    }
+    report
+}
+
+/// Splits entry text into its title and tags.
+///
+/// A tag starts with `prefix` at the beginning of a word and is either a single word or
+/// quoted (`@"deep work"`). `\@` keeps a literal prefix in the title.
+pub fn parse_entry(text: &str, prefix: char) -> (String, Vec<String>) {
+    let mut title = String::new();
+    let mut tags: Vec<String> = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut word_start = true;
+    while let Some(c) = chars.next() {
+        if c == '\\' && chars.peek() == Some(&prefix) {
+            chars.next();
+            title.push(prefix);
+            word_start = false;
+            continue;
+        }
+        if c == prefix && word_start {
+            let mut tag = String::new();
+            if chars.peek() == Some(&'"') {
+                chars.next();
+                for c in chars.by_ref() {
+                    if c == '"' {
+                        break;
+                    }
+                    tag.push(c);
+                }
+            } else {
+                while let Some(&c) = chars.peek() {
+                    if !(c.is_alphanumeric() || matches!(c, '_' | '-' | '/')) {
+                        break;
+                    }
+                    tag.push(c);
+                    chars.next();
+                }
+            }
+            let tag = tag.trim();
+            if tag.is_empty() {
+                title.push(prefix);
+                word_start = false;
+            } else if !tags.iter().any(|f| f == tag) {
+                tags.push(tag.to_owned());
+            }
+            continue;
+        }
+        word_start = c.is_whitespace();
+        title.push(c);
+    }
+    (title.split_whitespace().collect::<Vec<_>>().join(" "), tags)
+}
+
+/// Takes a `!level` word naming one of `levels` out of `title`, e.g. `fix outage !high`.
+/// The last one wins; other words starting with `!` stay in the title.
+pub fn parse_priority(title: &str, levels: &[String]) -> (String, Option<String>) {
+    let mut priority = None;
+    let words: Vec<&str> = title
+        .split_whitespace()
+        .filter(|word| {
+            let level = word
+                .strip_prefix('!')
+                .and_then(|f| levels.iter().find(|level| level.eq_ignore_ascii_case(f)));
+            match level {
+                Some(level) => {
+                    priority = Some(level.to_owned());
+                    false
+                }
+                None => true,
+            }
+        })
+        .collect();
+    (words.join(" "), priority)
+}
+
+/// A block of the day given to one task by [`plan_day`]
+#[derive(Debug, Clone, Copy)]
+pub struct TimeBlock<'a> {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub task: &'a Task,
+}
+
+/// Lays the open tasks that aren't held up into blocks between `start` and `end`, the
+/// ones started already first and then by how strongly `utd next` suggests them. Tasks
+/// take their remaining estimate, or `fallback` without one. Returns the blocks and the
+/// tasks that didn't fit.
+pub fn plan_day<'a>(
+    tasks: &'a [Task],
+    config: &Config,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    fallback: chrono::Duration,
+) -> (Vec<TimeBlock<'a>>, Vec<&'a Task>) {
+    let mut candidates: Vec<(&Task, f64)> = tasks
+        .iter()
+        .filter(|f| f.is_task && !f.is_done && !f.expired && !f.is_snoozed())
+        .filter(|f| !f.is_waiting() && f.blockers(tasks).is_empty())
+        .map(|f| (f, f.next_score(tasks, config)))
+        .collect();
+    candidates.sort_by(|a, b| {
+        b.0.in_progress
+            .cmp(&a.0.in_progress)
+            .then(b.1.total_cmp(&a.1))
+    });
+    let mut blocks = Vec::new();
+    let mut left_over = Vec::new();
+    let mut at = start;
+    for (task, _) in candidates {
+        let length = match task.remaining() {
+            Some(0) | None => fallback,
+            Some(seconds) => chrono::Duration::seconds(seconds as i64),
+        };
+        if at + length > end {
+            left_over.push(task);
+            continue;
+        }
+        blocks.push(TimeBlock {
+            start: at,
+            end: at + length,
+            task,
+        });
+        at += length;
+    }
+    (blocks, left_over)
+}
+
+/// Takes a `+project` word out of `title`, e.g. `draft changelog +release-2`. The last one
+/// wins; a lone `+` stays in the title.
+pub fn parse_project(title: &str) -> (String, Option<String>) {
+    let mut project = None;
+    let words: Vec<&str> = title
+        .split_whitespace()
+        .filter(|word| {
+            let name = word.strip_prefix('+').filter(|name| {
+                !name.is_empty()
+                    && name
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '/' | '.'))
+            });
+            match name {
+                Some(name) => {
+                    project = Some(name.to_owned());
+                    false
+                }
+                None => true,
+            }
+        })
+        .collect();
+    (words.join(" "), project)
+}
+
+/// Lowercased name with runs of whitespace collapsed, used to spot duplicates
+pub fn normalise_name(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Levenshtein distance between `a` and `b`, counted in characters
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+/// The existing tag closest to `tag` when it looks like a typo of it
+pub fn similar_tag<'a>(tag: &str, existing: &'a [String]) -> Option<&'a str> {
+    if existing.iter().any(|f| f == tag) {
+        return None;
+    }
+    let allowed = if tag.chars().count() <= 4 { 1 } else { 2 };
+    existing
+        .iter()
+        .map(|f| (f, edit_distance(&tag.to_lowercase(), &f.to_lowercase())))
+        .filter(|(_, distance)| *distance <= allowed)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(f, _)| f.as_str())
+}
+
+/// FNV-1a hash of `bytes` in hex, which unlike `DefaultHasher` is the same across builds
+pub fn checksum(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{hash:016x}")
+}
+
+/// The entries of a damaged board file that still parse, e.g. all but the last of
+/// a truncated one
+pub fn salvage_entries(contents: &str) -> Tasks {
+    // objects directly inside the top level array, or at the top level without one
+    let base = i32::from(contents.trim_start().starts_with('['));
+    let mut entries = Vec::new();
+    let (mut depth, mut start) = (0, None);
+    let (mut in_string, mut escaped) = (false, false);
+    for (index, c) in contents.char_indices() {
+        if in_string {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match c {
+            '"' => in_string = true,
+            '[' | '{' => {
+                if c == '{' && depth == base {
+                    start = Some(index);
+                }
+                depth += 1;
+            }
+            ']' | '}' => {
+                depth -= 1;
+                if c == '}' && depth == base {
+                    if let Some(from) = start.take() {
+                        if let Ok(task) = serde_json::from_str::<Task>(&contents[from..=index]) {
+                            entries.push(task);
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    entries
+}
+
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time is going backwards")
+        .as_secs()
+}
+
+/// Formats an age in its largest whole unit, e.g. `45m`, `3d` or `2w`
+pub fn format_age(seconds: u64) -> String {
+    match seconds {
+        s if s < 3600 => format!("{}m", s / 60),
+        s if s < 86400 => format!("{}h", s / 3600),
+        s if s < 7 * 86400 => format!("{}d", s / 86400),
+        s if s < 365 * 86400 => format!("{}w", s / (7 * 86400)),
+        s => format!("{}y", s / (365 * 86400)),
+    }
+}
+
+/// Formats seconds as e.g. `2h 5m`
+pub fn format_duration(seconds: u64) -> String {
+    let (hours, minutes) = (seconds / 3600, (seconds % 3600) / 60);
+    match (hours, minutes) {
+        (0, 0) => format!("{}s", seconds),
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_entry_splits_title_and_tags() {
+        let (title, tags) = parse_entry("buy milk @home @\"corner shop\"", '@');
+        assert_eq!(title, "buy milk");
+        assert_eq!(tags, ["home", "corner shop"]);
+    }
+
+    #[test]
+    fn parse_entry_keeps_prefix_inside_words_and_escaped() {
+        let (title, tags) = parse_entry("mail bob@example.com", '@');
+        assert_eq!(title, "mail bob@example.com");
+        assert!(tags.is_empty());
+        let (title, tags) = parse_entry("\\@home is not a tag", '@');
+        assert_eq!(title, "@home is not a tag");
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn parse_entry_keeps_a_bare_prefix_in_the_title() {
+        let (title, tags) = parse_entry("a @ b", '@');
+        assert_eq!(title, "a @ b");
+        assert!(tags.is_empty());
+    }
+
+    #[test]
+    fn parse_entry_drops_repeated_tags() {
+        let (title, tags) = parse_entry("x @a @a", '@');
+        assert_eq!(title, "x");
+        assert_eq!(tags, ["a"]);
+    }
+
+    #[test]
+    fn parse_entry_takes_an_unterminated_quote_to_the_end() {
+        let (title, tags) = parse_entry("x @\"deep work", '@');
+        assert_eq!(title, "x");
+        assert_eq!(tags, ["deep work"]);
+    }
+
+    #[test]
+    fn parse_entry_uses_the_configured_prefix() {
+        let (title, tags) = parse_entry("x #home @work", '#');
+        assert_eq!(title, "x @work");
+        assert_eq!(tags, ["home"]);
+    }
+
+    #[test]
+    fn push_tag_strips_the_prefix_and_skips_empty_and_repeated_tags() {
+        let mut tags = Vec::new();
+        push_tag(&mut tags, " @@work ", '@');
+        push_tag(&mut tags, "work", '@');
+        push_tag(&mut tags, "@", '@');
+        assert_eq!(tags, ["work"]);
+    }
+
+    #[test]
+    fn old_tag_text_is_split_and_normalised() {
+        #[derive(Deserialize)]
+        struct Stored {
+            #[serde(deserialize_with = "tags_from_text_or_list")]
+            tags: Vec<String>,
+        }
+        let stored: Stored = serde_json::from_str(r#"{"tags": "@work  @home @work @"}"#).unwrap();
+        assert_eq!(stored.tags, ["work", "home"]);
+        let stored: Stored = serde_json::from_str(r#"{"tags": ["deep work"]}"#).unwrap();
+        assert_eq!(stored.tags, ["deep work"]);
+    }
+
+    fn entry(timestamp: &str, name: &str) -> Task {
+        Task {
+            name: name.to_owned(),
+            timestamp: timestamp.to_owned(),
+            ..Task::default()
+        }
+    }
+
+    #[test]
+    fn merge_boards_carries_references_to_renumbered_entries() {
+        let mut tasks = vec![Task {
+            id: 1,
+            ..entry("1", "here")
+        }];
+        let other = vec![
+            Task {
+                id: 1,
+                ..entry("2", "there")
+            },
+            Task {
+                id: 2,
+                depends_on: vec![1],
+                split_from: Some(1),
+                ..entry("3", "after there")
+            },
+        ];
+        let report = merge_boards(&mut tasks, other);
+        assert_eq!(report.added, 2);
+        assert_eq!(report.renumbered, 2);
+        let ids: Vec<i64> = tasks.iter().map(|f| f.id).collect();
+        assert_eq!(ids, [1, 2, 3]);
+        assert_eq!(tasks[2].depends_on, [2]);
+        assert_eq!(tasks[2].split_from, Some(2));
+    }
+
+    fn names(tasks: &[Task]) -> Vec<&str> {
+        tasks.iter().map(|f| f.name.as_str()).collect()
+    }
+
+    #[test]
+    fn replay_keeps_entries_where_they_were_first_put() {
+        let board = replay([
+            Record::Put {
+                task: entry("1", "a"),
+            },
+            Record::Put {
+                task: entry("2", "b"),
+            },
+            Record::Put {
+                task: entry("1", "a again"),
+            },
+        ]);
+        assert_eq!(names(&board), ["a again", "b"]);
+    }
+
+    #[test]
+    fn replay_drops_deleted_entries_and_ignores_unknown_deletes() {
+        let board = replay([
+            Record::Put {
+                task: entry("1", "a"),
+            },
+            Record::Delete {
+                timestamp: "1".to_owned(),
+            },
+            Record::Delete {
+                timestamp: "9".to_owned(),
+            },
+            Record::Put {
+                task: entry("2", "b"),
+            },
+        ]);
+        assert_eq!(names(&board), ["b"]);
+    }
+
+    #[test]
+    fn record_changes_puts_only_changed_and_new_entries() {
+        let before = [entry("1", "a"), entry("2", "b"), entry("3", "c")];
+        let after = [entry("1", "a"), entry("3", "c!"), entry("4", "d")];
+        let records = record_changes(&before, &after).unwrap();
+        assert!(matches!(&records[0], Record::Delete { timestamp } if timestamp == "2"));
+        assert!(matches!(&records[1], Record::Put { task } if task.name == "c!"));
+        assert!(matches!(&records[2], Record::Put { task } if task.name == "d"));
+        assert_eq!(records.len(), 3);
+        let replayed = replay(
+            before
+                .iter()
+                .map(|f| Record::Put { task: f.clone() })
+                .chain(records),
+        );
+        assert_eq!(names(&replayed), names(&after));
+    }
+
+    #[test]
+    fn record_changes_gives_up_on_reordered_entries() {
+        let before = [entry("1", "a"), entry("2", "b")];
+        assert!(record_changes(&before, &[entry("2", "b"), entry("1", "a")]).is_none());
+        assert!(record_changes(
+            &before,
+            &[entry("1", "a"), entry("3", "c"), entry("2", "b")]
+        )
+        .is_none());
+    }
+
+    #[test]
+    fn merged_tasks_replay_as_one_entry() {
+        let before = vec![entry("2", "a"), entry("1", "b"), entry("3", "c")];
+        let mut after = before.clone();
+        let other = after.remove(1);
+        after[0].absorb(other);
+        assert_eq!(after[0].timestamp, "1");
+        let records = record_changes(&before, &after).unwrap();
+        let replayed = replay(
+            before
+                .iter()
+                .map(|f| Record::Put { task: f.clone() })
+                .chain(records),
+        );
+        assert_eq!(replayed.len(), 2);
+        assert_eq!(names(&replayed), names(&after));
+    }
+
+    #[test]
+    fn salvage_entries_keeps_whole_entries_of_a_truncated_board() {
+        let board =
+            serde_json::to_string_pretty(&vec![entry("1", "a {"), entry("2", "b")]).unwrap();
+        let cut = board.rfind("\"b\"").unwrap();
+        assert_eq!(names(&salvage_entries(&board[..cut])), ["a {"]);
+        assert_eq!(names(&salvage_entries(&board)), ["a {", "b"]);
+    }
+
+    #[test]
+    fn salvage_entries_skips_entries_that_no_longer_parse() {
+        let good = serde_json::to_string(&entry("1", "a")).unwrap();
+        let contents = format!("{good}\n{{\"name\": 3}}\n{good}");
+        assert_eq!(names(&salvage_entries(&contents)), ["a", "a"]);
+    }
+
+    #[test]
+    fn parse_priority_takes_a_known_level_from_anywhere_in_the_title() {
+        let levels = ["high".to_owned(), "low".to_owned()];
+        assert_eq!(
+            parse_priority("call  !HIGH the bank", &levels),
+            ("call the bank".to_owned(), Some("high".to_owned()))
+        );
+        assert_eq!(
+            parse_priority("wow! !urgent", &levels),
+            ("wow! !urgent".to_owned(), None)
+        );
+    }
+
+    #[test]
+    fn parse_priority_keeps_the_last_level_given() {
+        let levels = ["high".to_owned(), "low".to_owned()];
+        assert_eq!(
+            parse_priority("!low x !high", &levels),
+            ("x".to_owned(), Some("high".to_owned()))
+        );
+    }
 }
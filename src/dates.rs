@@ -0,0 +1,212 @@
+use std::sync::OnceLock;
+
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, Local, NaiveDate, Offset, TimeZone, Timelike, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+static ZONE: OnceLock<Tz> = OnceLock::new();
+
+/// Shows and reads dates in `name`, e.g. `Europe/Berlin`, instead of the system timezone.
+/// Has no effect once a zone was set.
+pub fn set_timezone(name: &str) -> Result<()> {
+    let zone: Tz = name
+        .parse()
+        .map_err(|_| format!("unknown timezone {name:?}, expected a name such as Europe/Berlin"))?;
+    let _ = ZONE.set(zone);
+    Ok(())
+}
+
+/// `date` in the configured timezone, or the system one
+pub fn to_local(date: &DateTime<Utc>) -> DateTime<FixedOffset> {
+    match ZONE.get() {
+        Some(zone) => {
+            let local = date.with_timezone(zone);
+            local.with_timezone(&local.offset().fix())
+        }
+        None => {
+            let local = date.with_timezone(&Local);
+            local.with_timezone(&local.offset().fix())
+        }
+    }
+}
+
+/// The current day in the configured timezone
+pub fn today() -> NaiveDate {
+    to_local(&Utc::now()).date_naive()
+}
+
+/// Parses a human date such as `today`, `tomorrow`, `monday`, `2022-04-01`, `3d` or `2w`.
+///
+/// Days resolve to local midnight, relative offsets are counted from now.
+pub fn parse_when(input: &str) -> Result<DateTime<Utc>> {
+    let input = input.trim().to_lowercase();
+    let today = today();
+    let day = match input.as_str() {
+        "now" => return Ok(Utc::now()),
+        "today" => today,
+        "tomorrow" => today + Duration::days(1),
+        "yesterday" => today - Duration::days(1),
+        "week" | "next-week" => today + Duration::days(7),
+        _ => {
+            if let Ok(weekday) = input.parse::<Weekday>() {
+                next_weekday(today, weekday)
+            } else if let Ok(date) = NaiveDate::parse_from_str(&input, "%Y-%m-%d") {
+                date
+            } else {
+                return Ok(Utc::now() + parse_offset(&input)?);
+            }
+        }
+    };
+    local_midnight(day)
+}
+
+/// Like [`parse_when`], but a day means the end of that day
+pub fn parse_deadline(input: &str) -> Result<DateTime<Utc>> {
+    let when = parse_when(input)?;
+    if to_local(&when).num_seconds_from_midnight() == 0 {
+        Ok(when + Duration::days(1) - Duration::seconds(1))
+    } else {
+        Ok(when)
+    }
+}
+
+/// Parses offsets such as `30m`, `12h`, `3d` or `2w`
+pub fn parse_offset(input: &str) -> Result<Duration> {
+    let input = input.trim();
+    let unit = input
+        .chars()
+        .last()
+        .ok_or_else(|| String::from("empty duration"))?;
+    let amount: i64 = input[..input.len() - unit.len_utf8()]
+        .parse()
+        .map_err(|_| format!("unrecognised date or duration: {input}"))?;
+    match unit {
+        'm' => Ok(Duration::minutes(amount)),
+        'h' => Ok(Duration::hours(amount)),
+        'd' => Ok(Duration::days(amount)),
+        'w' => Ok(Duration::weeks(amount)),
+        _ => Err(format!("unrecognised date or duration: {input}").into()),
+    }
+}
+
+/// Parses working hours such as `9-17` or `8:30-16:30` into minutes after midnight
+pub fn parse_hours(input: &str) -> Result<(u32, u32)> {
+    let invalid = || format!("expected hours such as 9-17 or 8:30-16:30, found {input}");
+    let minutes = |time: &str| -> Option<u32> {
+        let (hours, minutes) = time.trim().split_once(':').unwrap_or((time.trim(), "0"));
+        let (hours, minutes): (u32, u32) = (hours.parse().ok()?, minutes.parse().ok()?);
+        (hours <= 24 && minutes < 60).then_some(hours * 60 + minutes)
+    };
+    let (from, to) = input.split_once('-').ok_or_else(invalid)?;
+    match (minutes(from), minutes(to)) {
+        (Some(from), Some(to)) if from < to && to <= 24 * 60 => Ok((from, to)),
+        _ => Err(invalid().into()),
+    }
+}
+
+/// The next `weekday` strictly after `from`
+fn next_weekday(from: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let current = from.weekday().num_days_from_monday() as i64;
+    let target = weekday.num_days_from_monday() as i64;
+    let mut days = (target - current).rem_euclid(7);
+    if days == 0 {
+        days = 7;
+    }
+    from + Duration::days(days)
+}
+
+/// The start of `day` in the configured timezone
+pub fn local_midnight(day: NaiveDate) -> Result<DateTime<Utc>> {
+    match ZONE.get() {
+        Some(zone) => midnight_in(zone, day),
+        None => midnight_in(&Local, day),
+    }
+}
+
+fn midnight_in<Z: TimeZone>(zone: &Z, day: NaiveDate) -> Result<DateTime<Utc>> {
+    let midnight = day.and_hms_opt(0, 0, 0).ok_or("invalid date")?;
+    // days starting with a DST jump have no midnight, they begin at the first hour after it
+    let local = (0..24)
+        .find_map(|hour| {
+            zone.from_local_datetime(&(midnight + Duration::hours(hour)))
+                .earliest()
+        })
+        .ok_or("invalid local date")?;
+    Ok(local.with_timezone(&Utc))
+}
+
+/// Formats a stored date in local time
+pub fn format_date(date: &DateTime<Utc>) -> String {
+    to_local(date).format("%a %d %b %Y %H:%M").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_offset_reads_each_unit() {
+        assert_eq!(parse_offset("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_offset("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_offset(" 3d ").unwrap(), Duration::days(3));
+        assert_eq!(parse_offset("2w").unwrap(), Duration::weeks(2));
+    }
+
+    #[test]
+    fn parse_offset_rejects_bad_input() {
+        assert!(parse_offset("").is_err());
+        assert!(parse_offset("d").is_err());
+        assert!(parse_offset("3x").is_err());
+        assert!(parse_offset("threed").is_err());
+    }
+
+    #[test]
+    fn parse_when_reads_dates_as_local_midnight() {
+        let day = NaiveDate::from_ymd_opt(2022, 4, 1).unwrap();
+        assert_eq!(
+            parse_when("2022-04-01").unwrap(),
+            local_midnight(day).unwrap()
+        );
+    }
+
+    #[test]
+    fn parse_when_reads_named_days() {
+        let today = Local::now().date_naive();
+        let tomorrow = parse_when("TOMORROW ").unwrap().with_timezone(&Local);
+        assert_eq!(tomorrow.date_naive(), today + Duration::days(1));
+        let friday = parse_when("friday")
+            .unwrap()
+            .with_timezone(&Local)
+            .date_naive();
+        assert_eq!(friday.weekday(), Weekday::Fri);
+        assert!(friday > today && friday <= today + Duration::days(7));
+    }
+
+    #[test]
+    fn parse_when_counts_offsets_from_now() {
+        let before = Utc::now();
+        let when = parse_when("3d").unwrap();
+        assert!(when >= before + Duration::days(3));
+        assert!(when <= Utc::now() + Duration::days(3));
+    }
+
+    #[test]
+    fn parse_when_rejects_unknown_words() {
+        assert!(parse_when("someday").is_err());
+        assert!(parse_when("2022-13-01").is_err());
+    }
+
+    #[test]
+    fn parse_deadline_means_the_end_of_a_day() {
+        let deadline = parse_deadline("2022-04-01").unwrap().with_timezone(&Local);
+        assert_eq!(
+            deadline.date_naive(),
+            NaiveDate::from_ymd_opt(2022, 4, 1).unwrap()
+        );
+        assert_eq!(deadline.num_seconds_from_midnight(), 24 * 60 * 60 - 1);
+    }
+}
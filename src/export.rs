@@ -0,0 +1,397 @@
+use chrono::{DateTime, Utc};
+
+use crate::args::ChatService;
+use crate::{render_board, Config, LineKind, RenderOptions, Style, Task, Tasks};
+
+/// The board as org-mode headings, tasks as `TODO`/`DONE` and notes without a keyword
+pub fn to_org(tasks: &Tasks, config: &Config) -> String {
+    let levels = config.priority_levels();
+    let mut org = String::new();
+    for task in tasks.iter() {
+        let mut heading = String::from("*");
+        if task.is_task {
+            heading.push_str(if task.is_done { " DONE" } else { " TODO" });
+        }
+        // org priorities run from A, most important, to Z
+        if let Some(index) = levels.iter().position(|f| f.name == task.priority) {
+            heading.push_str(&format!(" [#{}]", (b'A' + index.min(25) as u8) as char));
+        }
+        heading.push(' ');
+        heading.push_str(&task.name);
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|f| org_tag(f)).collect();
+            heading.push_str(&format!(" :{}:", tags.join(":")));
+        }
+        org.push_str(&heading);
+        org.push('\n');
+
+        let mut planning = Vec::new();
+        if let Some(ref until) = task.snoozed_until {
+            planning.push(format!("SCHEDULED: {}", org_timestamp(until, true)));
+        }
+        if let Some(ref due) = task.due.or(task.expires_at) {
+            planning.push(format!("DEADLINE: {}", org_timestamp(due, true)));
+        }
+        if !planning.is_empty() {
+            org.push_str(&format!("  {}\n", planning.join(" ")));
+        }
+        org.push_str("  :PROPERTIES:\n");
+        org.push_str(&format!("  :ID: {}\n", task.id));
+        org.push_str(&format!(
+            "  :CREATED: {}\n",
+            org_timestamp(&task.created(), false)
+        ));
+        if task.in_progress {
+            org.push_str("  :STATE: in-progress\n");
+        }
+        org.push_str("  :END:\n");
+        if let Some(ref body) = task.body {
+            for line in body.lines() {
+                org.push_str(&format!("  {line}\n"));
+            }
+        }
+    }
+    org
+}
+
+/// `<2022-03-14 Mon 10:00>` when active, `[...]` otherwise
+fn org_timestamp(date: &DateTime<Utc>, active: bool) -> String {
+    let stamp = crate::dates::to_local(date)
+        .format("%Y-%m-%d %a %H:%M")
+        .to_string();
+    if active {
+        format!("<{stamp}>")
+    } else {
+        format!("[{stamp}]")
+    }
+}
+
+/// Org tags only hold letters, digits, `_`, `@`, `#` and `%`
+fn org_tag(tag: &str) -> String {
+    tag.chars()
+        .map(|c| match c {
+            c if c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%') => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+/// Open dated tasks as an iCalendar feed, each an event at its due time lasting its
+/// estimate. Tasks due at the end of a day, as days given with `--due` are, become
+/// all-day events.
+pub fn to_ics(tasks: &Tasks) -> String {
+    let stamp = |date: &DateTime<Utc>| date.format("%Y%m%dT%H%M%SZ").to_string();
+    let now = Utc::now();
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//utd//utd//EN"),
+        String::from("X-WR-CALNAME:utd"),
+    ];
+    for task in tasks.iter().filter(|f| !f.is_done && !f.expired) {
+        let due = match task.due {
+            Some(due) => due,
+            None => continue,
+        };
+        lines.push(String::from("BEGIN:VEVENT"));
+        lines.push(format!("UID:{}@utd", task.timestamp));
+        lines.push(format!("DTSTAMP:{}", stamp(&now)));
+        let local = crate::dates::to_local(&due);
+        if local.format("%H:%M").to_string() == "23:59" {
+            lines.push(format!("DTSTART;VALUE=DATE:{}", local.format("%Y%m%d")));
+        } else {
+            let end = due + chrono::Duration::seconds(task.estimate.unwrap_or(0) as i64);
+            lines.push(format!("DTSTART:{}", stamp(&due)));
+            lines.push(format!("DTEND:{}", stamp(&end)));
+        }
+        lines.push(format!(
+            "SUMMARY:{}",
+            escape_ics(&format!("{}. {}", task.id, task.name))
+        ));
+        if let Some(ref body) = task.body {
+            lines.push(format!("DESCRIPTION:{}", escape_ics(body)));
+        }
+        if !task.tags.is_empty() {
+            let tags: Vec<String> = task.tags.iter().map(|f| escape_ics(f)).collect();
+            lines.push(format!("CATEGORIES:{}", tags.join(",")));
+        }
+        lines.push(String::from("END:VEVENT"));
+    }
+    lines.push(String::from("END:VCALENDAR"));
+    let lines: Vec<String> = lines.iter().map(|f| fold_ics(f)).collect();
+    format!("{}\r\n", lines.join("\r\n"))
+}
+
+/// Open dated tasks as Remind reminders, e.g. `REM 2 May 2024 AT 14:30 DURATION 1:30 MSG
+/// 12. renew passport %b`. Tasks due at the end of a day are untimed.
+pub fn to_remind(tasks: &Tasks) -> String {
+    let mut lines = Vec::new();
+    for task in tasks.iter().filter(|f| !f.is_done && !f.expired) {
+        let due = match task.due {
+            Some(ref due) => crate::dates::to_local(due),
+            None => continue,
+        };
+        let mut line = format!("REM {}", due.format("%-d %b %Y"));
+        if due.format("%H:%M").to_string() != "23:59" {
+            line.push_str(&format!(" AT {}", due.format("%H:%M")));
+            if let Some(estimate) = task.estimate.filter(|f| *f >= 60) {
+                line.push_str(&format!(
+                    " DURATION {}:{:02}",
+                    estimate / 3600,
+                    estimate % 3600 / 60
+                ));
+            }
+        }
+        // `%` starts a substitution and `[` an expression in the body of a reminder
+        let message = format!("{}. {}", task.id, task.name)
+            .replace('%', "%%")
+            .replace('[', "[\"[\"]");
+        lines.push(format!("{line} MSG {message} %b"));
+    }
+    lines.iter().map(|f| format!("{f}\n")).collect()
+}
+
+/// Open dated tasks as lines of a `when` calendar, e.g. `2024 May 02 , 12. renew passport`
+pub fn to_when(tasks: &Tasks) -> String {
+    let mut lines = Vec::new();
+    for task in tasks.iter().filter(|f| !f.is_done && !f.expired) {
+        let due = match task.due {
+            Some(ref due) => crate::dates::to_local(due),
+            None => continue,
+        };
+        let time = due.format("%H:%M").to_string();
+        let time = if time == "23:59" {
+            String::new()
+        } else {
+            format!("{time} ")
+        };
+        lines.push(format!(
+            "{} , {}{}. {}",
+            due.format("%Y %b %d"),
+            time,
+            task.id,
+            task.name
+        ));
+    }
+    lines.iter().map(|f| format!("{f}\n")).collect()
+}
+
+fn escape_ics(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Breaks lines longer than 75 bytes, continuing each with a space
+fn fold_ics(line: &str) -> String {
+    let mut folded = String::new();
+    let mut length = 0;
+    for c in line.chars() {
+        if length + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            length = 1;
+        }
+        folded.push(c);
+        length += c.len_utf8();
+    }
+    folded
+}
+
+/// A self-contained page drawing the board with its theme colours
+pub fn to_html(tasks: &Tasks, config: &Config) -> String {
+    let lines = render_board(
+        tasks,
+        config,
+        RenderOptions {
+            title: Some(String::from("utd")),
+            ..RenderOptions::default()
+        },
+    );
+    let mut body = String::new();
+    for line in lines.iter() {
+        let (tag, class) = match line.kind {
+            LineKind::Title => ("h1", "title"),
+            LineKind::Heading => ("h2", "heading"),
+            LineKind::Entry => ("div", "entry"),
+            LineKind::Footer => ("div", "footer"),
+        };
+        body.push_str(&format!(
+            "<{tag} class=\"{class}\" style=\"padding-left: {}ch\">",
+            line.indent
+        ));
+        for span in line.spans.iter() {
+            body.push_str(&format!(
+                "<span style=\"{}\">{}</span>",
+                css(&span.style),
+                escape_html(&span.text)
+            ));
+        }
+        body.push_str(&format!("</{tag}>\n"));
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>utd</title>
+<style>
+body {{ background: #282828; color: #ebdbb2; font-family: ui-monospace, monospace; margin: 2em; }}
+h1 {{ font-size: 1.4em; }}
+h2 {{ font-size: 1.1em; margin: 1em 0 0.3em; }}
+.entry, .footer {{ line-height: 1.6; }}
+</style>
+</head>
+<body>
+{body}</body>
+</html>
+"#
+    )
+}
+
+fn css(style: &Style) -> String {
+    let mut css = Vec::new();
+    if let Some((r, g, b)) = style.colour {
+        css.push(format!("color: #{r:02x}{g:02x}{b:02x}"));
+    }
+    if style.bold {
+        css.push(String::from("font-weight: bold"));
+    }
+    if style.italic {
+        css.push(String::from("font-style: italic"));
+    }
+    let decorations: Vec<&str> = [
+        (style.underline, "underline"),
+        (style.strikethrough, "line-through"),
+    ]
+    .iter()
+    .filter(|(on, _)| *on)
+    .map(|(_, decoration)| *decoration)
+    .collect();
+    if !decorations.is_empty() {
+        css.push(format!("text-decoration: {}", decorations.join(" ")));
+    }
+    if style.dimmed {
+        css.push(String::from("opacity: 0.6"));
+    }
+    css.join("; ")
+}
+
+/// Tasks with dependencies as a Graphviz digraph, each edge pointing from a task to
+/// one waiting on it. Nodes are coloured by state: done, blocked, in progress or open.
+pub fn to_dot(tasks: &Tasks) -> String {
+    let linked: Vec<&Task> = tasks
+        .iter()
+        .filter(|f| !f.depends_on.is_empty() || tasks.iter().any(|t| t.depends_on.contains(&f.id)))
+        .collect();
+    let mut dot = String::from("digraph utd {\n    rankdir=LR;\n");
+    dot.push_str("    node [shape=box, style=\"rounded,filled\", fontname=\"sans-serif\"];\n");
+    for task in linked.iter() {
+        let colour = if task.is_done {
+            "#b8bb26"
+        } else if !task.blockers(tasks).is_empty() {
+            "#fb4934"
+        } else if task.in_progress {
+            "#fabd2f"
+        } else {
+            "#83a598"
+        };
+        dot.push_str(&format!(
+            "    {} [label=\"{}. {}\", fillcolor=\"{}\"];\n",
+            task.id,
+            task.id,
+            escape_dot(&task.name),
+            colour
+        ));
+    }
+    for task in linked.iter() {
+        for dependency in task
+            .depends_on
+            .iter()
+            .filter(|id| tasks.iter().any(|f| f.id == **id))
+        {
+            dot.push_str(&format!("    {} -> {};\n", dependency, task.id));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+/// Headed lists as chat markdown, `*bold*` headings and `•` bullets for Slack,
+/// `**bold**` and `-` for Discord. Empty lists read `none`.
+pub fn to_chat(sections: &[(String, Vec<String>)], service: ChatService, none: &str) -> String {
+    // Slack treats <, > and & as control characters
+    let escape = |text: &str| match service {
+        ChatService::Slack => text
+            .replace('&', "&amp;")
+            .replace('<', "&lt;")
+            .replace('>', "&gt;"),
+        ChatService::Discord => text.to_owned(),
+    };
+    let (bold, bullet) = match service {
+        ChatService::Slack => ("*", "\u{2022}"),
+        ChatService::Discord => ("**", "-"),
+    };
+    let mut text = Vec::new();
+    for (heading, items) in sections.iter() {
+        let mut lines = vec![format!("{bold}{}{bold}", escape(heading))];
+        if items.is_empty() {
+            lines.push(format!("{bullet} {}", escape(none)));
+        }
+        lines.extend(items.iter().map(|f| format!("{bullet} {}", escape(f))));
+        text.push(lines.join("\n"));
+    }
+    text.join("\n\n")
+}
+
+/// The body of a message posted to an incoming webhook of `service`
+pub fn chat_payload(text: &str, service: ChatService) -> serde_json::Value {
+    match service {
+        ChatService::Slack => serde_json::json!({ "text": text }),
+        // Discord rejects messages over 2000 characters
+        ChatService::Discord => {
+            let text = match text.char_indices().nth(1999) {
+                Some((end, _)) => format!("{}\u{2026}", &text[..end]),
+                None => text.to_owned(),
+            };
+            serde_json::json!({ "content": text })
+        }
+    }
+}
+
+/// Headed lists as a plain text email, sections without items left out
+pub fn digest_text(sections: &[(String, Vec<String>)]) -> String {
+    let mut text = String::new();
+    for (heading, items) in sections.iter().filter(|(_, items)| !items.is_empty()) {
+        text.push_str(&format!("{heading}\n"));
+        for item in items.iter() {
+            text.push_str(&format!("  - {item}\n"));
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// Headed lists as the HTML part of an email
+pub fn digest_html(sections: &[(String, Vec<String>)]) -> String {
+    let mut body = String::new();
+    for (heading, items) in sections.iter().filter(|(_, items)| !items.is_empty()) {
+        body.push_str(&format!("<h3>{}</h3>\n<ul>\n", escape_html(heading)));
+        for item in items.iter() {
+            body.push_str(&format!("  <li>{}</li>\n", escape_html(item)));
+        }
+        body.push_str("</ul>\n");
+    }
+    format!("<!DOCTYPE html>\n<html>\n<body>\n{body}</body>\n</html>\n")
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,168 @@
+use std::str::FromStr;
+
+use crate::Task;
+
+/// Space separated `key=value` conditions that must all hold,
+/// e.g. `priority=low state=done tag=@work`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Filter {
+    conditions: Vec<Condition>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Condition {
+    Priority(String),
+    State(String),
+    Tag(String),
+    Kind(String),
+    Name(String),
+    Project(String),
+}
+
+impl Filter {
+    /// Entries carrying `tag`, with or without its prefix
+    pub fn tag(tag: &str) -> Self {
+        Self {
+            conditions: vec![Condition::Tag(normalise_tag(tag))],
+        }
+    }
+
+    /// Entries of `project`, with or without its `+`
+    pub fn project(project: &str) -> Self {
+        Self {
+            conditions: vec![Condition::Project(normalise_tag(project))],
+        }
+    }
+
+    /// Requires both filters to match
+    pub fn and(mut self, other: Filter) -> Self {
+        self.conditions.extend(other.conditions);
+        self
+    }
+
+    pub fn matches(&self, task: &Task) -> bool {
+        self.conditions.iter().all(|condition| match condition {
+            Condition::Priority(priority) => task.priority == *priority,
+            Condition::State(state) => match state.as_str() {
+                "done" => task.is_done,
+                "in-progress" | "started" => task.in_progress,
+                "unverified" => task.checked_by.is_some(),
+                "snoozed" => task.is_snoozed(),
+                "pinned" => task.pinned,
+                "expired" => task.expired,
+                "waiting" => task.is_waiting(),
+                "overdue" => task.is_overdue(),
+                // open
+                _ => !task.is_done && !task.in_progress && !task.expired,
+            },
+            Condition::Tag(tag) => task.tags.iter().any(|f| normalise_tag(f) == *tag),
+            Condition::Kind(kind) => (kind == "task") == task.is_task,
+            Condition::Name(name) => task.name.to_lowercase().contains(name),
+            Condition::Project(project) => task
+                .project
+                .as_ref()
+                .is_some_and(|f| f.to_lowercase() == *project),
+        })
+    }
+}
+
+impl FromStr for Filter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut conditions = Vec::new();
+        for token in s.split_whitespace() {
+            let (key, value) = token
+                .split_once('=')
+                .ok_or(format!("expected key=value in filter, found {token}"))?;
+            let value = value.to_lowercase();
+            conditions.push(match key {
+                "priority" => Condition::Priority(value),
+                "state" => match value.as_str() {
+                    "open" | "todo" | "done" | "in-progress" | "started" | "unverified"
+                    | "snoozed" | "pinned" | "expired" | "waiting" | "overdue" => {
+                        Condition::State(value)
+                    }
+                    _ => return Err(format!("unknown state {value}")),
+                },
+                "tag" => Condition::Tag(normalise_tag(&value)),
+                "type" => match value.as_str() {
+                    "task" | "note" => Condition::Kind(value),
+                    _ => return Err(format!("unknown type {value}")),
+                },
+                "name" => Condition::Name(value),
+                "project" => Condition::Project(normalise_tag(&value)),
+                _ => return Err(format!("unknown filter key {key}")),
+            });
+        }
+        Ok(Self { conditions })
+    }
+}
+
+fn normalise_tag(tag: &str) -> String {
+    tag.trim_start_matches(|c: char| c.is_ascii_punctuation())
+        .trim_matches('"')
+        .to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task(name: &str, priority: &str, tags: &[&str]) -> Task {
+        Task {
+            name: name.to_owned(),
+            priority: priority.to_owned(),
+            tags: tags.iter().map(|f| f.to_string()).collect(),
+            is_task: true,
+            ..Task::default()
+        }
+    }
+
+    #[test]
+    fn all_conditions_have_to_hold() {
+        let filter: Filter = "priority=high state=open tag=@Work".parse().unwrap();
+        assert!(filter.matches(&task("report", "high", &["work"])));
+        assert!(!filter.matches(&task("report", "low", &["work"])));
+        assert!(!filter.matches(&task("report", "high", &["home"])));
+        let mut done = task("report", "high", &["work"]);
+        done.is_done = true;
+        assert!(!filter.matches(&done));
+    }
+
+    #[test]
+    fn an_empty_filter_matches_everything() {
+        let filter: Filter = "".parse().unwrap();
+        assert!(filter.matches(&task("anything", "low", &[])));
+    }
+
+    #[test]
+    fn names_match_in_part_and_ignoring_case() {
+        let filter: Filter = "name=MILK type=task".parse().unwrap();
+        assert!(filter.matches(&task("Buy milk", "normal", &[])));
+        let mut note = task("Buy milk", "normal", &[]);
+        note.is_task = false;
+        assert!(!filter.matches(&note));
+    }
+
+    #[test]
+    fn tags_and_projects_match_with_or_without_prefix() {
+        let mut entry = task("fix tap", "normal", &["home"]);
+        entry.project = Some(String::from("House"));
+        assert!(Filter::tag("@home").matches(&entry));
+        assert!(Filter::tag("home")
+            .and(Filter::project("+house"))
+            .matches(&entry));
+        assert!(!Filter::tag("home")
+            .and(Filter::project("garden"))
+            .matches(&entry));
+    }
+
+    #[test]
+    fn bad_conditions_are_rejected() {
+        assert!("priority".parse::<Filter>().is_err());
+        assert!("state=later".parse::<Filter>().is_err());
+        assert!("type=event".parse::<Filter>().is_err());
+        assert!("colour=red".parse::<Filter>().is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::collections::HashMap;
+
+/// English text of every translatable string, by key
+const ENGLISH: &[(&str, &str)] = &[
+    ("todo", "to-do"),
+    ("in-progress", "in progress"),
+    ("notes", "notes"),
+    ("yesterday", "Yesterday"),
+    ("today", "Today"),
+    ("blockers", "Blockers"),
+    ("none", "none"),
+    ("nothing-to-review", "nothing to review"),
+    ("nothing-next", "nothing left to do"),
+    ("overdue", "Overdue"),
+    ("due-today", "Due today"),
+    ("done", "done"),
+    ("pending", "pending"),
+];
+
+const ENGLISH_GREETINGS: &[&str] = &[
+    "Here's is your board",
+    "Remember...",
+    "Let's get things done",
+    "Focus",
+];
+
+/// A language, its strings by key and its greetings
+type Translation = (
+    &'static str,
+    &'static [(&'static str, &'static str)],
+    &'static [&'static str],
+);
+
+/// Bundled translations
+const BUNDLED: &[Translation] = &[
+    (
+        "de",
+        &[
+            ("todo", "zu erledigen"),
+            ("in-progress", "in Arbeit"),
+            ("notes", "Notizen"),
+            ("yesterday", "Gestern"),
+            ("today", "Heute"),
+            ("blockers", "Blockaden"),
+            ("none", "keine"),
+            ("nothing-to-review", "nichts zu überprüfen"),
+            ("nothing-next", "nichts mehr zu tun"),
+            ("overdue", "Überfällig"),
+            ("due-today", "Heute fällig"),
+            ("done", "erledigt"),
+            ("pending", "offen"),
+        ],
+        &[
+            "Hier ist dein Board",
+            "Denk daran...",
+            "Packen wir's an",
+            "Fokus",
+        ],
+    ),
+    (
+        "es",
+        &[
+            ("todo", "pendiente"),
+            ("in-progress", "en curso"),
+            ("notes", "notas"),
+            ("yesterday", "Ayer"),
+            ("today", "Hoy"),
+            ("blockers", "Bloqueos"),
+            ("none", "nada"),
+            ("nothing-to-review", "nada que revisar"),
+            ("nothing-next", "no queda nada por hacer"),
+            ("overdue", "Vencidas"),
+            ("due-today", "Vencen hoy"),
+            ("done", "hechas"),
+            ("pending", "pendientes"),
+        ],
+        &[
+            "Aquí está tu tablero",
+            "Recuerda...",
+            "Manos a la obra",
+            "Concéntrate",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            ("todo", "à faire"),
+            ("in-progress", "en cours"),
+            ("notes", "notes"),
+            ("yesterday", "Hier"),
+            ("today", "Aujourd'hui"),
+            ("blockers", "Blocages"),
+            ("none", "rien"),
+            ("nothing-to-review", "rien à revoir"),
+            ("nothing-next", "plus rien à faire"),
+            ("overdue", "En retard"),
+            ("due-today", "Pour aujourd'hui"),
+            ("done", "faites"),
+            ("pending", "en attente"),
+        ],
+        &[
+            "Voici ton tableau",
+            "N'oublie pas...",
+            "Au travail",
+            "Concentre-toi",
+        ],
+    ),
+    (
+        "pt",
+        &[
+            ("todo", "a fazer"),
+            ("in-progress", "em andamento"),
+            ("notes", "notas"),
+            ("yesterday", "Ontem"),
+            ("today", "Hoje"),
+            ("blockers", "Bloqueios"),
+            ("none", "nada"),
+            ("nothing-to-review", "nada para rever"),
+            ("nothing-next", "nada mais a fazer"),
+            ("overdue", "Atrasadas"),
+            ("due-today", "Vencem hoje"),
+            ("done", "feitas"),
+            ("pending", "pendentes"),
+        ],
+        &[
+            "Aqui está o seu quadro",
+            "Lembre-se...",
+            "Mãos à obra",
+            "Foco",
+        ],
+    ),
+];
+
+/// The language of a locale such as `de_DE.UTF-8`, or of the first of `$LC_ALL`,
+/// `$LC_MESSAGES` and `$LANG` that is set when `locale` is `None`
+pub fn language(locale: Option<&str>) -> String {
+    let locale = match locale {
+        Some(locale) => locale.to_owned(),
+        None => ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|f| std::env::var(f).ok())
+            .find(|f| !f.is_empty())
+            .unwrap_or_default(),
+    };
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// User-facing text in one language, with strings overridden from the config
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Translations {
+    language: String,
+    overrides: HashMap<String, String>,
+}
+
+impl Translations {
+    pub fn new(language: &str, overrides: HashMap<String, String>) -> Self {
+        Self {
+            language: language.to_owned(),
+            overrides,
+        }
+    }
+
+    fn bundled(&self) -> Option<&Translation> {
+        BUNDLED
+            .iter()
+            .find(|(language, ..)| *language == self.language)
+    }
+
+    /// The string for `key`: overridden, translated or else English
+    pub fn get(&self, key: &str) -> String {
+        if let Some(text) = self.overrides.get(key) {
+            return text.to_owned();
+        }
+        let lookup = |strings: &[(&str, &str)]| {
+            strings
+                .iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, text)| text.to_string())
+        };
+        self.bundled()
+            .and_then(|(_, strings, _)| lookup(strings))
+            .or_else(|| lookup(ENGLISH))
+            .unwrap_or_else(|| key.to_owned())
+    }
+
+    pub fn greetings(&self) -> Vec<String> {
+        let greetings = match self.bundled() {
+            Some((_, _, greetings)) => greetings,
+            None => ENGLISH_GREETINGS,
+        };
+        greetings.iter().map(|f| f.to_string()).collect()
+    }
+}
@@ -0,0 +1,138 @@
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+/// The glyphs icons default to when the config doesn't set them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IconProfile {
+    /// Glyphs from a patched (nerd) font
+    Nerd,
+    Unicode,
+    /// Markers such as `[ ]` and `[x]` for terminals without unicode fonts
+    Ascii,
+}
+
+impl FromStr for IconProfile {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nerd" => Ok(IconProfile::Nerd),
+            "unicode" => Ok(IconProfile::Unicode),
+            "ascii" => Ok(IconProfile::Ascii),
+            _ => Err(format!(
+                "expected auto, nerd, unicode or ascii for icons, found {s}"
+            )),
+        }
+    }
+}
+
+/// Default icon of each part of the board
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Icons {
+    pub title: &'static str,
+    pub todo_title: &'static str,
+    pub todo_entry: &'static str,
+    pub todo_completed: &'static str,
+    pub in_progress_title: &'static str,
+    pub in_progress_entry: &'static str,
+    pub notes_title: &'static str,
+    pub notes_entry: &'static str,
+    pub notes_completed: &'static str,
+    pub tag: &'static str,
+    pub pin: &'static str,
+    pub expired: &'static str,
+    pub stale: &'static str,
+}
+
+const NERD: Icons = Icons {
+    title: "\u{f64d} ",
+    todo_title: "\u{f45e} ",
+    todo_entry: "",
+    todo_completed: "\u{2713} ",
+    in_progress_title: "\u{fa1e} ",
+    in_progress_entry: "\u{f105} ",
+    notes_title: "\u{f249} ",
+    notes_entry: "",
+    notes_completed: "",
+    tag: "\u{23fd}",
+    pin: "\u{f08d} ",
+    expired: "\u{f252} ",
+    stale: "\u{f017} ",
+};
+
+const UNICODE: Icons = Icons {
+    title: "",
+    todo_title: "",
+    todo_entry: "",
+    todo_completed: "",
+    in_progress_title: "",
+    in_progress_entry: "",
+    notes_title: "",
+    notes_entry: "",
+    notes_completed: "",
+    tag: "",
+    pin: "* ",
+    expired: "\u{231b} ",
+    stale: "\u{231a} ",
+};
+
+const ASCII: Icons = Icons {
+    title: "",
+    todo_title: "",
+    todo_entry: "[ ] ",
+    todo_completed: "[x] ",
+    in_progress_title: "",
+    in_progress_entry: "> ",
+    notes_title: "",
+    notes_entry: "- ",
+    notes_completed: "- ",
+    tag: "",
+    pin: "* ",
+    expired: "~ ",
+    stale: "! ",
+};
+
+impl IconProfile {
+    /// ASCII on the Linux console, dumb terminals and locales that aren't UTF-8, unicode
+    /// otherwise. Nerd fonts can't be told apart from other fonts, so they are configured.
+    pub fn detect() -> Self {
+        let term = std::env::var("TERM").unwrap_or_default();
+        let locale = ["LC_ALL", "LC_CTYPE", "LANG"]
+            .iter()
+            .find_map(|f| std::env::var(f).ok().filter(|f| !f.is_empty()))
+            .unwrap_or_default()
+            .to_lowercase();
+        // Windows consoles set neither, but draw unicode
+        let utf8 = cfg!(windows) || locale.contains("utf-8") || locale.contains("utf8");
+        if matches!(term.as_str(), "linux" | "dumb") || !utf8 {
+            IconProfile::Ascii
+        } else {
+            IconProfile::Unicode
+        }
+    }
+
+    pub fn icons(self) -> &'static Icons {
+        match self {
+            IconProfile::Nerd => &NERD,
+            IconProfile::Unicode => &UNICODE,
+            IconProfile::Ascii => &ASCII,
+        }
+    }
+}
+
+static PROFILE: OnceLock<IconProfile> = OnceLock::new();
+
+/// Profile detected once for the whole process
+pub fn icon_profile() -> IconProfile {
+    *PROFILE.get_or_init(IconProfile::detect)
+}
+
+/// Overrides detection with the `icons` profile from the config
+pub fn set_icon_profile(profile: IconProfile) {
+    let _ = PROFILE.set(profile);
+}
+
+/// Icons of the current profile, used for those the config leaves unset
+pub fn icons() -> &'static Icons {
+    icon_profile().icons()
+}
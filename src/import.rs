@@ -0,0 +1,219 @@
+use chrono::{DateTime, NaiveDateTime, TimeZone, Utc};
+use serde::Deserialize;
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+const GITHUB_API: &str = "https://api.github.com";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubIssue {
+    pub number: u64,
+    pub title: String,
+    pub html_url: String,
+    #[serde(default)]
+    pub labels: Vec<GithubLabel>,
+    /// Present when the issue is a pull request
+    #[serde(default)]
+    pub pull_request: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GithubLabel {
+    pub name: String,
+}
+
+#[derive(Deserialize)]
+struct GithubUser {
+    login: String,
+}
+
+fn github_get(url: &str, token: Option<&str>) -> ureq::Request {
+    let request = ureq::get(url)
+        .set("Accept", "application/vnd.github+json")
+        .set("User-Agent", concat!("utd/", env!("CARGO_PKG_VERSION")));
+    match token {
+        Some(token) => request.set("Authorization", &format!("Bearer {token}")),
+        None => request,
+    }
+}
+
+/// Open issues of `repo` (`owner/name`), leaving out pull requests.
+///
+/// `assignee` is a login, `*`, `none` or `me` for the owner of `token`.
+pub fn github_issues(
+    repo: &str,
+    assignee: Option<&str>,
+    token: Option<&str>,
+) -> Result<Vec<GithubIssue>> {
+    if repo.split('/').count() != 2 {
+        return Err(format!("expected owner/repo, found {repo}").into());
+    }
+    let assignee = match assignee {
+        Some("me") => {
+            let token = token.ok_or("--assignee me needs GITHUB_TOKEN to be set")?;
+            let user: GithubUser = github_get(&format!("{GITHUB_API}/user"), Some(token))
+                .call()?
+                .into_json()?;
+            Some(user.login)
+        }
+        assignee => assignee.map(str::to_owned),
+    };
+    let mut issues = Vec::new();
+    for page in 1.. {
+        let mut request = github_get(&format!("{GITHUB_API}/repos/{repo}/issues"), token)
+            .query("state", "open")
+            .query("per_page", "100")
+            .query("page", &page.to_string());
+        if let Some(ref assignee) = assignee {
+            request = request.query("assignee", assignee);
+        }
+        let batch: Vec<GithubIssue> = request.call()?.into_json()?;
+        let last = batch.len() < 100;
+        issues.extend(batch.into_iter().filter(|f| f.pull_request.is_none()));
+        if last {
+            break;
+        }
+    }
+    Ok(issues)
+}
+
+/// The first label naming one of `levels`, such as `high` or `priority: high`
+pub fn priority_from_labels(labels: &[String], levels: &[String]) -> Option<String> {
+    labels.iter().find_map(|label| {
+        let label = label.to_lowercase();
+        let name = label
+            .trim_start_matches("priority")
+            .trim_start_matches([':', '/', '-', ' '])
+            .trim();
+        levels.iter().find(|f| f.to_lowercase() == name).cloned()
+    })
+}
+
+/// The parts of a Trello board export (`Menu > Print and export > JSON`) that are imported
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrelloBoard {
+    #[serde(default)]
+    pub lists: Vec<TrelloList>,
+    #[serde(default)]
+    pub cards: Vec<TrelloCard>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrelloList {
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TrelloCard {
+    pub name: String,
+    #[serde(default)]
+    pub desc: String,
+    pub id_list: String,
+    /// Archived cards
+    #[serde(default)]
+    pub closed: bool,
+    #[serde(default)]
+    pub labels: Vec<TrelloLabel>,
+    #[serde(default)]
+    pub short_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrelloLabel {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+impl TrelloLabel {
+    /// Unnamed labels are known by their colour
+    pub fn tag(&self) -> Option<String> {
+        match self.name.trim() {
+            "" => self.color.clone(),
+            name => Some(name.to_owned()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListState {
+    Todo,
+    InProgress,
+    Done,
+}
+
+impl TrelloBoard {
+    pub fn read(path: &std::path::Path) -> Result<Self> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    /// The state a card's list stands for, going by the list name
+    pub fn list_state(&self, card: &TrelloCard) -> ListState {
+        let name = self
+            .lists
+            .iter()
+            .find(|f| f.id == card.id_list)
+            .map(|f| f.name.to_lowercase())
+            .unwrap_or_default();
+        if ["done", "complete", "finished", "shipped"]
+            .iter()
+            .any(|f| name.contains(f))
+        {
+            ListState::Done
+        } else if ["doing", "progress", "started", "review"]
+            .iter()
+            .any(|f| name.contains(f))
+        {
+            ListState::InProgress
+        } else {
+            ListState::Todo
+        }
+    }
+}
+
+/// A task as Taskwarrior hands it to its hooks, one JSON object per line
+#[derive(Debug, Clone, Deserialize)]
+pub struct TaskwarriorTask {
+    pub uuid: String,
+    pub description: String,
+    /// `pending`, `completed`, `deleted`, `waiting` or `recurring`
+    pub status: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// `H`, `M` or `L`
+    pub priority: Option<String>,
+    pub project: Option<String>,
+    /// e.g. `20240502T220000Z`
+    pub due: Option<String>,
+    /// Set while the task is started
+    pub start: Option<String>,
+}
+
+impl TaskwarriorTask {
+    /// `high`, `normal` or `low` for `H`, `M` and `L`, when it is one of `levels`
+    pub fn priority(&self, levels: &[String]) -> Option<String> {
+        let name = match self.priority.as_deref()? {
+            "H" => "high",
+            "M" => "normal",
+            "L" => "low",
+            _ => return None,
+        };
+        levels.iter().find(|f| *f == name).cloned()
+    }
+
+    pub fn due(&self) -> Option<DateTime<Utc>> {
+        let due = NaiveDateTime::parse_from_str(self.due.as_deref()?, "%Y%m%dT%H%M%SZ").ok()?;
+        Some(Utc.from_utc_datetime(&due))
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.status == "completed"
+    }
+
+    pub fn is_deleted(&self) -> bool {
+        self.status == "deleted"
+    }
+}
@@ -0,0 +1,55 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+
+/// One line of the append-only journal
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    /// A stopped timer, logged when it stops
+    FocusSession { task: i64, seconds: u64 },
+    Interruption {
+        note: String,
+        #[serde(default)]
+        task: Option<i64>,
+    },
+}
+
+impl JournalEntry {
+    pub fn now(event: Event) -> Self {
+        Self {
+            at: Utc::now(),
+            event,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DayFocus {
+    /// Seconds of timed work
+    pub focus: u64,
+    pub interruptions: Vec<String>,
+}
+
+/// Focus time and interruptions per local day, oldest first
+pub fn focus_report(entries: &[JournalEntry]) -> BTreeMap<NaiveDate, DayFocus> {
+    let mut days: BTreeMap<NaiveDate, DayFocus> = BTreeMap::new();
+    for entry in entries.iter() {
+        let day = days
+            .entry(crate::dates::to_local(&entry.at).date_naive())
+            .or_default();
+        match entry.event {
+            Event::FocusSession { seconds, .. } => day.focus += seconds,
+            Event::Interruption { ref note, .. } => day.interruptions.push(note.to_owned()),
+        }
+    }
+    days
+}
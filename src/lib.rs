@@ -2,12 +2,61 @@ pub mod args;
 mod data;
 pub use data::*;
 use std::path::PathBuf;
+mod colour;
+pub use colour::*;
 mod config;
 pub use config::*;
+pub mod dates;
+mod export;
+pub use export::*;
+mod api;
+pub use api::*;
+mod chart;
+pub mod import;
+pub mod script;
+pub mod sync;
+pub use chart::*;
+mod i18n;
+pub use i18n::*;
+mod icons;
+pub use icons::*;
+mod journal;
+pub use journal::*;
+mod filter;
+pub use filter::*;
+mod render;
+pub use render::*;
+mod template;
+pub use template::*;
 
-pub fn setup_logger(log_level: args::LogLevel) -> tracing_appender::non_blocking::WorkerGuard {
-    let file_appender = tracing_appender::rolling::daily(data_dir(), "utd-log");
-    let (file_writer, guard) = tracing_appender::non_blocking(file_appender);
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Logs to the file or stderr chosen under `[logs]`, rotating the file first when it is full
+pub fn setup_logger(
+    log_level: args::LogLevel,
+    logs: &Logs,
+) -> Result<tracing_appender::non_blocking::WorkerGuard> {
+    let (file_writer, guard) = match logs.destination() {
+        "stderr" => tracing_appender::non_blocking(std::io::stderr()),
+        "file" => {
+            let path = logs.path();
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            rotate_log(&path, logs.max_size() * 1024, logs.max_files())?;
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)
+                .map_err(|e| format!("cannot open log file {}: {e}", path.display()))?;
+            tracing_appender::non_blocking(file)
+        }
+        other => {
+            return Err(
+                format!("expected file or stderr for [logs] destination, found {other}").into(),
+            )
+        }
+    };
     tracing::subscriber::set_global_default(
         tracing_subscriber::fmt::Subscriber::builder()
             .with_max_level(match log_level {
@@ -28,7 +77,29 @@ pub fn setup_logger(log_level: args::LogLevel) -> tracing_appender::non_blocking
         clap::crate_name!(),
         clap::crate_version!()
     );
-    guard
+    Ok(guard)
+}
+
+/// Shifts `path` to `path.1`, `path.1` to `path.2` and so on once it holds `max_size` bytes,
+/// dropping whatever falls past `max_files`
+fn rotate_log(path: &std::path::Path, max_size: u64, max_files: usize) -> std::io::Result<()> {
+    match std::fs::metadata(path) {
+        Ok(metadata) if metadata.len() >= max_size => {}
+        _ => return Ok(()),
+    }
+    let numbered = |n: usize| PathBuf::from(format!("{}.{n}", path.display()));
+    if max_files == 0 {
+        return std::fs::remove_file(path);
+    }
+    if numbered(max_files).exists() {
+        std::fs::remove_file(numbered(max_files))?;
+    }
+    for n in (1..max_files).rev() {
+        if numbered(n).exists() {
+            std::fs::rename(numbered(n), numbered(n + 1))?;
+        }
+    }
+    std::fs::rename(path, numbered(1))
 }
 
 pub fn data_dir() -> PathBuf {
@@ -36,3 +107,20 @@ pub fn data_dir() -> PathBuf {
     let dirs = ProjectDirs::from("org", "Ugly Todo", "utd").unwrap();
     dirs.data_local_dir().to_path_buf()
 }
+
+/// Writes `contents` to `path` readable by the user alone, for files holding secrets
+pub fn write_private(path: &std::path::Path, contents: impl AsRef<[u8]>) -> std::io::Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+        options.mode(0o600);
+        // the mode only applies to new files
+        if path.exists() {
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        }
+    }
+    options.open(path)?.write_all(contents.as_ref())
+}
@@ -1,673 +1,4347 @@
-use ansi_term::{ANSIGenericString, Color::RGB};
-use clap::{lazy_static::lazy_static, StructOpt};
+use chrono::Datelike;
+use clap::CommandFactory;
 use rand::Rng;
-use regex::Regex;
+use rustyline::error::ReadlineError;
 use std::{
-    collections::VecDeque,
+    collections::{HashMap, HashSet, VecDeque},
     fs::File,
     io::Read,
-    io::Write,
+    io::{IsTerminal, Seek, Write},
     path::PathBuf,
+    sync::{Mutex, OnceLock},
     time::{SystemTime, UNIX_EPOCH},
 };
 use term_table::{
     row::Row,
     table_cell::{Alignment, TableCell},
-    Table, TableBuilder, TableStyle,
+    TableBuilder, TableStyle,
 };
-use tracing::{debug, error, trace};
+use tracing::{debug, error, trace, warn};
 use utd::{
-    args::{PriorityLevel, SortParam},
-    data_dir, read_config_file, setup_logger, Config, Configurable, Tags, Task, Tasks,
+    args::{
+        BridgeTool, ChatService, Commands, ContextAction, EntryKind, ExportFormat, GoalAction,
+        GraphFormat, ImportSource, ProjectAction, ReportKind, SortField, SortKey, SortOrder,
+        SyncProvider, TimerAction, View,
+    },
+    bar_chart, chat_payload, checksum, data_dir, dates, depends_on, digest_html, digest_text,
+    edit_distance, focus_report, format_age, format_duration, icon_profile,
+    import::{ListState, TaskwarriorTask},
+    merge_boards, normalise_name, open_counts, parse_colour, parse_entry, parse_priority,
+    parse_project, plan_day, push_tag, read_config_file, record_changes, render_board, replay,
+    resequence, salvage_entries, set_colour_support, set_icon_profile, setup_logger, similar_tag,
+    summary_footer, task_events, title_span, to_chat, to_ics, Annotation, BoardSection,
+    ColourSupport, Config, EntryPatch, Event, Filter, Focus, Goal, IconProfile, JournalEntry,
+    LineKind, Logs, NewEntry, Record, RenderOptions, Span, Style, StyledLine, Tags, Task, Tasks,
+    Template, Title, Translations,
 };
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
 
 fn main() -> Result<()> {
-    let args = utd::args::Cli::parse();
-    // don't drop guard
-    let _guard = setup_logger(args.log.unwrap_or(utd::args::LogLevel::Trace));
+    // aliases are expanded before parsing, so the config is read first
     let config = read_config_file(false)?;
+    if let Some(ref timezone) = config.timezone {
+        dates::set_timezone(timezone)?;
+    }
+    if config.icons() != "auto" {
+        set_icon_profile(config.icons().parse()?);
+    }
+    match config.storage() {
+        "json" => {}
+        "jsonl" => {
+            JSON_LINES.get_or_init(|| true);
+        }
+        other => return Err(format!("expected json or jsonl for storage, found {other}").into()),
+    }
+    let args = utd::args::Cli::parse_with_aliases(&command_aliases(&config));
+    // don't drop guard
+    let log_config = config.logs.as_ref().cloned().unwrap_or_default();
+    let _guard = setup_logger(args.log.unwrap_or(utd::args::LogLevel::Trace), &log_config)?;
+    // check-due exits with 1 when something is due, so failing has to look different
+    if matches!(args.command, Some(Commands::CheckDue { .. })) {
+        if let Err(e) = run(&config, args, &log_config) {
+            eprintln!("Error: {e:?}");
+            // exiting skips the flush on drop
+            drop(_guard);
+            std::process::exit(2);
+        }
+        return Ok(());
+    }
+    run(&config, args, &log_config)
+}
+
+/// Everything after parsing, for the command line or a line typed into `utd shell`
+fn run(config: &Config, mut args: utd::args::Cli, log_config: &Logs) -> Result<()> {
+    // a saved view fills in what the command line leaves out, its filter narrowing further
+    if let Some(Commands::View {
+        name: Some(ref name),
+    }) = args.command
+    {
+        let saved = config.saved_view(name)?;
+        if args.sort.is_none() {
+            args.sort = saved.sort()?;
+        }
+        if args.view.is_none() {
+            args.view = saved.layout()?;
+        }
+        args.filter = match (saved.filter, args.filter.take()) {
+            (Some(saved), Some(filter)) => Some(format!("{saved} {filter}")),
+            (saved, filter) => filter.or(saved),
+        };
+        args.command = None;
+    }
+    if args.no_color || args.plain {
+        set_colour_support(ColourSupport::None);
+    }
+    let tag_config = config.tags.as_ref().cloned().unwrap_or_default();
+    let sort = match args.sort.clone() {
+        Some(sort) => Some(sort),
+        None => config.default_sort()?,
+    };
+    // a leading @tag wins over the context set with `utd context set`
+    let context_tag = match args.context.as_deref() {
+        None => active_context()?,
+        Some(context) => match context.strip_prefix('+') {
+            Some(board) => {
+                if board.is_empty()
+                    || !board
+                        .chars()
+                        .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+                {
+                    return Err(format!("invalid board name {board:?}").into());
+                }
+                if board == "journal" {
+                    open_journal(None)?;
+                } else {
+                    BOARD.get_or_init(|| board.to_owned());
+                }
+                active_context()?
+            }
+            None => match context.strip_prefix(tag_config.prefix()) {
+                Some(tag) if !tag.is_empty() => Some(tag_config.canonical(tag)),
+                _ => {
+                    return Err(format!(
+                        "expected {}tag or +board, found {context}",
+                        tag_config.prefix()
+                    )
+                    .into())
+                }
+            },
+        },
+    };
+    // before anything reads the board, which creates it
+    if let Some(Commands::Journal { day }) = args.command.as_ref() {
+        open_journal(day.as_deref())?;
+    }
+    let project = active_project()?;
+    let backup_config = config.backup.as_ref().cloned().unwrap_or_default();
+    if backup_config.auto() {
+        if let Err(e) = auto_backup(backup_config.keep()) {
+            error!("{e}");
+        }
+    }
+    if let Err(e) = expire_entries(config).and_then(|_| spawn_recurrences()) {
+        error!("{e}");
+    }
+    // entries picked by id or name, to report what changed in case it was the wrong one
+    let reported = args.add.is_some()
+        || args.note.is_some()
+        || args.check.is_some()
+        || args.begin.is_some()
+        || args.delete.is_some();
+    let before = if args.summary || reported || config.webhooks.is_some() {
+        state_file_contents()?
+    } else {
+        Tasks::new()
+    };
+
+    if let Some(ref command) = args.command {
+        match command {
+            Commands::Attach { id, target } => {
+                if let Err(e) = attach(*id, target) {
+                    error!("{e}");
+                }
+            }
+            Commands::Annotate { id, text } => {
+                if let Err(e) = annotate(*id, text) {
+                    error!("{e}");
+                }
+            }
+            Commands::Open { id } => return open_attachment(*id),
+            Commands::Show { id } => return show_task(config, *id),
+            Commands::Focus { id, clear } => return focus(config, *id, *clear),
+            Commands::Timer { action, id } => {
+                if let Err(e) = toggle_timer(*action, *id) {
+                    error!("{e}");
+                }
+            }
+            Commands::Snooze { id, until } => {
+                if let Err(e) = snooze(*id, until) {
+                    error!("{e}");
+                }
+            }
+            Commands::Due { id, date, clear } => {
+                let date = if *clear { None } else { date.as_deref() };
+                if let Err(e) = set_due(*id, date) {
+                    error!("{e}");
+                }
+            }
+            Commands::Split { id, parts } => {
+                if let Err(e) = split_task(config, *id, parts) {
+                    error!("{e}");
+                }
+            }
+            Commands::Convert { id, to } => {
+                if let Err(e) = convert_entry(*id, *to) {
+                    error!("{e}");
+                }
+            }
+            Commands::Merge {
+                first,
+                second: Some(second),
+                name,
+            } => {
+                let merged = match first.parse() {
+                    Ok(first) => merge_tasks(config, first, *second, name.as_deref()),
+                    Err(_) => Err(format!("expected a task id, found {first}").into()),
+                };
+                if let Err(e) = merged {
+                    error!("{e}");
+                }
+            }
+            Commands::Merge {
+                first,
+                second: None,
+                ..
+            } => {
+                if let Err(e) = merge_board(backup_config.keep(), first) {
+                    error!("{e}");
+                }
+            }
+            Commands::Pin { ids } => {
+                if let Err(e) = pin_tasks(ids, true) {
+                    error!("{e}");
+                }
+            }
+            Commands::Unpin { ids } => {
+                if let Err(e) = pin_tasks(ids, false) {
+                    error!("{e}");
+                }
+            }
+            Commands::Stats => return show_stats(),
+            Commands::Tags => return list_tags(),
+            Commands::Projects => return list_projects(),
+            Commands::List { format } => {
+                let mut filter: Filter = args.filter.as_deref().unwrap_or_default().parse()?;
+                for tag in args.tag.iter().flatten() {
+                    let tag = tag_config.canonical(tag.trim_start_matches(tag_config.prefix()));
+                    filter = filter.and(Filter::tag(&tag));
+                }
+                if let Some(tag) = context_tag.as_deref() {
+                    filter = filter.and(Filter::tag(tag));
+                }
+                if let Some(project) = project.as_deref() {
+                    filter = filter.and(Filter::project(project));
+                }
+                return list(config, format, sort.as_ref(), &filter);
+            }
+            Commands::CheckDue { within } => return check_due(within),
+            Commands::Daemon { every, ahead } => return daemon(config, every, ahead),
+            Commands::Serve { port } => return serve(config, *port),
+            Commands::ServeIcs { port } => return serve_ics(*port),
+            Commands::Logs { lines, follow } => return tail_log(log_config, *lines, *follow),
+            Commands::Doctor { repair } => return doctor(*repair),
+            Commands::Paths => return print_paths(log_config),
+            Commands::Shell => return shell(config, log_config),
+            Commands::View { .. } => return list_views(config),
+            Commands::Script { name: None, .. } => {
+                for name in utd::script::script_names() {
+                    println!("{name}");
+                }
+                return Ok(());
+            }
+            Commands::Script {
+                name: Some(name),
+                args,
+            } => return utd::script::run_script(name, &state_file_contents()?, args),
+            Commands::Manpage => {
+                print!("{}", include_str!(concat!(env!("OUT_DIR"), "/utd.1")));
+                return Ok(());
+            }
+            Commands::Backup { list: true } => return list_backups(),
+            Commands::Backup { list: false } => {
+                let path = backup(backup_config.keep())?;
+                println!("backed up to {}", path.display());
+                return Ok(());
+            }
+            Commands::Restore { from } => {
+                if let Err(e) = restore(backup_config.keep(), from) {
+                    error!("{e}");
+                }
+            }
+            Commands::Context { action } => {
+                return match action {
+                    ContextAction::Set { name } => set_context(Some(
+                        &tag_config.canonical(name.trim_start_matches(tag_config.prefix())),
+                    )),
+                    ContextAction::Clear => set_context(None),
+                    ContextAction::Show => {
+                        match context_tag {
+                            Some(ref tag) => println!("{}", tag_config.label(tag)),
+                            None => println!("no context"),
+                        }
+                        Ok(())
+                    }
+                }
+            }
+            Commands::Project { action } => {
+                return match action {
+                    ProjectAction::Switch { name } => {
+                        set_project(Some(name.trim_start_matches('+')))
+                    }
+                    ProjectAction::Clear => set_project(None),
+                    ProjectAction::Stats => project_stats(),
+                }
+            }
+            Commands::Wait {
+                id,
+                on,
+                external_ref,
+                clear,
+            } => {
+                if let Err(e) = wait_on(*id, on.as_deref(), external_ref.as_deref(), *clear) {
+                    error!("{e}");
+                }
+            }
+            Commands::Followup => return follow_up(config),
+            Commands::Review { older_than } => {
+                if let Err(e) = review(config, older_than.as_deref()) {
+                    error!("{e}");
+                }
+            }
+            Commands::Stale { older_than } => {
+                return list_stale(config, older_than.as_deref());
+            }
+            Commands::Next => return suggest_next(config),
+            Commands::Plan { hours, accept } => {
+                if let Err(e) = plan(config, hours, *accept) {
+                    error!("{e}");
+                }
+            }
+            Commands::Dup { id, count } => {
+                if let Err(e) = duplicate(*id, *count) {
+                    error!("{e}");
+                }
+            }
+            Commands::Export {
+                format,
+                output,
+                filter,
+            } => {
+                let mut filter: Filter = filter.as_deref().unwrap_or_default().parse()?;
+                if let Some(tag) = context_tag.as_deref() {
+                    filter = filter.and(Filter::tag(tag));
+                }
+                if let Some(project) = project.as_deref() {
+                    filter = filter.and(Filter::project(project));
+                }
+                return export(config, *format, output.as_deref(), &filter);
+            }
+            Commands::Import { source } => {
+                if let Err(e) = import(config, source) {
+                    error!("{e}");
+                }
+            }
+            Commands::Interrupt { note, id } => {
+                if let Err(e) = interrupt(note, *id) {
+                    error!("{e}");
+                }
+            }
+            Commands::Report { kind, days } => match kind {
+                ReportKind::Focus => return show_focus_report(*days),
+            },
+            Commands::Standup { markdown } => return standup(config, *markdown),
+            Commands::Post {
+                service,
+                webhook_url,
+                standup,
+            } => return post(config, *service, webhook_url.as_deref(), *standup),
+            Commands::Bridge {
+                tool: BridgeTool::Taskwarrior,
+            } => return taskwarrior_hook(config),
+            Commands::Sync { provider } => {
+                if let Err(e) = sync_board(config, *provider) {
+                    error!("{e}");
+                }
+            }
+            Commands::Digest { email, print } => return digest(config, email.as_deref(), *print),
+            Commands::Block { id, on, remove } => {
+                if let Err(e) = block(*id, on, *remove) {
+                    error!("{e}");
+                }
+            }
+            Commands::Graph { format, output } => return graph(*format, output.as_deref()),
+            Commands::Burndown { days } => return show_burndown(*days, args.plain),
+            // the journal was selected as the board, shown like any other
+            Commands::Journal { .. } => {}
+            // moved onto the flags they replace while parsing
+            Commands::Add { .. }
+            | Commands::Note { .. }
+            | Commands::Done { .. }
+            | Commands::Start { .. }
+            | Commands::Rm { .. }
+            | Commands::Tidy => {}
+            Commands::Goal { action } => match action {
+                Some(GoalAction::Set {
+                    name,
+                    target,
+                    by,
+                    tag,
+                }) => {
+                    if let Err(e) = set_goal(config, name, *target, by, tag.as_deref()) {
+                        error!("{e}");
+                    }
+                }
+                Some(GoalAction::Remove { name }) => {
+                    if let Err(e) = remove_goal(name) {
+                        error!("{e}");
+                    }
+                }
+                Some(GoalAction::List) | None => return list_goals(),
+            },
+            Commands::Verify { ids } => {
+                if ids.is_empty() {
+                    return show_review_queue();
+                }
+                if let Err(e) = verify_tasks(config, ids) {
+                    error!("{e}");
+                }
+            }
+        }
+    }
+
+    if let Some(ref period) = args.done {
+        return show_done(period);
+    }
 
     // Adding a new note/task
-    if args.note.is_some() || args.add.is_some() {
-        if let Err(e) = new_entry(&args) {
+    let adding = args.note.is_some() || args.add.is_some();
+    if adding {
+        if let Err(e) = new_entry(config, &args, context_tag.as_deref(), project.as_deref()) {
+            error!("{e}");
+        }
+    }
+    // --tag went to the new entries when adding
+    let selected_tags = if adding {
+        &[][..]
+    } else {
+        args.tag.as_deref().unwrap_or_default()
+    };
+    let selection = match (selected_tags, args.filter.as_deref()) {
+        ([], None) => None,
+        (tags, filter) => {
+            let filter: Filter = filter.unwrap_or_default().parse()?;
+            Some(tags.iter().fold(filter, |filter, tag| {
+                let tag = tag_config.canonical(tag.trim_start_matches(tag_config.prefix()));
+                filter.and(Filter::tag(&tag))
+            }))
+        }
+    };
+    // one bad command shouldn't cost the whole board, even without backups
+    let destructive = args.tidy
+        || args.re_set_ids
+        || args
+            .delete
+            .as_ref()
+            .is_some_and(|ids| ids.len() > 1 || selection.is_some());
+    if destructive {
+        snapshot(backup_config.snapshots())?;
+    }
+    if let Some(ref ids) = args.delete {
+        if let Err(e) =
+            selected_ids(ids, selection.as_ref()).and_then(|ids| delete_entry(&ids, args.yes))
+        {
             error!("{e}");
         }
     }
-    if args.delete.is_some() {
-        if let Err(e) = delete_entry(&args.delete.unwrap()) {
+    if let Some(ref values) = args.cancel {
+        if let Err(e) = cancel_task(&values[0], values.get(1).map(String::as_str)) {
             error!("{e}");
         }
     }
-    if args.begin.is_some() {
-        if let Err(e) = alter_tasks(&args.begin.unwrap(), State::Started) {
+    if let Some(ref ids) = args.begin {
+        if let Err(e) = selected_ids(ids, selection.as_ref())
+            .and_then(|ids| alter_tasks(config, &ids, State::Started, args.strict))
+        {
             println!("uhmmm: {}", e);
             error!("{e}");
         }
     }
-    if args.check.is_some() {
-        if let Err(e) = alter_tasks(&args.check.unwrap(), State::Completed) {
+    if let Some(ref ids) = args.check {
+        if let Err(e) = selected_ids(ids, selection.as_ref())
+            .and_then(|ids| alter_tasks(config, &ids, State::Completed, args.strict))
+        {
             error!("{e}");
         }
     }
+    // before tidying, which removes completed entries
+    if let Err(e) = spawn_recurrences() {
+        error!("{e}");
+    }
+    if let Err(e) = fire_webhooks(config, &before) {
+        error!("{e}");
+    }
     if args.tidy {
-        if let Err(e) = remove_completed() {
+        if let Err(e) = remove_completed(args.yes) {
+            error!("{e}");
+        }
+    }
+    if args.re_set_ids {
+        if let Err(e) = make_ids_sequential(args.yes) {
+            error!("{e}");
+        }
+    } else if config.auto_reid() && (args.tidy || args.delete.is_some()) {
+        if let Err(e) = close_id_gaps() {
+            error!("{e}");
+        }
+    }
+    // --tag/--filter narrow the board unless they selected entries to alter
+    let altered = args.delete.is_some()
+        || args.cancel.is_some()
+        || args.begin.is_some()
+        || args.check.is_some();
+    let selection = selection.filter(|_| !altered);
+    // a leading @tag always narrows the board
+    let view = match (selection, context_tag.as_deref()) {
+        (Some(selection), Some(tag)) => Some(selection.and(Filter::tag(tag))),
+        (None, Some(tag)) => Some(Filter::tag(tag)),
+        (selection, None) => selection,
+    };
+    // so does the project switched to
+    let view = match (view, project.as_deref()) {
+        (Some(view), Some(project)) => Some(view.and(Filter::project(project))),
+        (None, Some(project)) => Some(Filter::project(project)),
+        (view, None) => view,
+    };
+    if args.quiet {
+        return Ok(());
+    }
+    if args.summary {
+        return print_summary(&before);
+    }
+    if reported {
+        if let Err(e) = print_changes(&before) {
             error!("{e}");
         }
-    }
-    if args.re_set_ids {
-        if let Err(e) = make_ids_sequential() {
-            error!("{e}");
+    }
+    let hidden = hidden_sections(config, args.hide_notes, args.only.as_deref());
+    let layout = match args.view {
+        Some(layout) => layout,
+        None => config.default_view()?,
+    };
+    if args.watch {
+        return watch(
+            config,
+            sort.as_ref(),
+            args.show_snoozed,
+            args.plain,
+            view.as_ref(),
+            &hidden,
+            layout,
+        );
+    }
+    if let Err(e) = display_content(
+        config,
+        sort.as_ref(),
+        args.show_snoozed,
+        args.plain,
+        view.as_ref(),
+        &hidden,
+        layout,
+    ) {
+        error!("{e}");
+    }
+    Ok(())
+}
+
+/// Names of the views in `[views]` with what each shows
+fn list_views(config: &Config) -> Result<()> {
+    let mut views: Vec<_> = config.views.iter().flatten().collect();
+    views.sort_by(|a, b| a.0.cmp(b.0));
+    for (name, view) in views {
+        let settings: Vec<String> = [
+            ("filter", view.filter.as_deref()),
+            ("sort", view.sort.as_deref()),
+            ("layout", view.layout.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(key, value)| Some(format!("{key}={:?}", value?)))
+        .collect();
+        println!("{name:<12}{}", settings.join(" "));
+    }
+    Ok(())
+}
+
+/// The `[aliases]` of the config, plus one per script so `utd weekly` runs `weekly.rhai`
+/// unless an alias takes the name
+fn command_aliases(config: &Config) -> HashMap<String, String> {
+    let mut aliases = config.aliases.clone().unwrap_or_default();
+    for name in utd::script::script_names() {
+        aliases
+            .entry(name.clone())
+            .or_insert_with(|| format!("script {name}"));
+    }
+    aliases
+}
+
+/// Runs each line typed as `utd` would, without starting over or reading the config
+/// again. The board stays loaded between commands that leave its file unchanged.
+fn shell(config: &Config, log_config: &Logs) -> Result<()> {
+    if LOADED.set(Mutex::new(None)).is_err() {
+        return Err("already in utd shell".into());
+    }
+    let aliases = command_aliases(config);
+    let mut editor = rustyline::Editor::<ShellHelper>::new()?;
+    editor.set_helper(Some(ShellHelper {
+        words: completions(config)?,
+    }));
+    let history = data_dir().join(".utd-history");
+    // there is none before the first session
+    let _ = editor.load_history(&history);
+    loop {
+        let line = match editor.readline("utd> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => continue,
+            Err(ReadlineError::Eof) => break,
+            Err(e) => return Err(e.into()),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        editor.add_history_entry(line);
+        if matches!(line, "exit" | "quit") {
+            break;
+        }
+        if line.starts_with('+') {
+            eprintln!("error: pick the board when starting the shell e.g. `utd +home shell`");
+            continue;
+        }
+        match utd::args::Cli::try_parse_line(line, &aliases) {
+            Ok(args) => {
+                if let Err(e) = run(config, args, log_config) {
+                    eprintln!("error: {e}");
+                }
+            }
+            // --help and --version end up here too
+            Err(e) => {
+                let _ = e.print();
+            }
+        }
+        // so tags added since complete as well
+        if let Some(helper) = editor.helper_mut() {
+            helper.words = completions(config)?;
+        }
+    }
+    editor.save_history(&history)?;
+    Ok(())
+}
+
+/// Subcommands, long flags, aliases and the tags on the board, for `utd shell` to complete
+fn completions(config: &Config) -> Result<Vec<String>> {
+    let command = utd::args::Cli::command();
+    let tag_config = config.tags.as_ref().cloned().unwrap_or_default();
+    let mut words: Vec<String> = command
+        .get_subcommands()
+        .flat_map(|f| std::iter::once(f.get_name()).chain(f.get_all_aliases()))
+        .map(str::to_owned)
+        .chain(
+            command
+                .get_arguments()
+                .filter_map(|f| f.get_long())
+                .map(|f| format!("--{f}")),
+        )
+        .chain(command_aliases(config).into_keys())
+        .chain(
+            state_file_contents()?
+                .into_iter()
+                .flat_map(|f| f.tags)
+                .map(|f| format!("{}{f}", tag_config.prefix())),
+        )
+        .chain([String::from("exit")])
+        .collect();
+    words.sort();
+    words.dedup();
+    Ok(words)
+}
+
+/// Completes the word before the cursor in `utd shell`
+struct ShellHelper {
+    words: Vec<String>,
+}
+
+impl rustyline::completion::Completer for ShellHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |f| f + 1);
+        let word = &line[start..pos];
+        let candidates = self
+            .words
+            .iter()
+            .filter(|f| f.starts_with(word))
+            .cloned()
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl rustyline::hint::Hinter for ShellHelper {
+    type Hint = String;
+}
+
+impl rustyline::highlight::Highlighter for ShellHelper {}
+
+impl rustyline::validate::Validator for ShellHelper {}
+
+impl rustyline::Helper for ShellHelper {}
+
+/// One line such as `added 2 tasks, completed 1 task, 5 open`
+fn print_summary(before: &[Task]) -> Result<()> {
+    let after = state_file_contents()?;
+    // timestamps identify entries across id changes
+    let existed = |task: &Task| before.iter().find(|f| f.timestamp == task.timestamp);
+    let count = |n: usize, noun: &str, nouns: &str| match n {
+        1 => format!("1 {noun}"),
+        n => format!("{n} {nouns}"),
+    };
+    let mut changes = Vec::new();
+    let added_tasks = after
+        .iter()
+        .filter(|f| f.is_task && existed(f).is_none())
+        .count();
+    let added_notes = after
+        .iter()
+        .filter(|f| !f.is_task && existed(f).is_none())
+        .count();
+    if added_tasks > 0 {
+        changes.push(format!("added {}", count(added_tasks, "task", "tasks")));
+    }
+    if added_notes > 0 {
+        changes.push(format!("added {}", count(added_notes, "note", "notes")));
+    }
+    let completed = after
+        .iter()
+        .filter(|f| f.is_done && existed(f).is_some_and(|b| !b.is_done))
+        .count();
+    if completed > 0 {
+        changes.push(format!("completed {}", count(completed, "task", "tasks")));
+    }
+    let started = after
+        .iter()
+        .filter(|f| f.in_progress && existed(f).is_some_and(|b| !b.in_progress))
+        .count();
+    if started > 0 {
+        changes.push(format!("started {}", count(started, "task", "tasks")));
+    }
+    let removed = before
+        .iter()
+        .filter(|b| !after.iter().any(|f| f.timestamp == b.timestamp))
+        .count();
+    if removed > 0 {
+        changes.push(format!("removed {}", count(removed, "entry", "entries")));
+    }
+    let open = after
+        .iter()
+        .filter(|f| f.is_task && !f.is_done && !f.expired)
+        .count();
+    changes.push(format!("{open} open"));
+    println!("{}", changes.join(", "));
+    Ok(())
+}
+
+/// One coloured line per entry added, removed or moved between open, in progress and done,
+/// such as `~ 3. buy milk: open -> done`
+fn print_changes(before: &[Task]) -> Result<()> {
+    let after = state_file_contents()?;
+    let state = |task: &Task| match (task.is_done, task.in_progress) {
+        (true, _) => "done",
+        (false, true) => "in progress",
+        (false, false) => "open",
+    };
+    let paint = |hex: &str, text: String| {
+        let style = Style {
+            colour: parse_colour(hex).ok(),
+            ..Style::default()
+        };
+        style.to_ansi().paint(text).to_string()
+    };
+    for task in after.iter() {
+        // timestamps identify entries across id changes
+        match before.iter().find(|f| f.timestamp == task.timestamp) {
+            None => println!(
+                "{}",
+                paint("#b8bb26", format!("+ {}. {}", task.id, task.name))
+            ),
+            Some(old) if state(old) != state(task) => {
+                let colour = match state(task) {
+                    "done" => "#8ec07c",
+                    "in progress" => "#fabd2f",
+                    _ => "#83a598",
+                };
+                println!(
+                    "{}",
+                    paint(
+                        colour,
+                        format!(
+                            "~ {}. {}: {} -> {}",
+                            task.id,
+                            task.name,
+                            state(old),
+                            state(task)
+                        )
+                    )
+                );
+            }
+            Some(_) => {}
+        }
+    }
+    for task in before
+        .iter()
+        .filter(|b| !after.iter().any(|f| f.timestamp == b.timestamp))
+    {
+        println!(
+            "{}",
+            paint("#fb4934", format!("- {}. {}", task.id, task.name))
+        );
+    }
+    Ok(())
+}
+
+/// Posts each entry added, completed or started since `before` to its `[webhooks]` URL
+fn fire_webhooks(config: &Config, before: &[Task]) -> Result<()> {
+    let webhooks = match config.webhooks {
+        Some(ref webhooks) => webhooks,
+        None => return Ok(()),
+    };
+    let after = state_file_contents()?;
+    for (event, task) in task_events(before, &after) {
+        if let Some(url) = webhooks.url(event) {
+            let payload = serde_json::json!({ "event": event.name(), "task": task });
+            match ureq::post(url)
+                .timeout(std::time::Duration::from_secs(5))
+                .send_json(payload)
+            {
+                Ok(_) => debug!("{} webhook sent for task {}", event.name(), task.id),
+                Err(e) => warn!("{} webhook for task {} failed: {e}", event.name(), task.id),
+            }
+        }
+    }
+    Ok(())
+}
+
+fn display_content(
+    config: &Config,
+    args: Option<&SortOrder>,
+    show_snoozed: bool,
+    plain: bool,
+    filter: Option<&Filter>,
+    hidden: &[BoardSection],
+    layout: View,
+) -> Result<()> {
+    match board_output(config, args, show_snoozed, plain, filter, hidden, layout)? {
+        Some(output) => print_paged(config, &output),
+        None => Ok(()),
+    }
+}
+
+/// Sections left off the board by `--hide-notes` or `--only`, or else by the config
+fn hidden_sections(config: &Config, hide_notes: bool, only: Option<&str>) -> Vec<BoardSection> {
+    let only = only.or(config.only.as_deref()).and_then(|f| {
+        f.parse::<BoardSection>()
+            .map_err(|e| eprintln!("warning: {e}"))
+            .ok()
+    });
+    let hide_notes = hide_notes || config.hide_notes.unwrap_or(false);
+    BoardSection::ALL
+        .into_iter()
+        .filter(|f| match only {
+            Some(only) => *f != only,
+            None => hide_notes && *f == BoardSection::Notes,
+        })
+        .collect()
+}
+
+/// The board as printed, or nothing when it has no entries
+fn board_output(
+    config: &Config,
+    args: Option<&SortOrder>,
+    show_snoozed: bool,
+    plain: bool,
+    filter: Option<&Filter>,
+    hidden: &[BoardSection],
+    layout: View,
+) -> Result<Option<String>> {
+    let tasks = if let Some(sort) = args {
+        order_tasks(config, sort)?
+    } else {
+        state_file_contents()?
+    };
+    let tasks: Tasks = tasks
+        .into_iter()
+        .filter(|f| match filter {
+            Some(filter) => filter.matches(f),
+            None => true,
+        })
+        .collect();
+    let sections = config.sections.as_ref().cloned().unwrap_or_default();
+    let title = sections.title.unwrap_or_default();
+    let mut lines = render_board(
+        &tasks,
+        config,
+        RenderOptions {
+            title: Some(greeting(&title, &config.translations())),
+            show_snoozed,
+            plain,
+            // kanban columns share the width
+            width: board_width().map(|f| match layout {
+                View::Board => f,
+                View::Kanban => f / (BoardSection::ALL.len() - hidden.len()).max(1),
+            }),
+            goals: goals_file_contents()?,
+            hidden: hidden.to_vec(),
+        },
+    );
+    if config.footer.unwrap_or(false) {
+        lines.extend(summary_footer(&tasks, config, plain));
+    }
+    if plain {
+        let output: Vec<String> = lines.iter().map(|f| f.to_plain_string()).collect();
+        return Ok(Some(output.join("\n")));
+    }
+    let mut table = TableBuilder::new()
+        .style(
+            match config
+                .borders
+                .as_ref()
+                .cloned()
+                .unwrap_or_else(|| "empty".to_string())
+                .as_str()
+            {
+                "elegant" => TableStyle::extended(),
+                "extended" => TableStyle::elegant(),
+                "empty" => TableStyle::empty(),
+                _ => unreachable!(),
+            },
+        )
+        .build();
+    if layout == View::Kanban {
+        return Ok(kanban_output(table, &lines));
+    }
+    // lines without cells span every column of a columns layout
+    let columns = lines
+        .iter()
+        .map(|f| f.cells.len())
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for line in lines.iter() {
+        match line.kind {
+            LineKind::Title | LineKind::Footer => {
+                table.add_row(Row::new(vec![TableCell::new_with_alignment(
+                    line.to_ansi_string(),
+                    columns.max(2),
+                    Alignment::Center,
+                )]))
+            }
+            _ if !line.cells.is_empty() => table.add_row(Row::new(
+                line.cell_strings().into_iter().map(TableCell::new),
+            )),
+            _ => table.add_row(Row::new(vec![TableCell::new_with_alignment(
+                line.to_ansi_string(),
+                columns,
+                Alignment::Left,
+            )])),
+        }
+    }
+
+    if lines.iter().any(|f| f.kind == LineKind::Entry) {
+        Ok(Some(table.render()))
+    } else {
+        Ok(None)
+    }
+}
+
+/// The sections of the board side by side between the title and the footer
+fn kanban_output(mut table: term_table::Table<'_>, lines: &[StyledLine]) -> Option<String> {
+    let mut sections: Vec<Vec<String>> = Vec::new();
+    for line in lines.iter() {
+        match line.kind {
+            LineKind::Heading => sections.push(vec![line.to_ansi_string()]),
+            LineKind::Entry => match sections.last_mut() {
+                Some(section) => section.push(line.to_ansi_string()),
+                None => sections.push(vec![line.to_ansi_string()]),
+            },
+            LineKind::Title | LineKind::Footer => {}
+        }
+    }
+    if sections.is_empty() {
+        return None;
+    }
+    let banner = |kind: LineKind| {
+        lines
+            .iter()
+            .filter(|f| f.kind == kind)
+            .map(|f| {
+                Row::new(vec![TableCell::new_with_alignment(
+                    f.to_ansi_string(),
+                    sections.len(),
+                    Alignment::Center,
+                )])
+            })
+            .collect::<Vec<_>>()
+    };
+    for row in banner(LineKind::Title) {
+        table.add_row(row);
+    }
+    table.add_row(Row::new(
+        sections.iter().map(|f| TableCell::new(f.join("\n"))),
+    ));
+    for row in banner(LineKind::Footer) {
+        table.add_row(row);
+    }
+    Some(table.render())
+}
+
+/// Redraws the board whenever the board file changes or the terminal is resized,
+/// until interrupted
+fn watch(
+    config: &Config,
+    args: Option<&SortOrder>,
+    show_snoozed: bool,
+    plain: bool,
+    filter: Option<&Filter>,
+    hidden: &[BoardSection],
+    layout: View,
+) -> Result<()> {
+    use notify::Watcher;
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = sender.send(event);
+    })?;
+    // writes replace the board file, so its directory is watched rather than the file
+    watcher.watch(&data_dir(), notify::RecursiveMode::NonRecursive)?;
+    let board = state_path();
+    let size = || terminal_size::terminal_size().map(|(w, h)| (w.0, h.0));
+    let mut last_size = size();
+    loop {
+        print!("\x1b[2J\x1b[H");
+        if let Some(output) =
+            board_output(config, args, show_snoozed, plain, filter, hidden, layout)?
+        {
+            println!("{output}");
+        }
+        std::io::stdout().flush()?;
+        loop {
+            match receiver.recv_timeout(std::time::Duration::from_millis(250)) {
+                Ok(Ok(notify::Event { paths, .. }))
+                    if paths.iter().any(|f| f.file_name() == board.file_name()) =>
+                {
+                    break
+                }
+                Ok(_) => {}
+                Err(std::sync::mpsc::RecvTimeoutError::Timeout) => {
+                    if size() != last_size {
+                        last_size = size();
+                        break;
+                    }
+                }
+                Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+    }
+}
+
+/// Prints `output`, through `$PAGER` when it is taller than the terminal or paging is
+/// always on
+fn print_paged(config: &Config, output: &str) -> Result<()> {
+    let page = match config.paging() {
+        "always" => true,
+        "never" => false,
+        _ => match terminal_size::terminal_size() {
+            Some((_, terminal_size::Height(height))) if std::io::stdout().is_terminal() => {
+                output.lines().count() >= usize::from(height)
+            }
+            _ => false,
+        },
+    };
+    if !page {
+        println!("{output}");
+        return Ok(());
+    }
+    let pager = std::env::var("PAGER").unwrap_or_else(|_| String::from("less"));
+    let mut words = pager.split_whitespace();
+    let program = words.next().unwrap_or("less");
+    let spawned = std::process::Command::new(program)
+        .args(words)
+        // like git: keep colours, quit if it fits after all
+        .env(
+            "LESS",
+            std::env::var("LESS").unwrap_or_else(|_| String::from("FRX")),
+        )
+        .stdin(std::process::Stdio::piped())
+        .spawn();
+    match spawned {
+        Ok(mut child) => {
+            if let Some(mut stdin) = child.stdin.take() {
+                // the pager may be quit before reading everything
+                let _ = writeln!(stdin, "{output}");
+            }
+            child.wait()?;
+        }
+        Err(e) => {
+            debug!("could not start pager {}: {}", pager, e);
+            println!("{output}");
+        }
+    }
+    Ok(())
+}
+
+/// Columns left for entries once the table borders and padding are drawn
+fn board_width() -> Option<usize> {
+    let columns = match terminal_size::terminal_size() {
+        Some((terminal_size::Width(width), _)) => usize::from(width),
+        None => std::env::var("COLUMNS").ok()?.parse().ok()?,
+    };
+    Some(columns.saturating_sub(4))
+}
+
+fn order_tasks(config: &Config, sort: &SortOrder) -> Result<Tasks> {
+    let mut tasks = state_file_contents()?;
+    tasks.sort_by(|a, b| {
+        sort.0.iter().fold(std::cmp::Ordering::Equal, |order, key| {
+            order.then_with(|| compare_by(config, key, a, b))
+        })
+    });
+    Ok(tasks)
+}
+
+/// Orders two entries by one key of a sort order
+fn compare_by(config: &Config, key: &SortKey, a: &Task, b: &Task) -> std::cmp::Ordering {
+    let order = match key.field {
+        // the youngest first
+        SortField::Age => b.timestamp().cmp(&a.timestamp()),
+        SortField::Priority => a.priority_score(config).cmp(&b.priority_score(config)),
+        SortField::Due => match (a.due, b.due) {
+            (Some(a), Some(b)) => a.cmp(&b),
+            (Some(_), None) => return std::cmp::Ordering::Less,
+            (None, Some(_)) => return std::cmp::Ordering::Greater,
+            (None, None) => std::cmp::Ordering::Equal,
+        },
+        SortField::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+        SortField::Id => a.id.cmp(&b.id),
+    };
+    if key.descending {
+        order.reverse()
+    } else {
+        order
+    }
+}
+
+fn make_ids_sequential(yes: bool) -> Result<()> {
+    let tasks = state_file_contents()?;
+    let mut c_tasks = tasks.clone();
+    resequence(&mut c_tasks);
+    let renumbered: Vec<String> = tasks
+        .iter()
+        .zip(c_tasks.iter())
+        .filter(|(old, new)| old.id != new.id)
+        .map(|(old, new)| format!("{} -> {}. {}", old.id, new.id, new.name))
+        .collect();
+    if renumbered.is_empty() || !confirm("Renumber these entries?", &renumbered, yes)? {
+        return Ok(());
+    }
+    update_file(&c_tasks)?;
+    Ok(())
+}
+
+/// Closes the gaps deletes and tidying leave in the ids, for boards with `auto-reid` set
+fn close_id_gaps() -> Result<()> {
+    let tasks = state_file_contents()?;
+    let mut resequenced = tasks.clone();
+    resequence(&mut resequenced);
+    if tasks
+        .iter()
+        .zip(resequenced.iter())
+        .all(|(old, new)| old.id == new.id)
+    {
+        return Ok(());
+    }
+    update_file(&resequenced)?;
+    debug!("ids resequenced - ok");
+    Ok(())
+}
+
+/// Asks before a destructive change to `affected`, when there is someone at the terminal
+/// to answer. Anything but yes declines.
+fn confirm(question: &str, affected: &[String], yes: bool) -> Result<bool> {
+    if yes || !std::io::stdin().is_terminal() {
+        return Ok(true);
+    }
+    for line in affected {
+        println!("  {line}");
+    }
+    print!("{question} [y/N] ");
+    std::io::stdout().flush()?;
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input)?;
+    let confirmed = matches!(input.trim(), "y" | "Y" | "yes");
+    if !confirmed {
+        println!("Nothing changed");
+    }
+    Ok(confirmed)
+}
+
+/// Expires entries past their date, and archives those expired for longer than
+/// `[expired] archive-after`
+fn expire_entries(config: &Config) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let mut expired = 0;
+    for task in tasks.iter_mut() {
+        if task.expire_if_due() {
+            expired += 1;
+        }
+    }
+    let expired_config = config.expired.as_ref().cloned().unwrap_or_default();
+    let cutoff = chrono::Utc::now() - dates::parse_offset(expired_config.archive_after())?;
+    let (archived, tasks): (Tasks, Tasks) = tasks
+        .into_iter()
+        .partition(|f| f.expired && f.expires_at.is_some_and(|at| at <= cutoff));
+    // kept for stats, like tidied entries
+    if !archived.is_empty() {
+        archive_entries(&archived)?;
+        debug!("{} expired entries archived", archived.len());
+    }
+    if expired > 0 || !archived.is_empty() {
+        update_file(&tasks)?;
+        debug!("{} entries expired", expired);
+    }
+    Ok(())
+}
+
+/// Adds the next occurrence of each completed recurring task
+fn spawn_recurrences() -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let mut id = tasks.iter().map(|f| f.id).max().unwrap_or(0);
+    let mut spawned = Vec::new();
+    let mut records = Vec::new();
+    for task in tasks.iter_mut() {
+        if let Some(next) = task.next_occurrence(id + 1, timestamp().as_nanos()) {
+            id += 1;
+            task.recurred = true;
+            records.push(Record::Put { task: task.clone() });
+            spawned.push(next);
+        }
+    }
+    if !spawned.is_empty() {
+        debug!("{} recurring tasks added", spawned.len());
+        records.extend(spawned.iter().map(|f| Record::Put { task: f.clone() }));
+        tasks.extend(spawned);
+        save_records(&tasks, &records)?;
+    }
+    Ok(())
+}
+
+/// Looks at the board every `every` until interrupted: expires entries, adds recurring
+/// tasks and notifies of tasks due within `ahead`, overdue or back from a snooze
+fn daemon(config: &Config, every: &str, ahead: &str) -> Result<()> {
+    let every = dates::parse_offset(every)?;
+    let ahead = dates::parse_offset(ahead)?;
+    let pause = every.to_std()?;
+    // each reminder is sent once while the daemon runs
+    let mut notified: HashSet<(String, &str)> = Default::default();
+    let mut last_check = chrono::Utc::now() - every;
+    loop {
+        if let Err(e) = expire_entries(config).and_then(|_| spawn_recurrences()) {
+            error!("{e}");
+        }
+        let now = chrono::Utc::now();
+        for task in state_file_contents()?
+            .iter()
+            .filter(|f| f.is_task && !f.is_done && !f.expired)
+        {
+            let reminder = match (task.due, task.snoozed_until) {
+                (Some(due), _) if due <= now => Some((
+                    "overdue",
+                    format!("overdue since {}", dates::format_date(&due)),
+                )),
+                (Some(due), _) if due - now <= ahead => {
+                    Some(("due", format!("due {}", dates::format_date(&due))))
+                }
+                (_, Some(until)) if until <= now && until > last_check => {
+                    Some(("snooze", String::from("back from snooze")))
+                }
+                _ => None,
+            };
+            if let Some((kind, body)) = reminder {
+                if notified.insert((task.timestamp.to_owned(), kind)) {
+                    desktop_notification(task, &body);
+                }
+            }
+        }
+        last_check = now;
+        std::thread::sleep(pause);
+    }
+}
+
+fn desktop_notification(task: &Task, body: &str) {
+    let shown = notify_rust::Notification::new()
+        .appname("utd")
+        .summary(&format!("{}. {}", task.id, task.name))
+        .body(body)
+        .show();
+    match shown {
+        Ok(_) => debug!("notified of task {}: {}", task.id, body),
+        Err(e) => warn!("notification for task {} failed: {e}", task.id),
+    }
+}
+
+/// For scripts and prompts: exits with 1 when open tasks are due within `within`, and
+/// with 2 when it can't tell
+fn check_due(within: &str) -> Result<()> {
+    let until = chrono::Utc::now() + dates::parse_offset(within)?;
+    let due = state_file_contents()?
+        .iter()
+        .filter(|f| f.is_task && !f.is_done && !f.expired)
+        .filter(|f| matches!(f.due, Some(due) if due <= until))
+        .count();
+    if due > 0 {
+        println!("{due}");
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn list(config: &Config, format: &str, sort: Option<&SortOrder>, filter: &Filter) -> Result<()> {
+    let template: Template = format.parse()?;
+    let tags = config.tags.as_ref().cloned().unwrap_or_default();
+    let tasks = match sort {
+        Some(sort) => order_tasks(config, sort)?,
+        None => state_file_contents()?,
+    };
+    for task in tasks.iter().filter(|f| filter.matches(f)) {
+        println!("{}", template.render(task, &tags));
+    }
+    Ok(())
+}
+
+/// First line printed by `command` run through the shell
+fn title_command(command: &str) -> Result<String> {
+    let output = if cfg!(windows) {
+        std::process::Command::new("cmd")
+            .args(["/C", command])
+            .output()?
+    } else {
+        std::process::Command::new("sh")
+            .args(["-c", command])
+            .output()?
+    };
+    if !output.status.success() {
+        return Err(format!("{command:?} exited with {}", output.status).into());
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Ok(stdout.lines().next().unwrap_or_default().trim().to_owned())
+}
+
+/// Tags in use on the board, one per line
+fn list_tags() -> Result<()> {
+    let mut tags: Vec<String> = state_file_contents()?
+        .into_iter()
+        .flat_map(|f| f.tags)
+        .collect();
+    tags.sort();
+    tags.dedup();
+    for tag in tags {
+        println!("{tag}");
+    }
+    Ok(())
+}
+
+/// Each project with its done and total tasks, counting those tidied into the archive
+fn list_projects() -> Result<()> {
+    let tasks = state_file_contents()?;
+    let archived = archive_file_contents()?;
+    let mut projects: Vec<(&str, usize, usize)> = Vec::new();
+    for task in tasks.iter().chain(archived.iter()).filter(|f| f.is_task) {
+        let project = match task.project {
+            Some(ref project) => project.as_str(),
+            None => continue,
+        };
+        let position = match projects.iter().position(|f| f.0 == project) {
+            Some(position) => position,
+            None => {
+                projects.push((project, 0, 0));
+                projects.len() - 1
+            }
+        };
+        projects[position].1 += usize::from(task.is_done);
+        projects[position].2 += 1;
+    }
+    projects.sort_by_key(|f| f.0);
+    let width = projects.iter().map(|f| f.0.len()).max().unwrap_or(0);
+    for (project, done, total) in projects {
+        println!(
+            "{:<width$}  {}/{} done ({}%)",
+            project,
+            done,
+            total,
+            done * 100 / total
+        );
+    }
+    Ok(())
+}
+
+/// Open and done tasks of each project and how long its finished ones took on average,
+/// from being added to being completed
+fn project_stats() -> Result<()> {
+    let tasks = state_file_contents()?;
+    let archived = archive_file_contents()?;
+    let mut names: Vec<&str> = tasks
+        .iter()
+        .chain(archived.iter())
+        .filter_map(|f| f.project.as_deref())
+        .collect();
+    names.sort_unstable();
+    names.dedup();
+    if names.is_empty() {
+        println!("no projects");
+        return Ok(());
+    }
+    let width = names.iter().map(|f| f.len()).max().unwrap_or(0).max(7);
+    println!(
+        "{:<width$}  {:>5}  {:>5}  cycle time",
+        "project", "open", "done"
+    );
+    for name in names {
+        let project: Vec<&Task> = tasks
+            .iter()
+            .chain(archived.iter())
+            .filter(|f| f.is_task && f.project.as_deref() == Some(name))
+            .collect();
+        let open = project
+            .iter()
+            .filter(|f| !f.is_done && !f.expired && !f.cancelled)
+            .count();
+        let cycles: Vec<i64> = project
+            .iter()
+            .filter(|f| f.is_done)
+            .filter_map(|f| Some((f.completed_at? - f.created()).num_seconds().max(0)))
+            .collect();
+        let done = project.iter().filter(|f| f.is_done).count();
+        let cycle = match cycles.len() {
+            0 => String::from("-"),
+            n => format_age((cycles.iter().sum::<i64>() / n as i64) as u64),
+        };
+        println!("{name:<width$}  {open:>5}  {done:>5}  {cycle}");
+    }
+    Ok(())
+}
+
+fn show_stats() -> Result<()> {
+    let tasks = state_file_contents()?;
+    let archived = archive_file_contents()?;
+    let board: Vec<&Task> = tasks.iter().filter(|f| f.is_task).collect();
+    let history: Vec<&Task> = tasks
+        .iter()
+        .chain(archived.iter())
+        .filter(|f| f.is_task)
+        .collect();
+    let open = board
+        .iter()
+        .filter(|f| !f.is_done && !f.in_progress && !f.expired)
+        .count();
+    let in_progress = board.iter().filter(|f| f.in_progress).count();
+    let done = history.iter().filter(|f| f.is_done).count();
+    let expired = history.iter().filter(|f| f.expired).count();
+    let cancelled = history.iter().filter(|f| f.cancelled).count();
+    println!("{:<12}{}", "open", open);
+    println!("{:<12}{}", "in progress", in_progress);
+    println!("{:<12}{}", "done", done);
+    println!("{:<12}{}", "expired", expired);
+    println!("{:<12}{}", "cancelled", cancelled);
+    println!(
+        "{:<12}{}",
+        "notes",
+        tasks.iter().filter(|f| !f.is_task).count()
+    );
+    let goals = goals_file_contents()?;
+    if !goals.is_empty() {
+        println!();
+        list_goals()?;
+    }
+    Ok(())
+}
+
+fn remove_completed(yes: bool) -> Result<()> {
+    let (removed, tasks): (Tasks, Tasks) = state_file_contents()?
+        .into_iter()
+        .partition(|f| f.is_done || f.expired);
+    let affected: Vec<String> = removed
+        .iter()
+        .map(|f| format!("{}. {}", f.id, f.name))
+        .collect();
+    if !affected.is_empty() && !confirm("Clear these entries?", &affected, yes)? {
+        return Ok(());
+    }
+    // kept for stats and `--done`
+    if !removed.is_empty() {
+        archive_entries(&removed)?;
+    }
+    // a JSON Lines board drops the history of its entries when tidied
+    if json_lines() {
+        compact_board(&tasks)?;
+    } else {
+        update_file(&tasks)?;
+    }
+    Ok(())
+}
+
+/// Tasks completed during `period`: today, yesterday or week (the last seven days)
+fn show_done(period: &str) -> Result<()> {
+    // days are counted from local midnights, which aren't always 24h apart
+    let day = dates::today();
+    let midnight = |days| dates::local_midnight(day - chrono::Duration::days(days));
+    let (from, to) = match period {
+        "today" => (midnight(0)?, chrono::Utc::now()),
+        "yesterday" => (midnight(1)?, midnight(0)?),
+        "week" => (midnight(6)?, chrono::Utc::now()),
+        _ => return Err(format!("expected today, yesterday or week, found {period}").into()),
+    };
+    for task in completed_between(from, to)? {
+        if let Some(ref at) = task.completed_at {
+            println!("{}  {}. {}", dates::format_date(at), task.id, task.name);
+        }
+    }
+    Ok(())
+}
+
+/// Tasks completed in `[from, to)`, including archived ones, earliest first
+fn completed_between(
+    from: chrono::DateTime<chrono::Utc>,
+    to: chrono::DateTime<chrono::Utc>,
+) -> Result<Tasks> {
+    let mut done: Tasks = state_file_contents()?
+        .into_iter()
+        .chain(archive_file_contents()?)
+        .filter(|f| matches!(f.completed_at, Some(at) if at >= from && at < to))
+        .collect();
+    done.sort_by_key(|f| f.completed_at);
+    Ok(done)
+}
+
+/// What was done since the last working day, what is next and what is blocked
+fn standup(config: &Config, markdown: bool) -> Result<()> {
+    let none = config.translations().get("none");
+    for (heading, items) in standup_sections(config)? {
+        if markdown {
+            println!("**{heading}**");
+            if items.is_empty() {
+                println!("- {none}");
+            }
+            for item in items {
+                println!("- {item}");
+            }
+            println!();
+        } else if items.is_empty() {
+            println!("{heading}: {none}");
+        } else {
+            println!("{heading}: {}", items.join(", "));
+        }
+    }
+    Ok(())
+}
+
+/// What was finished since the last working day, what is next and what is in the way
+fn standup_sections(config: &Config) -> Result<Vec<(String, Vec<String>)>> {
+    let today = dates::today();
+    // on Mondays, yesterday was Friday
+    let since = match today.weekday() {
+        chrono::Weekday::Mon => today - chrono::Duration::days(3),
+        _ => today - chrono::Duration::days(1),
+    };
+    let yesterday: Vec<String> =
+        completed_between(dates::local_midnight(since)?, chrono::Utc::now())?
+            .into_iter()
+            .map(|f| f.name)
+            .collect();
+    let tasks = state_file_contents()?;
+    let open: Vec<&Task> = tasks
+        .iter()
+        .filter(|f| f.is_task && !f.is_done && !f.expired && !f.is_snoozed())
+        .collect();
+    let top = open
+        .iter()
+        .filter(|f| !f.in_progress && !f.is_waiting())
+        .map(|f| f.priority_score(config))
+        .max();
+    let mut next: Vec<&Task> = open.iter().copied().filter(|f| f.in_progress).collect();
+    next.extend(
+        open.iter()
+            .filter(|f| !f.in_progress && !f.is_waiting())
+            .filter(|f| Some(f.priority_score(config)) == top)
+            .take(3),
+    );
+    let doing: Vec<String> = next.iter().map(|f| f.name.to_owned()).collect();
+    let mut blockers: Vec<String> = open
+        .iter()
+        .filter(|f| f.is_waiting())
+        .map(|f| match f.assignee {
+            Some(ref who) => format!("{} (waiting on {})", f.name, who),
+            None => format!("{} (waiting)", f.name),
+        })
+        .collect();
+    blockers.extend(open.iter().filter(|f| !f.is_waiting()).filter_map(|f| {
+        let names: Vec<&str> = f.blockers(&tasks).iter().map(|f| f.name.as_str()).collect();
+        (!names.is_empty()).then(|| format!("{} (blocked by {})", f.name, names.join(", ")))
+    }));
+    let translations = config.translations();
+    Ok(vec![
+        (translations.get("yesterday"), yesterday),
+        (translations.get("today"), doing),
+        (translations.get("blockers"), blockers),
+    ])
+}
+
+/// Emails the overdue, due today and in progress tasks through the `[smtp]` server,
+/// sending nothing when there are none
+fn digest(config: &Config, email: Option<&str>, print: bool) -> Result<()> {
+    let translations = config.translations();
+    let tasks = state_file_contents()?;
+    let open: Vec<&Task> = tasks
+        .iter()
+        .filter(|f| f.is_task && !f.is_done && !f.expired)
+        .collect();
+    let lines = |keep: &dyn Fn(&Task) -> bool| -> Vec<String> {
+        open.iter()
+            .filter(|f| keep(f))
+            .map(|f| match f.due {
+                Some(ref due) => format!("{}. {} (due {})", f.id, f.name, dates::format_date(due)),
+                None => format!("{}. {}", f.id, f.name),
+            })
+            .collect()
+    };
+    let sections = vec![
+        (translations.get("overdue"), lines(&|f| f.is_overdue())),
+        (translations.get("due-today"), lines(&|f| f.is_due_today())),
+        (translations.get("in-progress"), lines(&|f| f.in_progress)),
+    ];
+    let text = digest_text(&sections);
+    if print {
+        print!("{text}");
+        return Ok(());
+    }
+    if text.is_empty() {
+        debug!("nothing to send in the digest");
+        return Ok(());
+    }
+    let smtp = config.smtp.as_ref().cloned().unwrap_or_default();
+    let to = email
+        .or(smtp.to.as_deref())
+        .ok_or("no recipient, pass --email or set `to` under [smtp]")?;
+    let host = smtp
+        .host
+        .as_deref()
+        .ok_or("set the mail server under [smtp]")?;
+    let from = smtp
+        .from
+        .as_deref()
+        .or(smtp.user.as_deref())
+        .ok_or("set `from` under [smtp]")?;
+    let subject = format!("utd: {} open tasks, {}", open.len(), dates::today());
+    let message = lettre::Message::builder()
+        .from(from.parse()?)
+        .to(to.parse()?)
+        .subject(subject)
+        .multipart(lettre::message::MultiPart::alternative_plain_html(
+            text,
+            digest_html(&sections),
+        ))?;
+    let mut transport = match smtp.security() {
+        "starttls" => lettre::SmtpTransport::starttls_relay(host)?,
+        "tls" => lettre::SmtpTransport::relay(host)?,
+        "none" => lettre::SmtpTransport::builder_dangerous(host),
+        other => {
+            return Err(
+                format!("expected starttls, tls or none for security, found {other}").into(),
+            )
+        }
+    };
+    if let Some(port) = smtp.port {
+        transport = transport.port(port);
+    }
+    if let (Some(user), Some(password)) = (smtp.user.clone(), smtp.password()) {
+        transport = transport.credentials(
+            lettre::transport::smtp::authentication::Credentials::new(user, password),
+        );
+    }
+    lettre::Transport::send(&transport.build(), &message)?;
+    debug!("digest sent to {to}");
+    Ok(())
+}
+
+/// Runs as a Taskwarrior on-add or on-modify hook, which pass the task (on-modify the
+/// original first) as JSON lines on stdin and expect it back on stdout. The task is always
+/// handed back, so a failure to mirror it never holds Taskwarrior up.
+fn taskwarrior_hook(config: &Config) -> Result<()> {
+    let lines: Vec<String> = std::io::stdin()
+        .lines()
+        .collect::<std::io::Result<Vec<_>>>()?
+        .into_iter()
+        .filter(|f| !f.trim().is_empty())
+        .collect();
+    let task = match lines.last() {
+        Some(task) => task,
+        None => return Ok(()),
+    };
+    println!("{task}");
+    if let Err(e) = serde_json::from_str(task)
+        .map_err(|e| e.into())
+        .and_then(|task| mirror_taskwarrior(config, &task))
+    {
+        error!("{e}");
+        // further lines are shown to the Taskwarrior user
+        println!("utd: {e}");
+    }
+    Ok(())
+}
+
+/// Adds or updates the entry linked to a Taskwarrior task, removing it once the task is
+/// deleted
+fn mirror_taskwarrior(config: &Config, theirs: &TaskwarriorTask) -> Result<()> {
+    use utd::sync::{Link, SyncState};
+    let state_path = board_file(".utd-taskwarrior.json");
+    let mut state: SyncState = match std::fs::read_to_string(&state_path) {
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => SyncState::default(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut tasks = state_file_contents()?;
+    let levels: Vec<String> = config
+        .priority_levels()
+        .into_iter()
+        .map(|f| f.name)
+        .collect();
+    let link = state.links.iter().position(|f| f.remote == theirs.uuid);
+    let position = link.and_then(|link| {
+        tasks
+            .iter()
+            .position(|f| f.timestamp == state.links[link].timestamp)
+    });
+    let position = match (position, link) {
+        (Some(position), Some(link)) if theirs.is_deleted() => {
+            debug!("task {} deleted in taskwarrior", tasks[position].id);
+            tasks.remove(position);
+            state.links.remove(link);
+            None
+        }
+        (Some(position), _) => Some(position),
+        _ if theirs.is_deleted() => None,
+        (None, _) => {
+            let id = tasks.iter().map(|f| f.id).max().unwrap_or(0) + 1;
+            let priority = theirs
+                .priority(&levels)
+                .unwrap_or_else(|| config.default_priority());
+            let mut task = Task::new(
+                &theirs.description,
+                &theirs.tags,
+                true,
+                id,
+                &priority,
+                timestamp().as_nanos(),
+            );
+            task.owner = Some(config.user());
+            task.record("mirrored from taskwarrior");
+            if let Some(link) = link {
+                state.links.remove(link);
+            }
+            state.links.push(Link {
+                timestamp: task.timestamp.to_owned(),
+                remote: theirs.uuid.to_owned(),
+                done: false,
+            });
+            tasks.push(task);
+            Some(tasks.len() - 1)
+        }
+    };
+    if let Some(position) = position {
+        let task = &mut tasks[position];
+        task.name = theirs.description.to_owned();
+        task.tags = theirs.tags.clone();
+        task.project = theirs.project.clone();
+        task.due = theirs.due();
+        if let Some(priority) = theirs.priority(&levels) {
+            task.priority = priority;
+        }
+        if theirs.is_completed() && !task.is_done {
+            task.in_progress = false;
+            task.stop_timer();
+            task.complete();
+        } else if !theirs.is_completed() {
+            task.is_done = false;
+            task.completed_at = None;
+            task.in_progress = theirs.start.is_some();
+        }
+        if let Some(link) = state.links.iter_mut().find(|f| f.remote == theirs.uuid) {
+            link.done = task.is_done;
+        }
+        debug!("task {} mirrored from taskwarrior", task.id);
+    }
+    update_file(&tasks)?;
+    std::fs::write(&state_path, serde_json::to_string_pretty(&state)?)?;
+    Ok(())
+}
+
+/// Syncs the board with its task list at `provider`, named after the board
+fn sync_board(config: &Config, provider: SyncProvider) -> Result<()> {
+    use utd::sync::{sync, GoogleTasks, MicrosoftTodo, Provider, SyncState};
+    let providers = config.sync.as_ref().cloned().unwrap_or_default();
+    let list_name = |list: &str| match BOARD.get() {
+        Some(board) => format!("{list}-{board}"),
+        None => list.to_owned(),
+    };
+    let (remote, key): (Box<dyn Provider>, &str) = match provider {
+        SyncProvider::Google => {
+            let app = providers
+                .google
+                .ok_or("set up an app under [sync.google] first, see the README")?;
+            let token = sign_in(&GoogleTasks::oauth(&app), "google")?;
+            let remote = GoogleTasks::connect(token, &list_name(app.list()))?;
+            (Box::new(remote), "google")
+        }
+        SyncProvider::Microsoft => {
+            let app = providers
+                .microsoft
+                .ok_or("set up an app under [sync.microsoft] first, see the README")?;
+            let token = sign_in(&MicrosoftTodo::oauth(&app), "microsoft")?;
+            let remote = MicrosoftTodo::connect(token, &list_name(app.list()))?;
+            (Box::new(remote), "microsoft")
+        }
+    };
+    let state_path = board_file(&format!(".utd-{key}.json"));
+    let mut state: SyncState = match std::fs::read_to_string(&state_path) {
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => SyncState::default(),
+        Err(e) => return Err(e.into()),
+    };
+    let mut tasks = state_file_contents()?;
+    let mut id = tasks.iter().map(|f| f.id).max().unwrap_or(0);
+    let priority = config.default_priority();
+    let result = sync(&mut tasks, &mut state, remote.as_ref(), |theirs| {
+        id += 1;
+        let mut task = Task::new(
+            &theirs.title,
+            &[],
+            true,
+            id,
+            &priority,
+            timestamp().as_nanos(),
+        );
+        task.body = theirs.notes.clone();
+        task.owner = Some(config.user());
+        task.record(format!("synced from {key}"));
+        task
+    });
+    // what was synced before a failure is saved, so the next sync doesn't repeat it
+    update_file(&tasks)?;
+    std::fs::write(&state_path, serde_json::to_string_pretty(&state)?)?;
+    println!("{}", result?);
+    Ok(())
+}
+
+/// An access token for `oauth`, refreshing the one kept as `<key>-token` in the data
+/// directory or signing in anew
+fn sign_in(oauth: &utd::sync::OAuth, key: &str) -> Result<String> {
+    let path = data_dir().join(format!("{key}-token"));
+    let refresh_token = std::fs::read_to_string(&path).ok();
+    let (token, refresh_token) = oauth.access_token(refresh_token.as_deref().map(str::trim))?;
+    if let Some(refresh_token) = refresh_token {
+        utd::write_private(&path, refresh_token)?;
+    }
+    Ok(token)
+}
+
+/// Posts the board, or the standup with `standup`, to a Slack or Discord channel
+fn post(
+    config: &Config,
+    service: ChatService,
+    webhook_url: Option<&str>,
+    standup: bool,
+) -> Result<()> {
+    let webhooks = config.webhooks.as_ref().cloned().unwrap_or_default();
+    let url = match webhook_url {
+        Some(url) => url.to_owned(),
+        None => webhooks
+            .chat(service)
+            .map(str::to_owned)
+            .ok_or("no webhook url, pass --webhook-url or set it under [webhooks]")?,
+    };
+    let translations = config.translations();
+    let sections = if standup {
+        standup_sections(config)?
+    } else {
+        let tasks = state_file_contents()?;
+        let shown: Vec<&Task> = tasks
+            .iter()
+            .filter(|f| !f.is_done && !f.expired && !f.is_snoozed())
+            .collect();
+        let lines = |keep: &dyn Fn(&Task) -> bool| -> Vec<String> {
+            shown
+                .iter()
+                .filter(|f| keep(f))
+                .map(|f| format!("{}. {}", f.id, f.name))
+                .collect()
+        };
+        vec![
+            (
+                translations.get("todo"),
+                lines(&|f| f.is_task && !f.in_progress),
+            ),
+            (translations.get("in-progress"), lines(&|f| f.in_progress)),
+            (translations.get("notes"), lines(&|f| !f.is_task)),
+        ]
+        .into_iter()
+        .filter(|(_, items)| !items.is_empty())
+        .collect()
+    };
+    let text = to_chat(&sections, service, &translations.get("none"));
+    ureq::post(&url)
+        .timeout(std::time::Duration::from_secs(10))
+        .send_json(chat_payload(&text, service))?;
+    debug!("posted {} lines to {service:?}", text.lines().count());
+    Ok(())
+}
+
+enum State {
+    Started,
+    Completed,
+}
+
+/// `strict` refuses to start tasks beyond the wip-limit, which is otherwise only warned about
+fn alter_tasks(config: &Config, ids: &[String], state: State, strict: bool) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let mut index: HashMap<i64, usize> = HashMap::with_capacity(tasks.len());
+    // ids on more than one entry, e.g. after hand edits, which can't tell them apart
+    let mut shared = HashSet::new();
+    for (position, f) in tasks.iter().enumerate() {
+        if index.insert(f.id, position).is_some() {
+            shared.insert(f.id);
+        }
+    }
+    let mut sessions = Vec::new();
+    // positions of tasks that were open before this run completed them
+    let mut completed = Vec::new();
+    let time_tracking = config.time_tracking.as_ref();
+    let auto_timer = time_tracking.cloned().unwrap_or_default().auto_timer();
+    let mut began = false;
+    let mut altered = Vec::new();
+    for i in ids.iter() {
+        let i: i64 = i.parse()?;
+        if shared.contains(&i) {
+            return Err(format!(
+                "several entries have id {i}, `utd --reset-ids` numbers them apart"
+            )
+            .into());
+        }
+        let position = match index.get(&i) {
+            Some(position) => *position,
+            None => continue,
+        };
+        if !altered.contains(&position) {
+            altered.push(position);
+        }
+        let f = &mut tasks[position];
+        match state {
+            State::Started => {
+                f.in_progress = !f.in_progress;
+                f.is_done = false;
+                f.checked_by = None;
+                began |= f.in_progress;
+                if auto_timer && f.in_progress {
+                    f.start_timer();
+                } else if auto_timer {
+                    if let Some(seconds) = f.stop_timer() {
+                        sessions.push((f.id, seconds));
+                    }
+                }
+
+                debug!("starting task {}: {}", i, config.redact(&f.name));
+            }
+            State::Completed => {
+                let was_done = f.is_done;
+                f.in_progress = false;
+                if auto_timer {
+                    if let Some(seconds) = f.stop_timer() {
+                        sessions.push((f.id, seconds));
+                    }
+                }
+                let user = config.user();
+                match f.owner {
+                    Some(ref owner) if config.verify_completions() && *owner != user => {
+                        debug!("task {} checked by {}, awaiting verification", i, user);
+                        f.checked_by = Some(user);
+                    }
+                    _ => {
+                        f.complete();
+                        debug!("completing task {}: {}", i, config.redact(&f.name));
+                    }
+                }
+                if !was_done && f.is_done && !completed.contains(&position) {
+                    completed.push(position);
+                }
+            }
+        }
+    }
+    if let Some(limit) = config.wip_limit.filter(|_| began) {
+        let started = tasks.iter().filter(|f| f.in_progress && !f.is_done).count();
+        if started > limit && strict {
+            return Err(
+                format!("{started} tasks would be in progress, the limit is {limit}").into(),
+            );
+        }
+        if started > limit {
+            eprintln!("warning: {started} tasks in progress, the limit is {limit}");
+        }
+    }
+    let records: Vec<Record> = altered
+        .iter()
+        .map(|&position| Record::Put {
+            task: tasks[position].clone(),
+        })
+        .collect();
+    save_records(&tasks, &records)?;
+    let sessions: Vec<JournalEntry> = sessions
+        .into_iter()
+        .map(|(task, seconds)| JournalEntry::now(Event::FocusSession { task, seconds }))
+        .collect();
+    append_journal(&sessions)?;
+    let completed: Vec<&Task> = completed
+        .into_iter()
+        .map(|position| &tasks[position])
+        .filter(|f| f.is_done)
+        .collect();
+    advance_goals(&completed)?;
+    debug!("{} tasks updated - ok", ids.len());
+    Ok(())
+}
+
+fn attach(id: i64, target: &str) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    let attachment = if target.contains("://") {
+        target.to_owned()
+    } else {
+        std::fs::canonicalize(target)?.display().to_string()
+    };
+    debug!("attaching {} to task {}", attachment, id);
+    task.attachments.push(attachment);
+    update_file(&tasks)?;
+    Ok(())
+}
+
+fn annotate(id: i64, text: &str) -> Result<()> {
+    let text = text.trim();
+    if text.is_empty() {
+        return Err("empty annotation".into());
+    }
+    let mut tasks = state_file_contents()?;
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    task.annotations.push(Annotation {
+        at: chrono::Utc::now(),
+        text: text.to_owned(),
+    });
+    debug!("task {} annotated", id);
+    update_file(&tasks)?;
+    Ok(())
+}
+
+fn open_attachment(id: i64) -> Result<()> {
+    let tasks = state_file_contents()?;
+    let task = tasks
+        .iter()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    let attachment = task
+        .attachments
+        .first()
+        .ok_or(format!("entry {id} has no attachments"))?;
+    let mut opener = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("cmd");
+        command.args(["/C", "start", ""]);
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    opener.arg(attachment).spawn()?;
+    trace!("opened {}", attachment);
+    Ok(())
+}
+
+fn show_task(config: &Config, id: i64) -> Result<()> {
+    let tasks = state_file_contents()?;
+    let task = tasks
+        .iter()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    let section = config.sections.as_ref();
+    let sections = section.cloned().unwrap_or_default();
+    let heading_section = sections.title.unwrap_or_default();
+    let state = if task.is_done {
+        "done"
+    } else if task.in_progress {
+        "in progress"
+    } else {
+        "open"
+    };
+    let label = |value: &str| {
+        let bold = Style {
+            bold: true,
+            ..Style::default()
+        };
+        Span::new(format!("{value:<12}"), bold).to_ansi_string()
+    };
+    println!(
+        "{}",
+        title_span(&heading_section, &format!("{}. {}", task.id, task.name)).to_ansi_string()
+    );
+    println!(
+        "{}{}",
+        label("type"),
+        if task.is_task { "task" } else { "note" }
+    );
+    println!("{}{}", label("state"), state);
+    if let Some(ref at) = task.completed_at {
+        println!("{}{}", label("completed"), dates::format_date(at));
+    }
+    println!("{}{}", label("priority"), task.priority);
+    if let Some(estimate) = task.estimate {
+        println!("{}{}", label("estimate"), format_duration(estimate));
+    }
+    if task.tracked() > 0 {
+        let running = if task.timer_started.is_some() {
+            " (running)"
+        } else {
+            ""
+        };
+        println!(
+            "{}{}{}",
+            label("tracked"),
+            format_duration(task.tracked()),
+            running
+        );
+    }
+    if !task.tags.is_empty() {
+        let tags = config.tags.as_ref().cloned().unwrap_or_default();
+        println!("{}{}", label("tags"), tags.labels(&task.tags));
+    }
+    if let Some(ref scheduled) = task.scheduled {
+        println!("{}{}", label("planned"), dates::format_date(scheduled));
+    }
+    if let Some(ref due) = task.due {
+        let overdue = if task.is_overdue() { " (overdue)" } else { "" };
+        println!("{}{}{}", label("due"), dates::format_date(due), overdue);
+    }
+    if let Some(ref until) = task.snoozed_until {
+        if task.is_snoozed() {
+            println!("{}{}", label("snoozed"), dates::format_date(until));
+        }
+    }
+    if let Some(parent) = task.split_from {
+        println!("{}{}", label("split from"), parent);
+    }
+    if let Some(ref external) = task.external_ref {
+        println!("{}{}", label("reference"), external);
+    }
+    if let Some(ref body) = task.body {
+        println!("{}", label("description"));
+        for line in body.lines() {
+            println!("  {line}");
+        }
+    }
+    if !task.attachments.is_empty() {
+        println!("{}", label("attachments"));
+        for (index, attachment) in task.attachments.iter().enumerate() {
+            println!("  {}. {}", index + 1, attachment);
+        }
+    }
+    if !task.annotations.is_empty() {
+        println!("{}", label("annotations"));
+        for annotation in task.annotations.iter() {
+            println!(
+                "  {}  {}",
+                dates::format_date(&annotation.at),
+                annotation.text
+            );
+        }
+    }
+    if !task.history.is_empty() {
+        println!("{}", label("history"));
+        for entry in task.history.iter() {
+            println!("  {}  {}", dates::format_date(&entry.at), entry.event);
+        }
+    }
+    Ok(())
+}
+
+/// Records `id` as the focused task, then shows that task with its tags, the time spent
+/// on it and its checklist: ticked off subtasks and description items
+fn focus(config: &Config, id: Option<i64>, clear: bool) -> Result<()> {
+    let path = board_file(".utd-focus.json");
+    if clear {
+        if path.exists() {
+            std::fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    let tasks = state_file_contents()?;
+    if let Some(id) = id {
+        let task = tasks
+            .iter()
+            .find(|f| f.id == id)
+            .ok_or(format!("no entry with id {id}"))?;
+        let focus = Focus {
+            task: task.timestamp.to_owned(),
+            since: chrono::Utc::now(),
+        };
+        std::fs::write(&path, serde_json::to_string_pretty(&focus)?)?;
+        debug!("focusing on task {id}");
+    }
+    let focus: Focus = match std::fs::read_to_string(&path) {
+        Ok(contents) => serde_json::from_str(&contents)?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Err("no task is focused, pick one with `utd focus <id>`".into())
+        }
+        Err(e) => return Err(e.into()),
+    };
+    let task = tasks
+        .iter()
+        .find(|f| f.timestamp == focus.task)
+        .ok_or("the focused task is no longer on the board")?;
+    let sections = config.sections.as_ref().cloned().unwrap_or_default();
+    let heading_section = sections.title.unwrap_or_default();
+    println!(
+        "{}",
+        title_span(&heading_section, &format!("{}. {}", task.id, task.name)).to_ansi_string()
+    );
+    if !task.tags.is_empty() {
+        let tags = config.tags.as_ref().cloned().unwrap_or_default();
+        println!("{}", tags.labels(&task.tags));
+    }
+    let elapsed = (chrono::Utc::now() - focus.since).num_seconds().max(0) as u64;
+    let mut times = vec![format!("focused {}", format_duration(elapsed))];
+    if task.tracked() > 0 {
+        times.push(format!("tracked {}", format_duration(task.tracked())));
+    }
+    if let Some(estimate) = task.estimate {
+        times.push(format!("estimate {}", format_duration(estimate)));
+    }
+    println!(
+        "{}",
+        Span::new(
+            times.join(" \u{b7} "),
+            Style {
+                dimmed: true,
+                ..Style::default()
+            }
+        )
+        .to_ansi_string()
+    );
+    let mut checklist: Vec<(bool, String)> = tasks
+        .iter()
+        .filter(|f| task.split_into.contains(&f.id))
+        .map(|f| (f.is_done, f.name.to_owned()))
+        .collect();
+    checklist.extend(task.checklist());
+    let (ticked, unticked) = match icon_profile() {
+        IconProfile::Ascii => ("[x] ", "[ ] "),
+        _ => ("\u{2714} ", "\u{2610} "),
+    };
+    for (done, item) in checklist {
+        println!("{}{}", if done { ticked } else { unticked }, item);
+    }
+    Ok(())
+}
+
+fn split_task(config: &Config, id: i64, parts: &[String]) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let index = tasks
+        .iter()
+        .position(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    let mut original = tasks.remove(index);
+    let mut next_id = tasks
+        .iter()
+        .map(|f| f.id)
+        .max()
+        .unwrap_or_default()
+        .max(original.id);
+    for part in parts {
+        next_id += 1;
+        let mut task = Task::new(
+            part,
+            &original.tags,
+            original.is_task,
+            next_id,
+            &original.priority,
+            timestamp().as_nanos(),
+        );
+        task.owner = Some(config.user());
+        task.split_from = Some(original.id);
+        original.split_into.push(next_id);
+        tasks.push(task);
+    }
+    archive_entries(&[original])?;
+    update_file(&tasks)?;
+    debug!("task {} split into {} parts", id, parts.len());
+    Ok(())
+}
+
+fn convert_entry(id: i64, to: EntryKind) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    task.is_task = to == EntryKind::Task;
+    if !task.is_task {
+        // notes are never in progress
+        task.in_progress = false;
+        task.stop_timer();
+    }
+    debug!("entry {} converted to {:?}", id, to);
+    update_file(&tasks)?;
+    Ok(())
+}
+
+fn merge_tasks(config: &Config, first: i64, second: i64, name: Option<&str>) -> Result<()> {
+    if first == second {
+        return Err("cannot merge a task with itself".into());
+    }
+    let mut tasks = state_file_contents()?;
+    let index = tasks
+        .iter()
+        .position(|f| f.id == second)
+        .ok_or(format!("no entry with id {second}"))?;
+    let other = tasks.remove(index);
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == first)
+        .ok_or(format!("no entry with id {first}"))?;
+    let combined = format!("{} / {}", task.name, other.name);
+    let name = match name {
+        Some(name) => name.to_owned(),
+        None if std::io::stdin().is_terminal() => {
+            print!("name of the merged task [{combined}]: ");
+            std::io::stdout().flush()?;
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            match input.trim() {
+                "" => combined,
+                input => input.to_owned(),
+            }
+        }
+        None => combined,
+    };
+    if other.priority_score(config) > task.priority_score(config) {
+        task.priority = other.priority.to_owned();
+    }
+    task.absorb(other);
+    task.name = name;
+    // entries pointing at the merged task now point at the one it went into
+    for task in tasks.iter_mut() {
+        if task.split_from == Some(second) {
+            task.split_from = (task.id != first).then_some(first);
+        }
+        for ids in [&mut task.split_into, &mut task.depends_on] {
+            if let Some(position) = ids.iter().position(|id| *id == second) {
+                ids.remove(position);
+                if task.id != first && !ids.contains(&first) {
+                    ids.push(first);
+                }
+            }
+        }
+    }
+    debug!("task {} merged into {}", second, first);
+    update_file(&tasks)?;
+    Ok(())
+}
+
+/// Merges another board file into this one, backing this one up first
+fn merge_board(keep: usize, path: &str) -> Result<()> {
+    let contents = std::fs::read_to_string(path).map_err(|e| format!("cannot read {path}: {e}"))?;
+    let other: Tasks = serde_json::from_str(&contents)?;
+    let mut tasks = state_file_contents()?;
+    backup(keep)?;
+    let report = merge_boards(&mut tasks, other);
+    update_file(&tasks)?;
+    debug!(
+        "merged {path} - {} added ({} renumbered), {} updated",
+        report.added, report.renumbered, report.updated
+    );
+    Ok(())
+}
+
+/// Makes `id` depend on the tasks `on`, refusing dependencies that would form a cycle
+fn block(id: i64, on: &[i64], remove: bool) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    for dependency in on.iter() {
+        if !tasks.iter().any(|f| f.id == *dependency) {
+            return Err(format!("no entry with id {dependency}").into());
+        }
+        if !remove && depends_on(&tasks, *dependency, id) {
+            return Err(format!("{dependency} already depends on {id}").into());
+        }
+    }
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    let ids: Vec<String> = on.iter().map(i64::to_string).collect();
+    if remove {
+        task.depends_on.retain(|f| !on.contains(f));
+        task.record(format!("unblocked from {}", ids.join(", ")));
+    } else {
+        for dependency in on.iter() {
+            if !task.depends_on.contains(dependency) {
+                task.depends_on.push(*dependency);
+            }
+        }
+        task.record(format!("blocked by {}", ids.join(", ")));
+    }
+    update_file(&tasks)?;
+    Ok(())
+}
+
+/// Answers requests for the board until interrupted. Only localhost can connect.
+fn serve(config: &Config, port: u16) -> Result<()> {
+    let server = tiny_http::Server::http(("127.0.0.1", port))?;
+    println!("serving the board on http://127.0.0.1:{port}");
+    for mut request in server.incoming_requests() {
+        // a web page can only send other types without the browser asking first, so this
+        // keeps sites open in the browser from changing the board
+        let json = request.headers().iter().any(|f| {
+            f.field.equiv("Content-Type")
+                && f.value
+                    .as_str()
+                    .split(';')
+                    .next()
+                    .is_some_and(|f| f.trim().eq_ignore_ascii_case("application/json"))
+        });
+        let mut body = String::new();
+        let response = if *request.method() != tiny_http::Method::Get && !json {
+            Ok((
+                415,
+                serde_json::json!({ "error": "expected Content-Type: application/json" }),
+            ))
+        } else {
+            request
+                .as_reader()
+                .read_to_string(&mut body)
+                .map_err(|e| e.into())
+                .and_then(|_| api_response(config, request.method(), request.url(), &body))
+        };
+        let (status, json) = match response {
+            Ok(response) => response,
+            Err(e) => (400, serde_json::json!({ "error": e.to_string() })),
+        };
+        debug!("{} {} - {}", request.method(), request.url(), status);
+        let content_type = tiny_http::Header::from_bytes("Content-Type", "application/json")
+            .map_err(|_| "invalid header")?;
+        let response = tiny_http::Response::from_string(json.to_string())
+            .with_status_code(status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            error!("{e}");
+        }
+    }
+    Ok(())
+}
+
+/// Answers every GET with the feed of the board as it is at that moment
+fn serve_ics(port: u16) -> Result<()> {
+    let server = tiny_http::Server::http(("127.0.0.1", port))?;
+    println!("serving the calendar on http://127.0.0.1:{port}/utd.ics");
+    for request in server.incoming_requests() {
+        let (status, body) = match request.method() {
+            tiny_http::Method::Get => match state_file_contents() {
+                Ok(tasks) => (200, to_ics(&tasks)),
+                Err(e) => (500, e.to_string()),
+            },
+            _ => (405, String::from("method not allowed")),
+        };
+        debug!("{} {} - {}", request.method(), request.url(), status);
+        let content_type = match status {
+            200 => "text/calendar; charset=utf-8",
+            _ => "text/plain; charset=utf-8",
+        };
+        let content_type = tiny_http::Header::from_bytes("Content-Type", content_type)
+            .map_err(|_| "invalid header")?;
+        let response = tiny_http::Response::from_string(body)
+            .with_status_code(status)
+            .with_header(content_type);
+        if let Err(e) = request.respond(response) {
+            error!("{e}");
+        }
+    }
+    Ok(())
+}
+
+fn api_response(
+    config: &Config,
+    method: &tiny_http::Method,
+    url: &str,
+    body: &str,
+) -> Result<(u16, serde_json::Value)> {
+    use tiny_http::Method;
+    let path = url
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('/');
+    let not_allowed = || Ok((405, serde_json::json!({ "error": "method not allowed" })));
+    if path == "/tasks" {
+        return match method {
+            Method::Get => Ok((200, serde_json::to_value(state_file_contents()?)?)),
+            Method::Post => {
+                let entry: NewEntry = serde_json::from_str(body)?;
+                let mut tasks = state_file_contents()?;
+                let before = tasks.clone();
+                let id = tasks.iter().map(|f| f.id).max().unwrap_or(0) + 1;
+                let task = entry.into_task(config, id, timestamp().as_nanos())?;
+                tasks.push(task.clone());
+                save_records(&tasks, &[Record::Put { task: task.clone() }])?;
+                fire_webhooks(config, &before)?;
+                Ok((201, serde_json::to_value(task)?))
+            }
+            _ => not_allowed(),
+        };
+    }
+    let id = match path.strip_prefix("/tasks/") {
+        Some(id) => id.parse::<i64>().map_err(|_| format!("invalid id {id}"))?,
+        None => return Ok((404, serde_json::json!({ "error": "not found" }))),
+    };
+    let mut tasks = state_file_contents()?;
+    let before = tasks.clone();
+    let task = match tasks.iter_mut().find(|f| f.id == id) {
+        Some(task) => task,
+        None => {
+            return Ok((
+                404,
+                serde_json::json!({ "error": format!("no entry with id {id}") }),
+            ))
+        }
+    };
+    match method {
+        Method::Get => Ok((200, serde_json::to_value(task)?)),
+        Method::Patch => {
+            let patch: EntryPatch = serde_json::from_str(body)?;
+            let completed = patch.apply(task, config)?;
+            let task = task.clone();
+            save_records(&tasks, &[Record::Put { task: task.clone() }])?;
+            if completed {
+                advance_goals(&[&task])?;
+            }
+            fire_webhooks(config, &before)?;
+            Ok((200, serde_json::to_value(task)?))
+        }
+        _ => not_allowed(),
+    }
+}
+
+fn graph(format: GraphFormat, output: Option<&std::path::Path>) -> Result<()> {
+    let tasks = state_file_contents()?;
+    let graph = match format {
+        GraphFormat::Dot => utd::to_dot(&tasks),
+    };
+    match output {
+        Some(path) => std::fs::write(path, graph)?,
+        None => print!("{graph}"),
+    }
+    Ok(())
+}
+
+fn pin_tasks(ids: &[i64], pinned: bool) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    for id in ids {
+        let task = tasks
+            .iter_mut()
+            .find(|f| f.id == *id)
+            .ok_or(format!("no entry with id {id}"))?;
+        task.pinned = pinned;
+    }
+    update_file(&tasks)?;
+    debug!("{} tasks pinned: {} - ok", ids.len(), pinned);
+    Ok(())
+}
+
+fn snooze(id: i64, until: &str) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let until = dates::parse_when(until)?;
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    task.snoozed_until = Some(until);
+    debug!("task {} snoozed until {}", id, until);
+    update_file(&tasks)?;
+    Ok(())
+}
+
+fn set_due(id: i64, date: Option<&str>) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let due = match date {
+        Some(date) => Some(dates::parse_deadline(date)?),
+        None => None,
+    };
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    task.due = due;
+    match due {
+        Some(due) => task.record(format!("due {}", dates::format_date(&due))),
+        None => task.record("due date cleared"),
+    }
+    update_file(&tasks)?;
+    Ok(())
+}
+
+fn wait_on(id: i64, on: Option<&str>, external_ref: Option<&str>, clear: bool) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == id && f.is_task)
+        .ok_or(format!("no task with id {id}"))?;
+    if clear {
+        task.waiting_since = None;
+        task.record("stopped waiting");
+    } else {
+        task.waiting_since = Some(chrono::Utc::now());
+        if on.is_some() {
+            task.assignee = on.map(str::to_owned);
+        }
+        if external_ref.is_some() {
+            task.external_ref = external_ref.map(str::to_owned);
+        }
+        match task.assignee {
+            Some(ref assignee) => task.record(format!("waiting on {assignee}")),
+            None => task.record("waiting"),
+        }
+    }
+    debug!("task {} waiting: {}", id, !clear);
+    update_file(&tasks)?;
+    Ok(())
+}
+
+/// Waiting tasks older than `[followup] after`, each offered a bump, ping or snooze
+fn follow_up(config: &Config) -> Result<()> {
+    let followup = config.followup.as_ref().cloned().unwrap_or_default();
+    let threshold = dates::parse_offset(followup.after())?;
+    let mut tasks = state_file_contents()?;
+    let now = chrono::Utc::now();
+    let interactive = std::io::stdin().is_terminal();
+    let mut changed = false;
+    for task in tasks.iter_mut().filter(|f| !f.is_done) {
+        let since = match task.waiting_since {
+            Some(since) if now - since >= threshold => since,
+            _ => continue,
+        };
+        let days = (now - since).num_days();
+        println!(
+            "{}. {} - waiting on {} for {} day(s){}",
+            task.id,
+            task.name,
+            task.assignee.as_deref().unwrap_or("someone"),
+            days,
+            match task.external_ref {
+                Some(ref external) => format!(" ({external})"),
+                None => String::new(),
+            }
+        );
+        if !interactive {
+            continue;
+        }
+        print!("[b]ump, [p]ing, [s]nooze or skip? ");
+        let mut input = String::new();
+        // what was chosen so far is still saved
+        if let Err(e) = std::io::stdout()
+            .flush()
+            .and_then(|_| std::io::stdin().read_line(&mut input))
+        {
+            eprintln!("warning: {e}");
+            break;
+        }
+        match input.trim() {
+            "b" => {
+                task.waiting_since = Some(now);
+                task.record("followed up");
+                changed = true;
+            }
+            "p" => {
+                let pinged = match followup.webhook.as_deref() {
+                    Some(webhook) => ureq::post(webhook)
+                        .send_json(serde_json::json!({
+                            "id": task.id,
+                            "task": task.name,
+                            "assignee": task.assignee,
+                            "external_ref": task.external_ref,
+                            "waiting_days": days,
+                        }))
+                        .map(|_| ())
+                        .map_err(|e| e.to_string()),
+                    None => Err(String::from("no [followup] webhook configured")),
+                };
+                match pinged {
+                    Ok(()) => {
+                        task.waiting_since = Some(now);
+                        task.record("pinged");
+                        changed = true;
+                    }
+                    Err(e) => eprintln!("warning: could not ping about {}: {e}", task.id),
+                }
+            }
+            "s" => {
+                task.snoozed_until = Some(now + threshold);
+                changed = true;
+            }
+            _ => {}
+        }
+    }
+    if changed {
+        update_file(&tasks)?;
+    }
+    Ok(())
+}
+
+/// Open tasks untouched for longer than `older_than`, each offered to keep, complete,
+/// delete or snooze for as long again
+fn review(config: &Config, older_than: Option<&str>) -> Result<()> {
+    let age = config.age.as_ref().cloned().unwrap_or_default();
+    let threshold = dates::parse_offset(older_than.unwrap_or_else(|| age.stale_after()))?;
+    let mut tasks = state_file_contents()?;
+    let now = chrono::Utc::now();
+    let interactive = std::io::stdin().is_terminal();
+    let mut deleted = Vec::new();
+    let mut completed = Vec::new();
+    let mut changed = false;
+    let stale = |f: &Task| f.is_stale(threshold);
+    let count = tasks.iter().filter(|f| stale(f)).count();
+    if count == 0 {
+        println!("{}", config.translations().get("nothing-to-review"));
+        return Ok(());
+    }
+    for (index, task) in tasks.iter_mut().filter(|f| stale(f)).enumerate() {
+        println!(
+            "[{}/{}] {}. {} - untouched for {}",
+            index + 1,
+            count,
+            task.id,
+            task.name,
+            format_age((now - task.last_activity()).num_seconds().max(0) as u64)
+        );
+        if !interactive {
+            continue;
+        }
+        print!("[k]eep, [c]omplete, [d]elete, [s]nooze or skip? ");
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        match input.trim() {
+            "k" => task.record("reviewed"),
+            "c" => {
+                task.in_progress = false;
+                task.stop_timer();
+                task.complete();
+                completed.push(task.id);
+            }
+            "d" => deleted.push(task.id),
+            "s" => task.snoozed_until = Some(now + threshold),
+            _ => continue,
+        }
+        changed = true;
+    }
+    if changed {
+        advance_goals(
+            &tasks
+                .iter()
+                .filter(|f| completed.contains(&f.id))
+                .collect::<Vec<_>>(),
+        )?;
+        tasks.retain(|f| !deleted.contains(&f.id));
+        update_file(&tasks)?;
+        debug!(
+            "review - {} completed, {} deleted",
+            completed.len(),
+            deleted.len()
+        );
+    }
+    Ok(())
+}
+
+/// Open tasks untouched for longer than `older_than`, the longest untouched first
+fn list_stale(config: &Config, older_than: Option<&str>) -> Result<()> {
+    let age = config.age.as_ref().cloned().unwrap_or_default();
+    let threshold = dates::parse_offset(older_than.unwrap_or_else(|| age.stale_after()))?;
+    let now = chrono::Utc::now();
+    let mut tasks: Tasks = state_file_contents()?
+        .into_iter()
+        .filter(|f| f.is_stale(threshold))
+        .collect();
+    if tasks.is_empty() {
+        println!("{}", config.translations().get("nothing-to-review"));
+        return Ok(());
+    }
+    tasks.sort_by_key(Task::last_activity);
+    for task in tasks.iter() {
+        println!(
+            "{}. {} - untouched for {}",
+            task.id,
+            task.name,
+            format_age((now - task.last_activity()).num_seconds().max(0) as u64)
+        );
+    }
+    Ok(())
+}
+
+/// The open task not yet started that scores highest in `Task::next_score`, with what
+/// counted for it
+fn suggest_next(config: &Config) -> Result<()> {
+    let tasks = state_file_contents()?;
+    let best = tasks
+        .iter()
+        .filter(|f| f.is_task && !f.is_done && !f.in_progress && !f.expired && !f.is_snoozed())
+        .map(|f| (f, f.next_score(&tasks, config)))
+        .max_by(|a, b| a.1.total_cmp(&b.1));
+    let task = match best {
+        Some((task, _)) => task,
+        None => {
+            println!("{}", config.translations().get("nothing-next"));
+            return Ok(());
+        }
+    };
+    let mut reasons = vec![task.priority.to_owned()];
+    match task.due {
+        _ if task.is_overdue() => reasons.push(String::from("overdue")),
+        Some(ref due) => reasons.push(format!("due {}", dates::format_date(due))),
+        None => {}
+    }
+    let age = (chrono::Utc::now() - task.created()).num_seconds().max(0) as u64;
+    reasons.push(format!("open {}", format_age(age)));
+    if task.is_waiting() || !task.blockers(&tasks).is_empty() {
+        reasons.push(String::from("blocked"));
+    }
+    let reasons = Span::new(
+        format!("({})", reasons.join(", ")),
+        Style {
+            dimmed: true,
+            ..Style::default()
+        },
+    );
+    println!("{}. {} {}", task.id, task.name, reasons.to_ansi_string());
+    Ok(())
+}
+
+/// Proposes a timetable of today's working `hours` and, once accepted, sets each task's
+/// planned start
+fn plan(config: &Config, hours: &str, accept: bool) -> Result<()> {
+    // tasks without an estimate get a block of this long
+    const UNESTIMATED: i64 = 30;
+    let (from, to) = dates::parse_hours(hours)?;
+    let midnight = dates::local_midnight(dates::today())?;
+    let now = chrono::Utc::now();
+    // the day so far is gone, the rest starts at the next quarter hour
+    let elapsed = (now - midnight).num_minutes().max(0) as u32;
+    let from = from.max(elapsed.div_ceil(15) * 15);
+    if from >= to {
+        println!("no working hours left today");
+        return Ok(());
+    }
+    let start = midnight + chrono::Duration::minutes(from.into());
+    let end = midnight + chrono::Duration::minutes(to.into());
+    let mut tasks = state_file_contents()?;
+    let (blocks, left_over) = plan_day(
+        &tasks,
+        config,
+        start,
+        end,
+        chrono::Duration::minutes(UNESTIMATED),
+    );
+    if blocks.is_empty() {
+        println!("{}", config.translations().get("nothing-next"));
+        return Ok(());
+    }
+    let time = |at: &chrono::DateTime<chrono::Utc>| dates::to_local(at).format("%H:%M").to_string();
+    for block in blocks.iter() {
+        println!(
+            "{}-{}  {}. {}",
+            time(&block.start),
+            time(&block.end),
+            block.task.id,
+            block.task.name
+        );
+    }
+    if !left_over.is_empty() {
+        let ids: Vec<String> = left_over.iter().map(|f| f.id.to_string()).collect();
+        println!("doesn't fit: {}", ids.join(", "));
+    }
+    let accepted = if accept {
+        true
+    } else if std::io::stdin().is_terminal() {
+        print!("Set these start times? [y/N] ");
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input)?;
+        matches!(input.trim(), "y" | "Y" | "yes")
+    } else {
+        false
+    };
+    if !accepted {
+        return Ok(());
+    }
+    let starts: HashMap<String, chrono::DateTime<chrono::Utc>> = blocks
+        .iter()
+        .map(|f| (f.task.timestamp.to_owned(), f.start))
+        .collect();
+    for task in tasks.iter_mut() {
+        if let Some(start) = starts.get(&task.timestamp) {
+            task.scheduled = Some(*start);
+            task.record(format!("planned for {}", time(start)));
+        }
+    }
+    update_file(&tasks)?;
+    debug!("{} tasks planned", starts.len());
+    Ok(())
+}
+
+fn toggle_timer(action: TimerAction, id: i64) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let task = tasks
+        .iter_mut()
+        .find(|f| f.id == id)
+        .ok_or(format!("no entry with id {id}"))?;
+    let session = match action {
+        TimerAction::Start => {
+            task.start_timer();
+            None
+        }
+        TimerAction::Stop => task.stop_timer(),
+    };
+    debug!("timer for task {} - {:?}", id, action);
+    update_file(&tasks)?;
+    if let Some(seconds) = session {
+        append_journal(&[JournalEntry::now(Event::FocusSession { task: id, seconds })])?;
+    }
+    Ok(())
+}
+
+fn verify_tasks(config: &Config, ids: &[i64]) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let user = config.user();
+    for id in ids {
+        let task = tasks
+            .iter_mut()
+            .find(|f| f.id == *id)
+            .ok_or(format!("no entry with id {id}"))?;
+        match task.checked_by {
+            Some(ref checker) if *checker == user => {
+                return Err(
+                    format!("task {id} must be verified by someone other than {user}").into(),
+                )
+            }
+            Some(_) => {
+                task.checked_by = None;
+                task.complete();
+                debug!("task {} verified by {}", id, user);
+            }
+            None => return Err(format!("task {id} is not awaiting verification").into()),
+        }
+    }
+    update_file(&tasks)?;
+    let verified: Vec<&Task> = tasks.iter().filter(|f| ids.contains(&f.id)).collect();
+    advance_goals(&verified)?;
+    Ok(())
+}
+
+fn show_review_queue() -> Result<()> {
+    let tasks = state_file_contents()?;
+    for task in tasks.iter() {
+        if let Some(ref checker) = task.checked_by {
+            println!("{}. {} (checked by {})", task.id, task.name, checker);
+        }
+    }
+    Ok(())
+}
+
+/// Explicit ids plus those of the entries matching `selection`. Ids can also be name
+/// fragments such as `groceries`; those matching nothing are reported and skipped.
+fn selected_ids(ids: &[String], selection: Option<&Filter>) -> Result<Vec<String>> {
+    let tasks = state_file_contents()?;
+    let mut selected = Vec::with_capacity(ids.len());
+    for reference in ids.iter() {
+        match resolve_id(&tasks, reference) {
+            Ok(id) => selected.push(id.to_string()),
+            // the others still go ahead
+            Err(e) => eprintln!("warning: {e}"),
+        }
+    }
+    let mut ids = selected;
+    if let Some(filter) = selection {
+        ids.extend(
+            tasks
+                .iter()
+                .filter(|f| filter.matches(f))
+                .map(|f| f.id.to_string()),
+        );
+    }
+    if ids.is_empty() {
+        return Err("no matching entries".into());
+    }
+    Ok(ids)
+}
+
+/// The id of an existing entry, or of the one a name fragment picks
+fn resolve_id(tasks: &[Task], reference: &str) -> Result<i64> {
+    match reference.parse::<i64>() {
+        Ok(id) if tasks.iter().any(|f| f.id == id) => Ok(id),
+        Ok(id) => {
+            let mut nearest: Vec<&Task> = tasks.iter().collect();
+            nearest.sort_by_key(|f| (f.id - id).abs());
+            Err(format!("no entry with id {id}{}", nearest_entries(&nearest)).into())
+        }
+        Err(_) => resolve_name(tasks, reference),
+    }
+}
+
+/// `, nearest: 4. buy milk, 6. walk the dog` for the first few of `tasks`
+fn nearest_entries(tasks: &[&Task]) -> String {
+    if tasks.is_empty() {
+        return String::new();
+    }
+    let names: Vec<String> = tasks
+        .iter()
+        .take(3)
+        .map(|f| format!("{}. {}", f.id, f.name))
+        .collect();
+    format!(", nearest: {}", names.join(", "))
+}
+
+/// The entry whose name is `fragment`, starts with it or contains it, trying each in turn
+fn resolve_name(tasks: &[Task], fragment: &str) -> Result<i64> {
+    let fragment = normalise_name(fragment);
+    let names: Vec<(&Task, String)> = tasks.iter().map(|f| (f, normalise_name(&f.name))).collect();
+    let tiers: [&dyn Fn(&str) -> bool; 3] = [
+        &|name: &str| name == fragment,
+        &|name: &str| name.starts_with(&fragment),
+        &|name: &str| name.contains(&fragment),
+    ];
+    for matches_name in tiers {
+        let found: Vec<&Task> = names
+            .iter()
+            .filter(|(_, name)| matches_name(name.as_str()))
+            .map(|(task, _)| *task)
+            .collect();
+        match found.as_slice() {
+            [] => continue,
+            [task] => return Ok(task.id),
+            found => {
+                let candidates = found
+                    .iter()
+                    .map(|f| format!("{}. {}", f.id, f.name))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                return Err(format!(
+                    "\"{fragment}\" matches {} entries ({candidates}), use an id or more of the name",
+                    found.len()
+                )
+                .into());
+            }
+        }
+    }
+    let mut nearest: Vec<&Task> = tasks.iter().collect();
+    nearest.sort_by_key(|f| edit_distance(&normalise_name(&f.name), &fragment));
+    Err(format!(
+        "no entry with id or name matching \"{fragment}\"{}",
+        nearest_entries(&nearest)
+    )
+    .into())
+}
+
+fn cancel_task(id: &str, reason: Option<&str>) -> Result<()> {
+    let id: i64 = id.parse()?;
+    let mut tasks = state_file_contents()?;
+    let index = tasks
+        .iter()
+        .position(|f| f.id == id && f.is_task)
+        .ok_or(format!("no task with id {id}"))?;
+    let mut task = tasks.remove(index);
+    task.cancel(reason);
+    archive_entries(&[task])?;
+    update_file(&tasks)?;
+    debug!("task {} cancelled", id);
+    Ok(())
+}
+
+fn delete_entry(ids: &[String], yes: bool) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let ids = ids
+        .iter()
+        .map(|f| f.parse::<i64>())
+        .collect::<std::result::Result<HashSet<_>, _>>()?;
+    let affected: Vec<String> = tasks
+        .iter()
+        .filter(|f| ids.contains(&f.id))
+        .map(|f| format!("{}. {}", f.id, f.name))
+        .collect();
+    if !affected.is_empty() && !confirm("Delete these entries?", &affected, yes)? {
+        return Ok(());
+    }
+    let records: Vec<Record> = tasks
+        .iter()
+        .filter(|f| ids.contains(&f.id))
+        .map(|f| Record::Delete {
+            timestamp: f.timestamp.to_owned(),
+        })
+        .collect();
+    tasks.retain(|f| !ids.contains(&f.id));
+    save_records(&tasks, &records)?;
+    debug!("{} tasks deleted - ok", ids.len());
+    Ok(())
+}
+
+fn duplicate(id: i64, count: u32) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let original = tasks
+        .iter()
+        .find(|f| f.id == id)
+        .cloned()
+        .ok_or(format!("no entry with id {id}"))?;
+    let mut next = tasks.iter().map(|f| f.id).max().unwrap_or(0);
+    let mut records = Vec::new();
+    for _ in 0..count {
+        next += 1;
+        let copy = original.duplicate(next, timestamp().as_nanos());
+        records.push(Record::Put { task: copy.clone() });
+        tasks.push(copy);
+    }
+    save_records(&tasks, &records)?;
+    debug!("entry {} duplicated {} time(s)", id, count);
+    Ok(())
+}
+
+fn export(
+    config: &Config,
+    format: ExportFormat,
+    output: Option<&std::path::Path>,
+    filter: &Filter,
+) -> Result<()> {
+    let tasks: Tasks = state_file_contents()?
+        .into_iter()
+        .filter(|f| filter.matches(f))
+        .collect();
+    let exported = match format {
+        ExportFormat::Org => utd::to_org(&tasks, config),
+        ExportFormat::Html => utd::to_html(&tasks, config),
+        ExportFormat::Json => serde_json::to_string_pretty(&tasks)? + "\n",
+        ExportFormat::Remind => utd::to_remind(&tasks),
+        ExportFormat::When => utd::to_when(&tasks),
+    };
+    match output {
+        Some(path) => std::fs::write(path, exported)?,
+        None => print!("{exported}"),
+    }
+    Ok(())
+}
+
+fn import(config: &Config, source: &ImportSource) -> Result<()> {
+    let levels: Vec<String> = config
+        .priority_levels()
+        .into_iter()
+        .map(|f| f.name)
+        .collect();
+    let default_priority = config.default_priority();
+    let entries: Vec<Task> = match source {
+        ImportSource::Github { repo, assignee } => {
+            let token = std::env::var("GITHUB_TOKEN").ok();
+            utd::import::github_issues(repo, assignee.as_deref(), token.as_deref())?
+                .into_iter()
+                .map(|issue| {
+                    let labels: Vec<String> = issue.labels.into_iter().map(|f| f.name).collect();
+                    let priority = utd::import::priority_from_labels(&labels, &levels)
+                        .unwrap_or_else(|| default_priority.to_owned());
+                    let name = format!("#{} {}", issue.number, issue.title);
+                    let mut task = Task::new(
+                        &name,
+                        &[repo.to_owned()],
+                        true,
+                        0,
+                        &priority,
+                        timestamp().as_nanos(),
+                    );
+                    task.external_ref = Some(issue.html_url);
+                    task
+                })
+                .collect()
+        }
+        ImportSource::Trello { export } => {
+            let board = utd::import::TrelloBoard::read(export)?;
+            board
+                .cards
+                .iter()
+                .filter(|f| !f.closed)
+                .map(|card| {
+                    let tags: Vec<String> = card.labels.iter().filter_map(|f| f.tag()).collect();
+                    let mut task = Task::new(
+                        &card.name,
+                        &tags,
+                        true,
+                        0,
+                        &default_priority,
+                        timestamp().as_nanos(),
+                    );
+                    match board.list_state(card) {
+                        ListState::Done => task.is_done = true,
+                        ListState::InProgress => task.in_progress = true,
+                        ListState::Todo => {}
+                    }
+                    if !card.desc.trim().is_empty() {
+                        task.body = Some(card.desc.trim().to_owned());
+                    }
+                    task.external_ref = card.short_url.clone();
+                    task
+                })
+                .collect()
+        }
+        ImportSource::Json { file } => {
+            let contents = std::fs::read_to_string(file)
+                .map_err(|e| format!("cannot read {}: {e}", file.display()))?;
+            serde_json::from_str(&contents)?
+        }
+    };
+    add_imported(config, entries)
+}
+
+/// Adds imported entries to the board, skipping those imported before
+fn add_imported(config: &Config, entries: Vec<Task>) -> Result<()> {
+    let mut tasks = state_file_contents()?;
+    let mut id = tasks.iter().map(|f| f.id).max().unwrap_or(0);
+    let mut imported = 0;
+    for mut entry in entries {
+        // re-imported exports keep their creation time
+        let known = (entry.external_ref.is_some()
+            && tasks.iter().any(|f| f.external_ref == entry.external_ref))
+            || tasks.iter().any(|f| f.timestamp == entry.timestamp);
+        if known {
+            continue;
+        }
+        id += 1;
+        entry.id = id;
+        if entry.owner.is_none() {
+            entry.owner = Some(config.user());
+        }
+        entry.record("imported");
+        tasks.push(entry);
+        imported += 1;
+    }
+    update_file(&tasks)?;
+    debug!("{} entries imported", imported);
+    Ok(())
+}
+
+/// Adds the entries of `-a`/`-n`, tagged with `context_tag` and filed under `project`
+/// unless they name one of their own
+fn new_entry(
+    config: &Config,
+    args: &utd::args::Cli,
+    context_tag: Option<&str>,
+    project: Option<&str>,
+) -> Result<()> {
+    let tag_config = config.tags.as_ref().cloned().unwrap_or_default();
+    let prefix = tag_config.prefix();
+    let default_priority = config.default_priority();
+    let expires_at = match args.expires {
+        Some(ref when) => Some(dates::parse_when(when)?),
+        None => None,
+    };
+    let due = match args.due {
+        Some(ref when) => Some(dates::parse_deadline(when)?),
+        None => None,
+    };
+    if let Some(ref every) = args.every {
+        dates::parse_offset(every)?;
+    }
+    let estimate = match args.estimate {
+        Some(ref effort) => {
+            let effort = dates::parse_offset(effort)?.num_seconds();
+            Some(u64::try_from(effort).map_err(|_| "estimates cannot be negative")?)
+        }
+        None => None,
+    };
+    if let Some(ref priorities) = args.priority {
+        let levels = config.priority_levels();
+        if let Some(unknown) = priorities
+            .iter()
+            .find(|p| !levels.iter().any(|f| f.name == **p))
+        {
+            return Err(format!("unknown priority level {unknown}").into());
+        }
+    }
+    let level_names: Vec<String> = config
+        .priority_levels()
+        .into_iter()
+        .map(|f| f.name)
+        .collect();
+    let entry_adder =
+        |list: &[String], is_task: bool, priority: &mut VecDeque<&String>| -> Result<()> {
+            let mut tasks: Tasks = state_file_contents()?;
+            {
+                // Check if file has data in it
+                if !tasks.is_empty() {
+                    trace!("found {} existing tasks", tasks.len());
+                } else {
+                    trace!("found no existing tasks");
+                }
+            }
+            let mut entries = Vec::with_capacity(list.len());
+            let mut len = match tasks.iter().max_by_key(|f| f.id) {
+                Some(task) => task.id,
+                None => 0,
+            };
+            for entry_name in list.iter() {
+                let (title, mut tags) = parse_entry(entry_name, prefix);
+                let (title, inline_priority) = parse_priority(&title, &level_names);
+                let (title, inline_project) = parse_project(&title);
+                let untagged = tags.is_empty() && args.tag.iter().flatten().all(|f| f.is_empty());
+                for tag in args.tag.iter().flatten() {
+                    push_tag(&mut tags, tag, prefix);
+                }
+                if untagged {
+                    for tag in config.default_tags.iter().flatten() {
+                        push_tag(&mut tags, tag, prefix);
+                    }
+                }
+                let mut tags = correct_tags(&tag_config, &tasks, tag_config.canonicalise(&tags))?;
+                // the context was chosen on purpose, so it isn't questioned as a typo
+                if let Some(tag) = context_tag {
+                    push_tag(&mut tags, tag, prefix);
+                }
+                // entries still take their -p by position, but `!high` wins over it
+                let entry_priority = priority
+                    .pop_front()
+                    .map(String::as_str)
+                    .unwrap_or(&default_priority);
+                let entry_priority = inline_priority.as_deref().unwrap_or(entry_priority);
+                if args.unless_exists {
+                    let name = normalise_name(&title);
+                    if tasks
+                        .iter()
+                        .chain(entries.iter())
+                        .any(|f: &Task| !f.is_done && normalise_name(&f.name) == name)
+                    {
+                        debug!("{} already exists, skipping", config.redact(&title));
+                        continue;
+                    }
+                }
+                len += 1;
+                let mut task = Task::new(
+                    &title,
+                    &tags,
+                    is_task,
+                    len,
+                    entry_priority,
+                    timestamp().as_nanos(),
+                );
+                task.owner = Some(config.user());
+                task.expires_at = expires_at;
+                task.due = due;
+                task.project = inline_project.or_else(|| project.map(str::to_owned));
+                if is_task {
+                    task.estimate = estimate;
+                    task.recur = args.every.clone();
+                }
+                entries.push(task);
+            }
+            let records: Vec<Record> = entries
+                .iter()
+                .map(|f| Record::Put { task: f.clone() })
+                .collect();
+            tasks.append(&mut entries);
+            save_records(&tasks, &records)
+        };
+    // if note is some, iterate and add notes
+    let mut vd = VecDeque::from_iter(args.priority.iter().flatten());
+    if let Some(ref tasks) = args.add {
+        let tasks = with_stdin_entries(tasks)?;
+        entry_adder(&tasks, true, &mut vd)?;
+    }
+    if let Some(ref notes) = args.note {
+        let notes = with_stdin_entries(notes)?;
+        entry_adder(&notes, false, &mut vd)?;
+    }
+    if !vd.is_empty() {
+        let unused: Vec<&str> = vd.iter().map(|f| f.as_str()).collect();
+        eprintln!(
+            "warning: more priorities than entries, {} went unused",
+            unused.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// Replaces a `-` entry with the non-empty lines read from stdin
+fn with_stdin_entries(list: &[String]) -> Result<Vec<String>> {
+    let mut entries = Vec::with_capacity(list.len());
+    for entry in list.iter() {
+        if entry == "-" {
+            for line in std::io::stdin().lines() {
+                let line = line?;
+                if !line.trim().is_empty() {
+                    entries.push(line.trim().to_owned());
+                }
+            }
+        } else {
+            entries.push(entry.to_owned());
+        }
+    }
+    Ok(entries)
+}
+
+/// Offers the existing tag for each new tag that looks like a typo of it
+fn correct_tags(tag_config: &Tags, tasks: &[Task], tags: Vec<String>) -> Result<Vec<String>> {
+    let mut existing: Vec<String> = Vec::new();
+    for tag in tasks.iter().flat_map(|f| f.tags.iter()) {
+        if !existing.contains(tag) {
+            existing.push(tag.to_owned());
+        }
+    }
+    let mut corrected = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let suggestion = match similar_tag(&tag, &existing) {
+            Some(suggestion) => suggestion.to_owned(),
+            None => {
+                corrected.push(tag);
+                continue;
+            }
+        };
+        let (typed, suggested) = (tag_config.label(&tag), tag_config.label(&suggestion));
+        let replace = if tag_config.auto_correct() {
+            true
+        } else if std::io::stdin().is_terminal() {
+            print!("{typed} looks like {suggested}, use {suggested} instead? [Y/n] ");
+            std::io::stdout().flush()?;
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input)?;
+            !matches!(input.trim(), "n" | "N" | "no")
+        } else {
+            eprintln!("warning: {typed} looks like {suggested}");
+            false
+        };
+        if replace {
+            debug!("tag {} corrected to {}", tag, suggestion);
+            if !corrected.contains(&suggestion) {
+                corrected.push(suggestion);
+            }
+        } else {
+            warn!("new tag {} is close to {}", tag, suggestion);
+            corrected.push(tag);
+        }
+    }
+    Ok(corrected)
+}
+
+fn timestamp() -> std::time::Duration {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("time is going backwards")
+}
+
+fn write_to_file(file: &mut File, tasks: &Tasks) {
+    writeln!(file, "{}", serde_json::to_string_pretty(tasks).unwrap()).unwrap();
+    trace!("tasks updated");
+}
+
+/// Board chosen with a leading `+board`, the default board when unset
+static BOARD: OnceLock<String> = OnceLock::new();
+
+/// The board as `utd shell` last read or wrote it, with the modification time of its file
+static LOADED: OnceLock<Mutex<Option<(PathBuf, SystemTime, Tasks)>>> = OnceLock::new();
+
+/// The board kept by `utd shell`, unless its file changed since
+fn loaded_board() -> Option<Tasks> {
+    let path = state_path();
+    let modified = std::fs::metadata(&path).and_then(|f| f.modified()).ok()?;
+    match LOADED.get()?.lock().ok()?.as_ref() {
+        Some((loaded, at, tasks)) if *loaded == path && *at == modified => Some(tasks.clone()),
+        _ => None,
+    }
+}
+
+fn keep_loaded(tasks: &Tasks) {
+    let loaded = match LOADED.get() {
+        Some(loaded) => loaded,
+        None => return,
+    };
+    let path = state_path();
+    if let Ok(modified) = std::fs::metadata(&path).and_then(|f| f.modified()) {
+        if let Ok(mut loaded) = loaded.lock() {
+            *loaded = Some((path, modified, tasks.clone()));
+        }
+    }
+}
+
+/// Set when `storage = "jsonl"`
+static JSON_LINES: OnceLock<bool> = OnceLock::new();
+
+fn json_lines() -> bool {
+    JSON_LINES.get().copied().unwrap_or(false)
+}
+
+/// The file holding the board in the configured storage
+fn state_path() -> PathBuf {
+    if json_lines() {
+        board_file(".utd.jsonl")
+    } else {
+        board_file(".utd.json")
+    }
+}
+
+/// Selects the journal of `day` as the board. Today's journal starts out with the
+/// unfinished tasks of the last day written.
+fn open_journal(day: Option<&str>) -> Result<()> {
+    if BOARD.get().is_some() {
+        return Err("the journal is a board of its own, leave out +board".into());
+    }
+    let today = dates::today();
+    let day = match day {
+        Some(day) => dates::to_local(&dates::parse_when(day)?).date_naive(),
+        None => today,
+    };
+    BOARD.get_or_init(|| format!("journal-{day}"));
+    if day != today || state_path().exists() || board_file(".utd.json").exists() {
+        return Ok(());
+    }
+    let (last, path) = match last_journal(day) {
+        Some(journal) => journal,
+        None => return Ok(()),
+    };
+    let contents = std::fs::read_to_string(&path)?;
+    let tasks: Tasks = if path.extension().is_some_and(|f| f == "jsonl") {
+        replay(
+            contents
+                .lines()
+                .filter(|f| !f.trim().is_empty())
+                .map(serde_json::from_str)
+                .collect::<serde_json::Result<Vec<Record>>>()
+                .map_err(|e| damaged(&path, e))?,
+        )
+    } else {
+        serde_json::from_str(&contents).map_err(|e| damaged(&path, e))?
+    };
+    let unfinished: Tasks = tasks
+        .into_iter()
+        .filter(|f| f.is_task && !f.is_done)
+        .collect();
+    debug!("carried {} tasks over from {last}", unfinished.len());
+    update_file(&unfinished)
+}
+
+/// The latest journal before `day` and its board file
+fn last_journal(day: chrono::NaiveDate) -> Option<(chrono::NaiveDate, PathBuf)> {
+    std::fs::read_dir(data_dir())
+        .ok()?
+        .filter_map(|f| f.ok())
+        .filter_map(|f| {
+            let name = f.file_name().to_string_lossy().into_owned();
+            let (date, extension) = name.strip_prefix(".utd-journal-")?.rsplit_once('.')?;
+            let date = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+            matches!(extension, "json" | "jsonl").then(|| (date, f.path()))
+        })
+        .filter(|(date, _)| *date < day)
+        .max()
+}
+
+/// The data file `name` of the selected board, e.g. `.utd.json` or `.utd-home.json`
+fn board_file(name: &str) -> PathBuf {
+    let mut path = data_dir();
+    match (BOARD.get(), name.rsplit_once('.')) {
+        (Some(board), Some((stem, extension))) => path.push(format!("{stem}-{board}.{extension}")),
+        _ => path.push(name),
+    }
+    path
+}
+
+fn state_file_contents() -> Result<Tasks> {
+    if let Some(tasks) = loaded_board() {
+        return Ok(tasks);
+    }
+    if json_lines() {
+        match std::fs::read_to_string(state_path()) {
+            Ok(contents) => {
+                let records = contents
+                    .lines()
+                    .filter(|f| !f.trim().is_empty())
+                    .map(serde_json::from_str)
+                    .collect::<serde_json::Result<Vec<Record>>>()
+                    .map_err(|e| damaged(&state_path(), e))?;
+                let tasks = replay(records);
+                keep_loaded(&tasks);
+                return Ok(tasks);
+            }
+            // a board kept as JSON until now carries over with the first change
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    let path = board_file(".utd.json");
+    let read_file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .read(true)
+        .open(&path)
+        .unwrap();
+    let mut buf_reader = std::io::BufReader::new(read_file);
+    let mut contents = String::new();
+    buf_reader.read_to_string(&mut contents)?;
+    // empty list
+    if contents.is_empty() {
+        contents.push_str("[]");
+    }
+    let tasks: Tasks = serde_json::from_str(&contents).map_err(|e| damaged(&path, e))?;
+    keep_loaded(&tasks);
+    Ok(tasks)
+}
+
+fn damaged(path: &std::path::Path, error: serde_json::Error) -> String {
+    format!(
+        "cannot read {}: {error}, `utd doctor --repair` may recover it",
+        path.display()
+    )
+}
+
+/// A file of its own to write before renaming it into place, so that the daemon, `utd
+/// serve` and commands running at once never rename each other's half-written files
+fn temp_path() -> PathBuf {
+    data_dir().join(format!(
+        ".temp-{}-{:08x}",
+        std::process::id(),
+        rand::thread_rng().gen::<u32>()
+    ))
+}
+
+fn update_file(tasks: &Tasks) -> Result<()> {
+    if json_lines() {
+        return append_changes(tasks);
+    }
+    let contents = format!("{}\n", serde_json::to_string_pretty(tasks)?);
+    let path = temp_path();
+    std::fs::write(&path, &contents)?;
+    let board = board_file(".utd.json");
+    // the board being replaced is the last good copy, unless it was damaged since
+    if board_is_intact(&board) {
+        std::fs::copy(&board, board_file(".utd-good.json"))?;
+    }
+    // the checksum goes first, a board newer than its checksum would look damaged
+    let sum = temp_path();
+    std::fs::write(&sum, checksum(contents.as_bytes()))?;
+    std::fs::rename(sum, board_file(".utd.sum"))?;
+    std::fs::rename(path, &board)?;
+    keep_loaded(tasks);
+    trace!("tasks updated");
+    Ok(())
+}
+
+/// Whether `board` is as utd last wrote it. Boards written before checksums were kept
+/// only have to parse.
+fn board_is_intact(board: &std::path::Path) -> bool {
+    let contents = match std::fs::read(board) {
+        Ok(contents) => contents,
+        Err(_) => return false,
+    };
+    match std::fs::read_to_string(board_file(".utd.sum")) {
+        Ok(sum) => sum.trim() == checksum(&contents),
+        Err(_) => serde_json::from_slice::<Tasks>(&contents).is_ok(),
+    }
+}
+
+fn read_board(path: &std::path::Path) -> Option<Tasks> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+/// Checks the board can be read. With `repair`, a damaged board is rebuilt from the
+/// entries that still parse, topped up from the last good copy, or else from the
+/// latest backup. The damaged file is kept next to the board.
+fn doctor(repair: bool) -> Result<()> {
+    let board = state_path();
+    let contents = match std::fs::read_to_string(&board) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            println!("No board yet");
+            return Ok(());
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if json_lines() {
+        return doctor_json_lines(&board, &contents, repair);
+    }
+    let parsed = match contents.trim() {
+        "" => Ok(Tasks::new()),
+        _ => serde_json::from_str::<Tasks>(&contents),
+    };
+    let error = match parsed {
+        Ok(tasks) if board_is_intact(&board) => {
+            println!("Board is healthy, {} entries", tasks.len());
+            return Ok(());
+        }
+        Ok(tasks) => {
+            println!(
+                "Board was changed outside utd and reads fine, {} entries",
+                tasks.len()
+            );
+            if repair {
+                update_file(&tasks)?;
+                println!("Checksum updated");
+            }
+            return Ok(());
+        }
+        Err(e) => e,
+    };
+    println!("Board cannot be read: {error}");
+    if !repair {
+        return Err("board is damaged, run `utd doctor --repair` to recover it".into());
+    }
+    let mut tasks = salvage_entries(&contents);
+    let salvaged = tasks.len();
+    let mut recovered = vec![format!("{salvaged} entries that still parse")];
+    match read_board(&board_file(".utd-good.json")) {
+        Some(good) => {
+            let report = merge_boards(&mut tasks, good);
+            recovered.push(format!("{} from the last good copy", report.added));
+        }
+        None if salvaged == 0 => {
+            let latest = backups()?
+                .last()
+                .and_then(|f| read_board(f))
+                .ok_or("nothing to recover the board from")?;
+            recovered.push(format!("{} from the latest backup", latest.len()));
+            tasks = latest;
+        }
+        None => {}
+    }
+    let kept = keep_damaged(&board)?;
+    update_file(&tasks)?;
+    println!(
+        "Recovered {}, the damaged board is kept as {}",
+        recovered.join(" and "),
+        kept.display()
+    );
+    Ok(())
+}
+
+fn doctor_json_lines(board: &std::path::Path, contents: &str, repair: bool) -> Result<()> {
+    let mut records = Vec::new();
+    let mut bad_lines = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Record>(line) {
+            Ok(record) => records.push(record),
+            Err(_) => bad_lines.push((number + 1).to_string()),
         }
     }
-    if let Err(e) = display_content(&config, args.sort.as_ref()) {
-        error!("{e}");
+    if bad_lines.is_empty() {
+        println!("Board is healthy, {} records", records.len());
+        return Ok(());
+    }
+    println!("Lines {} of the board cannot be read", bad_lines.join(", "));
+    if !repair {
+        return Err("board is damaged, run `utd doctor --repair` to recover it".into());
     }
+    let tasks = replay(records);
+    let kept = keep_damaged(board)?;
+    compact_board(&tasks)?;
+    println!(
+        "Recovered {} entries, the damaged board is kept as {}",
+        tasks.len(),
+        kept.display()
+    );
     Ok(())
 }
 
-fn display_content(config: &Config, args: Option<&SortParam>) -> Result<()> {
-    let section = config.sections.as_ref();
-    let sections = section.cloned().unwrap_or_default();
-    let heading_section = sections.title.as_ref();
-    let heading_section = heading_section.cloned().unwrap_or_default();
-    let tasks = if let Some(sort) = args {
-        order_tasks(*sort)?
-    } else {
-        state_file_contents()?
-    };
-    let disabled_title = config.disable_title.unwrap_or(false);
-    let mut table = TableBuilder::new()
-        .style(
-            match &*config
-                .borders
-                .as_ref()
-                .cloned()
-                .unwrap_or_else(|| "empty".to_string())
-                .as_str()
-            {
-                "elegant" => TableStyle::extended(),
-                "extended" => TableStyle::elegant(),
-                "empty" => TableStyle::empty(),
-                _ => unreachable!(),
-            },
-        )
-        .build();
-    if !disabled_title {
-        let title_message = draw_titles(&heading_section, &greeting());
-        table.add_row(Row::new(vec![TableCell::new_with_alignment(
-            title_message,
-            2,
-            Alignment::Center,
-        )]))
-    }
+/// Copies a damaged board aside, in case more can be recovered from it by hand
+fn keep_damaged(board: &std::path::Path) -> Result<PathBuf> {
+    let mut kept = data_dir();
+    kept.push(format!(
+        "{}-damaged-{}",
+        backup_name(),
+        dates::to_local(&chrono::Utc::now()).format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::copy(board, &kept)?;
+    Ok(kept)
+}
 
-    let set_tasks: Vec<_> = tasks
-        .iter()
-        .filter(|f| f.is_task && !f.in_progress)
-        .collect();
-    let in_progress: Vec<_> = tasks.iter().filter(|f| f.in_progress).collect();
-    let notes: Vec<_> = tasks.iter().filter(|f| !f.is_task).collect();
-    for (index, i) in set_tasks.iter().enumerate() {
-        if i.is_task && !i.in_progress {
-            if index == 0 {
-                draw_todo_title(config, &tasks, &mut table);
-            }
-            draw_todo_list(config, i, &mut table);
-        }
+/// Appends what changed since the board was read, rewriting it only when entries moved
+fn append_changes(tasks: &Tasks) -> Result<()> {
+    let path = state_path();
+    if !path.exists() {
+        return compact_board(tasks);
     }
+    let records = match record_changes(&state_file_contents()?, tasks) {
+        Some(records) => records,
+        None => return compact_board(tasks),
+    };
+    append_records(&path, &records)
+}
 
-    for (index, i) in in_progress.iter().enumerate() {
-        if i.in_progress {
-            if index == 0 {
-                draw_progress_title(config, &mut table);
-            }
-            draw_progress_list(config, i, &mut table);
-        }
+/// Saves `tasks` after a change the caller knows the records of, so a JSON Lines board
+/// appends just those instead of comparing it with the board on disk
+fn save_records(tasks: &Tasks, records: &[Record]) -> Result<()> {
+    let path = state_path();
+    if !json_lines() {
+        return update_file(tasks);
     }
+    if !path.exists() {
+        return compact_board(tasks);
+    }
+    append_records(&path, records)
+}
 
-    for (index, i) in notes.iter().enumerate() {
-        if !i.is_task {
-            if index == 0 {
-                draw_notes_title(config, &mut table);
-            }
-            draw_notes_list(config, i, &mut table);
-        }
+fn append_records(path: &std::path::Path, records: &[Record]) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new().append(true).open(path)?;
+    for record in records.iter() {
+        writeln!(file, "{}", serde_json::to_string(record)?)?;
     }
+    trace!("{} records appended", records.len());
+    Ok(())
+}
 
-    if !tasks.is_empty() {
-        println!("{}", table.render());
+/// Rewrites a JSON Lines board as one record per entry
+fn compact_board(tasks: &Tasks) -> Result<()> {
+    let path = temp_path();
+    let mut temp = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&path)?;
+    for task in tasks.iter() {
+        let record = Record::Put {
+            task: task.to_owned(),
+        };
+        writeln!(temp, "{}", serde_json::to_string(&record)?)?;
     }
+    std::fs::rename(path, state_path())?;
+    trace!("board compacted to {} entries", tasks.len());
     Ok(())
 }
 
-fn draw_progress_list(config: &Config, task: &Task, table: &mut Table) {
-    let section = config.sections.as_ref();
-    let sections = section.cloned().unwrap_or_default();
-    let task_title = format!("{}. {}", task.id, &task.name);
-    let res = draw_lists(
-        &sections.in_progress.unwrap_or_default(),
-        task.is_done,
-        task_title,
-        &task.priority,
+/// The files utd reads and writes for the current board, as resolved from the config
+fn print_paths(logs: &Logs) -> Result<()> {
+    let config = match utd::config_file_path() {
+        Some(path) => path.display().to_string(),
+        None => String::from("none, using the defaults"),
+    };
+    let log = match logs.destination() {
+        "stderr" => String::from("stderr"),
+        _ => logs.path().display().to_string(),
+    };
+    let paths = [
+        ("data dir", data_dir().display().to_string()),
+        ("board", state_path().display().to_string()),
+        ("config", config),
+        ("log", log),
         (
-            &task.tags,
-            &config.tags.as_ref().cloned().unwrap_or_default(),
+            "archive",
+            board_file(".utd-archive.json").display().to_string(),
         ),
-    );
-    table.add_row(Row::new(vec![TableCell::new(res); 1]));
+        ("backups", backup_dir().display().to_string()),
+        ("scripts", utd::scripts_dir().display().to_string()),
+    ];
+    for (name, path) in paths {
+        println!("{name:<10}{path}");
+    }
+    Ok(())
 }
 
-fn draw_notes_list(config: &Config, task: &Task, table: &mut Table) {
-    let section = config.sections.as_ref();
-    let sections = section.cloned().unwrap_or_default();
-    let task_title = format!("{}. {}", task.id, &task.name);
-    let res = draw_lists(
-        &sections.notes.unwrap_or_default(),
-        task.is_done,
-        task_title,
-        &task.priority,
-        (
-            &task.tags,
-            &config.tags.as_ref().cloned().unwrap_or_default(),
-        ),
-    );
-    table.add_row(Row::new(vec![TableCell::new(res); 1]));
+fn backup_dir() -> PathBuf {
+    let mut path = data_dir();
+    path.push("backups");
+    path
 }
-fn draw_todo_list(config: &Config, task: &Task, table: &mut Table) {
-    let section = config.sections.as_ref();
-    let sections = section.cloned().unwrap_or_default();
-    let task_title = format!("{}. {}", task.id, &task.name);
-    let res = draw_lists(
-        &sections.todo.unwrap_or_default(),
-        task.is_done,
-        task_title,
-        &task.priority,
-        (
-            &task.tags,
-            &config.tags.as_ref().cloned().unwrap_or_default(),
-        ),
-    );
-    table.add_row(Row::new(vec![TableCell::new(res); 1]));
-}
-
-fn draw_lists<'a>(
-    config: &'a impl Configurable,
-    completed: bool,
-    value: String,
-    priority: &'a str,
-    tags: (&str, &Tags),
-) -> String {
-    let (tag_text, tags) = tags;
-    let mut padding = String::default();
-    for _ in 0..config.indent_spaces() + 2 {
-        padding.push(' ');
-    }
-    let value = if config.entry_icon_suffix() {
-        if completed {
-            format!("{}{}", value, config.completed_icon())
-        } else {
-            format!("{}{}", value, config.entry_icon())
-        }
-    } else if completed {
-        format!("{}{}", config.completed_icon(), value)
-    } else {
-        format!("{}{}", config.entry_icon(), value)
-    };
-    /***********
-     ***/
 
-    let tag_text = if tags.icon_suffix.unwrap_or(false) {
-        if !tag_text.is_empty() {
-            format!("{}{}", tag_text, tags.icon())
-        } else {
-            tag_text.to_owned()
-        }
-    } else if !tag_text.is_empty() {
-        format!("{}{}", tags.icon(), tag_text)
-    } else {
-        tag_text.to_owned()
-    };
-    /************************/
-    let hex_title = match completed {
-        false => match &*priority {
-            "low" => hex_to_rgb(config.colour_low()),
-            "normal" => hex_to_rgb(config.colour_normal()),
-            "high" => hex_to_rgb(config.colour_high()),
-            _ => unreachable!(),
-        },
-        true => hex_to_rgb(config.colour_completed()),
-    };
-
-    let heading = if config.dim_completed() {
-        if config.entry_italic() && config.entry_bold() && completed {
-            RGB(hex_title.0, hex_title.1, hex_title.2)
-                .italic()
-                .strikethrough()
-                .dimmed()
-                .bold()
-        } else if config.entry_italic() && !config.entry_bold() && !completed {
-            RGB(hex_title.0, hex_title.1, hex_title.2).italic()
-        } else if !config.entry_italic() && config.entry_bold() && !completed {
-            RGB(hex_title.0, hex_title.1, hex_title.2).bold()
-        } else if !config.entry_italic() && !config.entry_bold() && completed {
-            RGB(hex_title.0, hex_title.1, hex_title.2)
-                .strikethrough()
-                .dimmed()
-        } else if !config.entry_italic() && config.entry_bold() && completed {
-            RGB(hex_title.0, hex_title.1, hex_title.2)
-                .strikethrough()
-                .dimmed()
-                .bold()
-        } else if config.entry_italic() && config.entry_bold() && completed {
-            RGB(hex_title.0, hex_title.1, hex_title.2)
-                .italic()
-                .bold()
-                .dimmed()
-                .strikethrough()
-        } else if config.entry_italic() && !config.entry_bold() && completed {
-            RGB(hex_title.0, hex_title.1, hex_title.2)
-                .italic()
-                .dimmed()
-                .strikethrough()
-        } else {
-            RGB(hex_title.0, hex_title.1, hex_title.2).normal()
-        }
-    } else if config.entry_italic() && config.entry_bold() && completed {
-        RGB(hex_title.0, hex_title.1, hex_title.2)
-            .italic()
-            .strikethrough()
-            .bold()
-    } else if config.entry_italic() && !config.entry_bold() && !completed {
-        RGB(hex_title.0, hex_title.1, hex_title.2).italic()
-    } else if !config.entry_italic() && config.entry_bold() && !completed {
-        RGB(hex_title.0, hex_title.1, hex_title.2).bold()
-    } else if !config.entry_italic() && !config.entry_bold() && completed {
-        RGB(hex_title.0, hex_title.1, hex_title.2).strikethrough()
-    } else if !config.entry_italic() && config.entry_bold() && completed {
-        RGB(hex_title.0, hex_title.1, hex_title.2)
-            .strikethrough()
-            .bold()
-    } else if config.entry_italic() && config.entry_bold() && completed {
-        RGB(hex_title.0, hex_title.1, hex_title.2)
-            .italic()
-            .bold()
-            .strikethrough()
-    } else if config.entry_italic() && !config.entry_bold() && completed {
-        RGB(hex_title.0, hex_title.1, hex_title.2)
-            .italic()
-            .strikethrough()
-    } else {
-        RGB(hex_title.0, hex_title.1, hex_title.2).normal()
-    };
-    let vals = heading.paint(value);
-    let res = format!("{padding}{vals}");
-    let hex_title_tag = hex_to_rgb(tags.colour());
-    let tag = if tags.italic() && tags.bold() && tags.underline() {
-        RGB(hex_title_tag.0, hex_title_tag.1, hex_title_tag.2)
-            .italic()
-            .underline()
-            .bold()
-    } else if tags.italic() && !tags.bold() && !tags.underline() {
-        RGB(hex_title_tag.0, hex_title_tag.1, hex_title_tag.2).italic()
-    } else if !tags.italic() && tags.bold() && !tags.underline() {
-        RGB(hex_title_tag.0, hex_title_tag.1, hex_title_tag.2).bold()
-    } else if !tags.italic.unwrap() && !tags.bold() && tags.underline() {
-        RGB(hex_title_tag.0, hex_title_tag.1, hex_title_tag.2).underline()
-    } else if !tags.italic() && tags.bold() && tags.underline() {
-        RGB(hex_title_tag.0, hex_title_tag.1, hex_title_tag.2)
-            .underline()
-            .bold()
-    } else if tags.italic() && tags.bold() && !tags.underline() {
-        RGB(hex_title_tag.0, hex_title_tag.1, hex_title_tag.2)
-            .italic()
-            .bold()
-    } else if tags.italic() && !tags.bold() && tags.underline() {
-        RGB(hex_title_tag.0, hex_title_tag.1, hex_title_tag.2)
-            .italic()
-            .underline()
-    } else {
-        RGB(hex_title_tag.0, hex_title_tag.1, hex_title_tag.2).normal()
-    };
-    let other = tag.paint(tag_text);
-    format!("{res} {other}")
-}
-
-fn draw_titles(title: &impl Configurable, value: impl AsRef<str>) -> ANSIGenericString<str> {
-    let hex_title = hex_to_rgb(title.title_colour());
-    let heading = if title.title_italic() && title.title_bold() && title.title_underline() {
-        RGB(hex_title.0, hex_title.1, hex_title.2)
-            .italic()
-            .underline()
-            .bold()
-    } else if title.title_italic() && !title.title_bold() && !title.title_underline() {
-        RGB(hex_title.0, hex_title.1, hex_title.2).italic()
-    } else if !title.title_italic() && title.title_bold() && !title.title_underline() {
-        RGB(hex_title.0, hex_title.1, hex_title.2).bold()
-    } else if !title.title_italic() && !title.title_bold() && title.title_underline() {
-        RGB(hex_title.0, hex_title.1, hex_title.2).underline()
-    } else if !title.title_italic() && title.title_bold() && title.title_underline() {
-        RGB(hex_title.0, hex_title.1, hex_title.2)
-            .underline()
-            .bold()
-    } else if title.title_italic() && title.title_bold() && !title.title_underline() {
-        RGB(hex_title.0, hex_title.1, hex_title.2).italic().bold()
-    } else if title.title_italic() && !title.title_bold() && title.title_underline() {
-        RGB(hex_title.0, hex_title.1, hex_title.2)
-            .italic()
-            .underline()
-    } else {
-        RGB(hex_title.0, hex_title.1, hex_title.2).normal()
-    };
-    heading.paint(if !title.title_icon_suffix() {
-        format!("{}{}", title.title_icon(), value.as_ref())
-    } else {
-        format!("{}{}", value.as_ref(), title.title_icon())
-    })
+/// The board file name without its dot and extension, e.g. `utd` or `utd-home`
+fn backup_name() -> String {
+    let state = board_file(".utd.json");
+    state
+        .file_stem()
+        .and_then(|f| f.to_str())
+        .map(|f| f.trim_start_matches('.').to_owned())
+        .unwrap_or_else(|| String::from("utd"))
 }
 
-fn draw_todo_title(config: &Config, tasks: &Tasks, table: &mut Table) {
-    let section = config.sections.as_ref();
-    let sections = section.cloned().unwrap_or_default();
-    let task_count = tasks.iter().filter(|f| f.is_task).count();
-    let completed_count = tasks.iter().filter(|f| f.is_task && f.is_done).count();
-    let heading_to_do = format!("to-do [{}/{}]", completed_count, task_count);
-    let heading_section = sections.todo.as_ref();
-    let heading_section = heading_section.cloned().unwrap_or_default();
-    let heading_to_do = draw_titles(&heading_section, &heading_to_do);
-    let heading = sections.todo.unwrap_or_default();
-    let heading = heading.indent_spaces();
-    let mut padding = String::default();
-    for _ in 0..heading {
-        padding.push(' ')
-    }
-    table.add_row(Row::new(vec![
-        TableCell::new(format!(
-            "{}{}",
-            padding, heading_to_do
-        ));
-        1
-    ]));
-}
-
-fn draw_progress_title(config: &Config, table: &mut Table) {
-    let section = config.sections.as_ref();
-    let sections = section.cloned().unwrap_or_default();
-    let heading = String::from("in progress");
-    let heading_section = sections.in_progress.as_ref();
-    let heading_section = heading_section.cloned().unwrap_or_default();
-    let heading_to_do = draw_titles(&heading_section, &heading);
-    let heading = sections.in_progress.unwrap_or_default();
-    let heading = heading.indent_spaces();
-    let mut padding = String::default();
-    for _ in 0..heading {
-        padding.push(' ')
-    }
-    table.add_row(Row::new(vec![
-        TableCell::new(format!(
-            "{}{}",
-            padding, heading_to_do
-        ));
-        1
-    ]));
-}
-
-fn draw_notes_title(config: &Config, table: &mut Table) {
-    let section = config.sections.as_ref();
-    let sections = section.cloned().unwrap_or_default();
-    let heading = String::from("notes");
-    let heading_section = sections.notes.as_ref();
-    let heading_section = heading_section.cloned().unwrap_or_default();
-    let heading_to_do = draw_titles(&heading_section, &heading);
-    let heading = sections.notes.unwrap_or_default();
-    let heading = heading.indent_spaces();
-    let mut padding = String::default();
-    for _ in 0..heading {
-        padding.push(' ')
-    }
-    table.add_row(Row::new(vec![
-        TableCell::new(format!(
-            "{}{}",
-            padding, heading_to_do
-        ));
-        1
-    ]));
-}
-
-fn order_tasks(sort: utd::args::SortParam) -> Result<Tasks> {
-    let mut tasks = state_file_contents()?;
-    match sort {
-        utd::args::SortParam::Age => tasks.sort_unstable_by_key(|f| f.timestamp()),
-        utd::args::SortParam::Priority => {
-            tasks.sort_unstable_by_key(|f| f.priority_score());
-            tasks.reverse();
+fn snapshot_dir() -> PathBuf {
+    let mut path = data_dir();
+    path.push("snapshots");
+    path
+}
+
+fn backups() -> Result<Vec<PathBuf>> {
+    backups_in(&backup_dir())
+}
+
+/// Backups of the current board in `dir`, oldest first. They are named after the board
+/// file and the local time they were taken, e.g. `utd-home-20220401-093000.json`.
+fn backups_in(dir: &std::path::Path) -> Result<Vec<PathBuf>> {
+    let name = backup_name();
+    let mut backups = Vec::new();
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(backups),
+        Err(e) => return Err(e.into()),
+    };
+    for entry in entries {
+        let path = entry?.path();
+        let taken = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .and_then(|f| f.strip_prefix(name.as_str()))
+            .and_then(|f| f.strip_prefix('-'))
+            .and_then(|f| f.strip_suffix(".json"));
+        // other boards share the prefix, but not a timestamp right after it
+        let timestamp =
+            |f: &str| f.len() == 15 && f.chars().all(|c| c.is_ascii_digit() || c == '-');
+        if taken.is_some_and(timestamp) {
+            backups.push(path);
         }
     }
-    Ok(tasks)
+    backups.sort();
+    Ok(backups)
 }
 
-fn make_ids_sequential() -> Result<()> {
-    let tasks = state_file_contents()?;
-    let mut c_tasks = tasks.clone();
-    for (index, _task) in tasks.into_iter().enumerate() {
-        let t = c_tasks.get_mut(index).unwrap();
-        t.id = (index + 1) as i64;
+fn backup(keep: usize) -> Result<PathBuf> {
+    backup_into(&backup_dir(), keep)
+}
+
+/// Snapshots the board into the snapshots directory before a destructive command
+fn snapshot(keep: usize) -> Result<()> {
+    if keep > 0 {
+        backup_into(&snapshot_dir(), keep)?;
     }
-    update_file(&c_tasks)?;
     Ok(())
 }
 
-fn remove_completed() -> Result<()> {
-    let mut tasks = state_file_contents()?;
-    tasks = tasks
+fn backup_into(dir: &std::path::Path, keep: usize) -> Result<PathBuf> {
+    let name = backup_name();
+    std::fs::create_dir_all(dir)?;
+    let mut path = dir.to_path_buf();
+    path.push(format!(
+        "{}-{}.json",
+        name,
+        dates::to_local(&chrono::Utc::now()).format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::write(&path, serde_json::to_string(&state_file_contents()?)?)?;
+    let mut backups = backups_in(dir)?;
+    while backups.len() > keep {
+        let oldest = backups.remove(0);
+        std::fs::remove_file(&oldest)?;
+        trace!("pruned backup {}", oldest.display());
+    }
+    debug!("backed up to {}", path.display());
+    Ok(path)
+}
+
+/// Backs up once a day, before the first change
+fn auto_backup(keep: usize) -> Result<()> {
+    let today = dates::to_local(&chrono::Utc::now())
+        .format("-%Y%m%d-")
+        .to_string();
+    let taken_today = backups()?
         .iter()
-        .filter_map(|f| {
-            if f.is_done.to_string() != true.to_string() {
-                Some(f.to_owned())
-            } else {
-                None
-            }
-        })
-        .collect();
-    update_file(&tasks)?;
+        .filter_map(|f| f.file_name().and_then(|f| f.to_str()))
+        .any(|f| f.contains(&today));
+    if !taken_today {
+        backup(keep)?;
+    }
     Ok(())
 }
 
-enum State {
-    Started,
-    Completed,
+/// Prints the last `lines` lines of the log, then what is appended to it when `follow`ing
+fn tail_log(logs: &Logs, lines: usize, follow: bool) -> Result<()> {
+    if logs.destination() == "stderr" {
+        return Err("[logs] destination is stderr, there is no log file".into());
+    }
+    let path = logs.path();
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+    let all: Vec<&str> = contents.lines().collect();
+    for line in &all[all.len().saturating_sub(lines)..] {
+        println!("{line}");
+    }
+    if !follow {
+        return Ok(());
+    }
+    let mut offset = contents.len() as u64;
+    loop {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        let mut file = std::fs::File::open(&path)?;
+        let len = file.metadata()?.len();
+        // rotated away by another run
+        if len < offset {
+            offset = 0;
+        }
+        if len > offset {
+            file.seek(std::io::SeekFrom::Start(offset))?;
+            let mut appended = String::new();
+            file.read_to_string(&mut appended)?;
+            print!("{appended}");
+            std::io::stdout().flush()?;
+            offset = len;
+        }
+    }
 }
 
-fn alter_tasks(ids: &[String], state: State) -> Result<()> {
-    let mut tasks = state_file_contents()?;
-    for i in ids.iter() {
-        let i: usize = i.parse()?;
-        let vals = tasks
-            .clone()
-            .into_iter()
-            .map(|mut f| {
-                if f.id as usize == i {
-                    match state {
-                        State::Started => {
-                            f.in_progress = !f.in_progress;
-                            f.is_done = false;
-
-                            debug!("starting task {}: {}", i, f.name);
-                        }
-                        State::Completed => {
-                            f.in_progress = false;
-                            f.is_done = true;
-                            debug!("completing task {}: {}", i, f.name);
-                        }
-                    }
-                }
-                f
-            })
-            .collect();
-        tasks = vals;
+fn list_backups() -> Result<()> {
+    for path in backups()?.iter().rev() {
+        if let Some(name) = path.file_name().and_then(|f| f.to_str()) {
+            println!("{name}");
+        }
+    }
+    for path in backups_in(&snapshot_dir())?.iter().rev() {
+        if let Some(name) = path.file_name().and_then(|f| f.to_str()) {
+            println!("{name} (snapshot)");
+        }
     }
+    Ok(())
+}
+
+fn restore(keep: usize, from: &str) -> Result<()> {
+    let path = match PathBuf::from(from) {
+        path if path.is_file() => path,
+        _ if snapshot_dir().join(from).is_file() && !backup_dir().join(from).is_file() => {
+            snapshot_dir().join(from)
+        }
+        _ => {
+            let mut path = backup_dir();
+            path.push(from);
+            path
+        }
+    };
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("cannot read backup {}: {e}", path.display()))?;
+    let tasks: Tasks = serde_json::from_str(&contents)?;
+    backup(keep)?;
     update_file(&tasks)?;
-    debug!("{} tasks updated - ok", ids.len());
+    debug!("restored {} entries from {}", tasks.len(), path.display());
     Ok(())
 }
 
-fn delete_entry(ids: &[String]) -> Result<()> {
-    let mut tasks = state_file_contents()?;
-    for i in ids.iter() {
-        let num: i64 = i.parse()?;
-        tasks = tasks
-            .iter()
-            .filter_map(|f| {
-                if f.id != num {
-                    Some(f.to_owned())
-                } else {
-                    None
-                }
-            })
-            .collect();
+fn archive_file_contents() -> Result<Tasks> {
+    let path = board_file(".utd-archive.json");
+    match std::fs::read_to_string(&path) {
+        Ok(contents) if !contents.trim().is_empty() => Ok(serde_json::from_str(&contents)?),
+        Ok(_) => Ok(Tasks::new()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Tasks::new()),
+        Err(e) => Err(e.into()),
     }
-    update_file(&tasks)?;
-    debug!("{} tasks deleted - ok", ids.len());
+}
+
+fn archive_entries(entries: &[Task]) -> Result<()> {
+    let mut archived = archive_file_contents()?;
+    archived.extend_from_slice(entries);
+    let path = temp_path();
+    let mut temp = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(&path)?;
+    write_to_file(&mut temp, &archived);
+    std::fs::rename(path, board_file(".utd-archive.json"))?;
+    trace!("{} entries archived", entries.len());
     Ok(())
 }
 
-fn new_entry(args: &utd::args::Cli) -> Result<()> {
-    lazy_static! {
-        static ref RE: Regex = Regex::new(r"(@.\w+)").unwrap();
+fn goals_file_contents() -> Result<Vec<Goal>> {
+    match std::fs::read_to_string(board_file(".utd-goals.json")) {
+        Ok(contents) if !contents.trim().is_empty() => Ok(serde_json::from_str(&contents)?),
+        Ok(_) => Ok(Vec::new()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
     }
-    fn timestamp() -> std::time::Duration {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("time is going backwards")
+}
+
+fn update_goals(goals: &[Goal]) -> Result<()> {
+    let path = temp_path();
+    std::fs::write(&path, serde_json::to_string_pretty(goals)?)?;
+    std::fs::rename(path, board_file(".utd-goals.json"))?;
+    Ok(())
+}
+
+fn set_goal(config: &Config, name: &str, target: u32, by: &str, tag: Option<&str>) -> Result<()> {
+    let by = dates::parse_deadline(by)?;
+    let tags = config.tags.as_ref().cloned().unwrap_or_default();
+    let goal = Goal {
+        name: name.to_owned(),
+        tag: tag.map(|f| tags.canonical(f.trim_start_matches(tags.prefix()))),
+        target,
+        progress: 0,
+        by,
+        created: chrono::Utc::now(),
+    };
+    let mut goals = goals_file_contents()?;
+    goals.retain(|f| f.name != goal.name);
+    goals.push(goal);
+    update_goals(&goals)?;
+    debug!("goal {} set", config.redact(name));
+    Ok(())
+}
+
+fn remove_goal(name: &str) -> Result<()> {
+    let mut goals = goals_file_contents()?;
+    let count = goals.len();
+    goals.retain(|f| f.name != name);
+    if goals.len() == count {
+        return Err(format!("no goal named {name}").into());
     }
-    let entry_adder = |list: &[String],
-                       is_task: bool,
-                       file: &mut File,
-                       priority: &mut VecDeque<&PriorityLevel>|
-     -> Result<()> {
-        let mut tasks: Tasks = state_file_contents()?;
-        {
-            // Check if file has data in it
-            if !tasks.is_empty() {
-                trace!("found {} existing tasks", tasks.len());
-            } else {
-                trace!("found no existing tasks");
-            }
-        }
-        let mut entries = Vec::with_capacity(list.len());
-        let mut len = match tasks.iter().max_by_key(|f| f.id) {
-            Some(task) => task.id,
-            None => 0,
+    update_goals(&goals)
+}
+
+fn list_goals() -> Result<()> {
+    for goal in goals_file_contents()?.iter() {
+        let state = if goal.is_met() {
+            "met"
+        } else if goal.is_active() {
+            "due"
+        } else {
+            "missed"
         };
-        for entry_name in list.iter() {
-            let tags: Vec<_> = RE.find_iter(entry_name).map(|f| f.as_str()).collect();
-            let title = RE.replace_all(entry_name, " ");
-            len += 1;
-            let task = Task::new(
-                &title,
-                &tags.join(" "),
-                is_task,
-                len,
-                *priority.pop_front().unwrap_or(&PriorityLevel::Normal),
-                timestamp().as_nanos(),
-            );
-            entries.push(task);
+        println!(
+            "{} {} ({} {})",
+            goal.progress_bar(10),
+            goal.name,
+            state,
+            dates::format_date(&goal.by)
+        );
+    }
+    Ok(())
+}
+
+/// Counts newly completed tasks towards the active goals they match
+fn advance_goals(completed: &[&Task]) -> Result<()> {
+    if completed.is_empty() {
+        return Ok(());
+    }
+    let mut goals = goals_file_contents()?;
+    let mut advanced = false;
+    for goal in goals.iter_mut().filter(|f| f.is_active()) {
+        let count = completed.iter().filter(|f| goal.counts(f)).count() as u32;
+        if count > 0 {
+            goal.progress += count;
+            advanced = true;
         }
-        tasks.append(&mut entries);
-        write_to_file(file, &tasks);
-        Ok(())
-    };
+    }
+    if advanced {
+        update_goals(&goals)?;
+    }
+    Ok(())
+}
+
+fn context_file() -> PathBuf {
     let mut path = data_dir();
-    path.push(".utd.json");
-    // if note is some, iterate and add notes
-    let default_vec = &vec![
-        PriorityLevel::Normal;
-        match args.add.as_ref() {
-            Some(tasks) => tasks.len(),
-            // Safe to unwrap since we are sure one of them is some
-            None => args.note.as_ref().unwrap().len(),
-        }
-    ];
+    path.push(".utd-context");
+    path
+}
 
-    let mut vd = VecDeque::from_iter(args.priority.as_ref().unwrap_or(default_vec));
-    if let Some(ref tasks) = args.add {
-        entry_adder(tasks, true, &mut state_file(&path, false, true)?, &mut vd)?;
+/// Tag set with `utd context set`, scoping every board until cleared
+fn active_context() -> Result<Option<String>> {
+    match std::fs::read_to_string(context_file()) {
+        Ok(context) if !context.trim().is_empty() => Ok(Some(context.trim().to_owned())),
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
     }
-    if let Some(ref notes) = args.note {
-        entry_adder(notes, false, &mut state_file(&path, false, true)?, &mut vd)?;
+}
+
+fn set_context(tag: Option<&str>) -> Result<()> {
+    match tag {
+        Some(tag) => std::fs::write(context_file(), tag)?,
+        None => {
+            if let Err(e) = std::fs::remove_file(context_file()) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(e.into());
+                }
+            }
+        }
     }
+    debug!("context set to {:?}", tag);
     Ok(())
 }
 
-fn state_file(path: &PathBuf, read: bool, write: bool) -> Result<File> {
-    Ok(std::fs::OpenOptions::new()
-        .create(true)
-        .write(write)
-        .read(read)
-        .open(&path)?)
+/// Project switched to with `utd project switch`, scoping its board until cleared
+fn active_project() -> Result<Option<String>> {
+    match std::fs::read_to_string(board_file(".utd-project")) {
+        Ok(project) if !project.trim().is_empty() => Ok(Some(project.trim().to_owned())),
+        Ok(_) => Ok(None),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
 }
 
-fn write_to_file(file: &mut File, tasks: &Tasks) {
-    writeln!(file, "{}", serde_json::to_string_pretty(tasks).unwrap()).unwrap();
-    trace!("tasks updated");
+fn set_project(project: Option<&str>) -> Result<()> {
+    match project {
+        Some(project) => std::fs::write(board_file(".utd-project"), project)?,
+        None => {
+            if let Err(e) = std::fs::remove_file(board_file(".utd-project")) {
+                if e.kind() != std::io::ErrorKind::NotFound {
+                    return Err(e.into());
+                }
+            }
+        }
+    }
+    debug!("project set to {:?}", project);
+    Ok(())
 }
 
-fn state_file_contents() -> Result<Tasks> {
-    let mut path = data_dir();
-    path.push(".utd.json");
-    let read_file = std::fs::OpenOptions::new()
-        .create(true)
-        .write(true)
-        .read(true)
-        .open(&path)
-        .unwrap();
-    let mut buf_reader = std::io::BufReader::new(read_file);
-    let mut contents = String::new();
-    buf_reader.read_to_string(&mut contents)?;
-    // empty list
-    if contents.is_empty() {
-        contents.push_str("[]");
+fn journal_contents() -> Result<Vec<JournalEntry>> {
+    match std::fs::read_to_string(board_file(".utd-journal.jsonl")) {
+        Ok(contents) => contents
+            .lines()
+            .filter(|f| !f.trim().is_empty())
+            .map(|f| Ok(serde_json::from_str(f)?))
+            .collect(),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
     }
-    let tasks: Tasks = serde_json::from_str(&contents)?;
-    Ok(tasks)
 }
 
-fn update_file(tasks: &Tasks) -> Result<()> {
-    let mut path = data_dir();
-    path.push(".temp");
-    let mut temp = std::fs::OpenOptions::new()
+fn append_journal(entries: &[JournalEntry]) -> Result<()> {
+    if entries.is_empty() {
+        return Ok(());
+    }
+    let mut journal = std::fs::OpenOptions::new()
         .create(true)
-        .write(true)
-        .open(&path)?;
-    write_to_file(&mut temp, tasks);
-    let mut original = data_dir();
-    original.push(".utd.json");
-    std::fs::rename(path, original)?;
+        .append(true)
+        .open(board_file(".utd-journal.jsonl"))?;
+    for entry in entries.iter() {
+        writeln!(journal, "{}", serde_json::to_string(entry)?)?;
+    }
+    trace!("{} journal entries written", entries.len());
     Ok(())
 }
 
-fn greeting() -> String {
-    let greetings = || -> Vec<String> {
-        vec![
-            "Here's is your board",
-            "Remember...",
-            "Let's get things done",
-            "Focus",
-        ]
-        .into_iter()
-        .map(String::from)
-        .collect()
+fn interrupt(note: &str, id: Option<i64>) -> Result<()> {
+    let task = match id {
+        Some(id) => Some(id),
+        None => state_file_contents()?
+            .iter()
+            .find(|f| f.timer_started.is_some())
+            .map(|f| f.id),
     };
+    append_journal(&[JournalEntry::now(Event::Interruption {
+        note: note.to_owned(),
+        task,
+    })])
+}
 
-    let greetings = greetings();
-    let num = rand::thread_rng().gen_range(0..greetings.len());
-    greetings.get(num).unwrap().to_owned()
+fn show_focus_report(days: i64) -> Result<()> {
+    let since = dates::today() - chrono::Duration::days(days - 1);
+    for (day, focus) in focus_report(&journal_contents()?).range(since..) {
+        let interruptions = match focus.interruptions.len() {
+            0 => String::from("no interruptions"),
+            1 => format!("1 interruption ({})", focus.interruptions[0]),
+            n => format!("{n} interruptions ({})", focus.interruptions.join(", ")),
+        };
+        let label = day.format("%a %d %b").to_string();
+        println!(
+            "{:<12}{:<10}{}",
+            label,
+            format_duration(focus.focus),
+            interruptions
+        );
+    }
+    Ok(())
+}
+
+/// Open tasks at the end of each day, counting archived ones
+fn show_burndown(days: i64, plain: bool) -> Result<()> {
+    if days < 1 {
+        return Err("--days must be at least 1".into());
+    }
+    let today = dates::today();
+    let days: Vec<chrono::NaiveDate> = (0..days)
+        .rev()
+        .map(|f| today - chrono::Duration::days(f))
+        .collect();
+    let mut ends = Vec::with_capacity(days.len());
+    for day in days.iter() {
+        ends.push(dates::local_midnight(*day + chrono::Duration::days(1))?);
+    }
+    let mut tasks = state_file_contents()?;
+    tasks.extend(archive_file_contents()?);
+    let counts = open_counts(&tasks, &ends);
+    let chart = bar_chart(&counts, 8, plain);
+    let max = counts.iter().copied().max().unwrap_or(0).max(1);
+    let offset = max.to_string().len() + 2;
+    for line in chart.iter() {
+        println!("{line}");
+    }
+    let first = days[0].format("%d %b").to_string();
+    let last = days[days.len() - 1].format("%d %b").to_string();
+    let width = (counts.len() * 3 - 1).saturating_sub(first.len());
+    if width > last.len() {
+        println!("{:offset$}{first}{last:>width$}", "");
+    } else {
+        println!("{:offset$}{first}", "");
+    }
+    let (start, end) = (counts[0], counts[counts.len() - 1]);
+    println!(
+        "{} open, {:+} over {} day(s)",
+        end,
+        end as i64 - start as i64,
+        counts.len()
+    );
+    Ok(())
 }
 
-fn hex_to_rgb(hex_colour: &str) -> (u8, u8, u8) {
-    let first = u8::from_str_radix(&hex_colour[1..3], 16).unwrap();
-    let second = u8::from_str_radix(&hex_colour[3..5], 16).unwrap();
-    let third = u8::from_str_radix(&hex_colour[5..7], 16).unwrap();
-    (first, second, third)
+/// The board title: the output of the title command, the fixed text or a random greeting
+fn greeting(title: &Title, translations: &Translations) -> String {
+    if let Some(ref command) = title.command {
+        match title_command(command) {
+            Ok(text) if !text.is_empty() => return text,
+            Ok(_) => debug!("title command printed nothing"),
+            Err(e) => warn!("title command failed: {e}"),
+        }
+    }
+    if let Some(ref text) = title.text {
+        return text.to_owned();
+    }
+    if !title.greeting() {
+        return String::new();
+    }
+    let greetings = match title.greetings {
+        Some(ref greetings) if !greetings.is_empty() => greetings.to_vec(),
+        _ => translations.greetings(),
+    };
+    let num = rand::thread_rng().gen_range(0..greetings.len());
+    greetings.get(num).unwrap().to_owned()
 }
@@ -0,0 +1,740 @@
+use chrono::Utc;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+use crate::dates::parse_offset;
+use crate::{
+    colour_support, format_age, format_duration, parse_colour, ColourSupport, Config, Configurable,
+    Expired, Goal, IconProfile, Layout, Task, Tasks,
+};
+
+/// How a span of text should be drawn
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Style {
+    /// Foreground colour as `(red, green, blue)`
+    pub colour: Option<(u8, u8, u8)>,
+    pub bold: bool,
+    pub italic: bool,
+    pub underline: bool,
+    pub dimmed: bool,
+    pub strikethrough: bool,
+}
+
+impl Style {
+    /// The ansi style, with the colour degraded to what the terminal supports
+    pub fn to_ansi(&self) -> ansi_term::Style {
+        let support = colour_support();
+        if support == ColourSupport::None {
+            return ansi_term::Style::new();
+        }
+        let mut style = match self.colour.and_then(|f| support.colour(f)) {
+            Some(colour) => colour.normal(),
+            None => ansi_term::Style::new(),
+        };
+        if self.bold {
+            style = style.bold();
+        }
+        if self.italic {
+            style = style.italic();
+        }
+        if self.underline {
+            style = style.underline();
+        }
+        if self.dimmed {
+            style = style.dimmed();
+        }
+        if self.strikethrough {
+            style = style.strikethrough();
+        }
+        style
+    }
+}
+
+/// A run of text sharing one style
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub text: String,
+    pub style: Style,
+}
+
+impl Span {
+    pub fn new(text: impl Into<String>, style: Style) -> Self {
+        Self {
+            text: text.into(),
+            style,
+        }
+    }
+
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self::new(text, Style::default())
+    }
+
+    pub fn to_ansi_string(&self) -> String {
+        self.style.to_ansi().paint(self.text.as_str()).to_string()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LineKind {
+    /// The greeting spanning the whole board
+    Title,
+    /// A section heading such as `to-do [1/3]`
+    Heading,
+    Entry,
+    /// Goal progress below the sections
+    Footer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StyledLine {
+    pub kind: LineKind,
+    /// Leading spaces configured for the section
+    pub indent: usize,
+    pub spans: Vec<Span>,
+    /// The spans split into table cells in the columns layout, empty otherwise
+    pub cells: Vec<Vec<Span>>,
+}
+
+impl StyledLine {
+    pub fn to_ansi_string(&self) -> String {
+        let mut line = " ".repeat(self.indent);
+        for span in self.spans.iter() {
+            line.push_str(&span.to_ansi_string());
+        }
+        line
+    }
+
+    pub fn to_plain_string(&self) -> String {
+        let mut line = " ".repeat(self.indent);
+        for span in self.spans.iter() {
+            line.push_str(&span.text);
+        }
+        line
+    }
+
+    /// Each cell drawn on its own, the first one indented like the line
+    pub fn cell_strings(&self) -> Vec<String> {
+        self.cells
+            .iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                let mut text = " ".repeat(if index == 0 { self.indent } else { 0 });
+                for span in cell.iter() {
+                    text.push_str(&span.to_ansi_string());
+                }
+                text
+            })
+            .collect()
+    }
+}
+
+/// One of the sections entries are listed in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardSection {
+    Todo,
+    InProgress,
+    Notes,
+}
+
+impl BoardSection {
+    pub const ALL: [BoardSection; 3] = [
+        BoardSection::Todo,
+        BoardSection::InProgress,
+        BoardSection::Notes,
+    ];
+}
+
+impl std::str::FromStr for BoardSection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "todo" | "to-do" => Ok(BoardSection::Todo),
+            "in-progress" => Ok(BoardSection::InProgress),
+            "notes" => Ok(BoardSection::Notes),
+            _ => Err(format!(
+                "unknown section {s:?}, expected todo, in-progress or notes"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RenderOptions {
+    /// Text of the title line, left out when `None` or disabled in the config
+    pub title: Option<String>,
+    pub show_snoozed: bool,
+    /// Leave out section, entry, pin and tag icons
+    pub plain: bool,
+    /// Columns available to the board, entries are only fitted when set
+    pub width: Option<usize>,
+    /// Goals shown with their progress in the footer, unless past their deadline
+    pub goals: Vec<Goal>,
+    /// Sections left off the board, heading and entries
+    pub hidden: Vec<BoardSection>,
+}
+
+/// Lays out the board as styled lines: the title, then the to-do, in progress and notes
+/// sections, each with pinned entries first.
+pub fn render_board(tasks: &Tasks, config: &Config, options: RenderOptions) -> Vec<StyledLine> {
+    let sections = config.sections.as_ref().cloned().unwrap_or_default();
+    let all = tasks;
+    let translations = config.translations();
+    let mut tasks: Vec<&Task> = tasks
+        .iter()
+        .filter(|f| options.show_snoozed || !f.is_snoozed())
+        .collect();
+    // pinned entries lead their section whatever the sort order
+    tasks.sort_by_key(|f| !f.pinned);
+
+    let mut lines = Vec::new();
+    if let Some(ref title) = options.title {
+        if !config.disable_title.unwrap_or(false) {
+            lines.push(StyledLine {
+                kind: LineKind::Title,
+                indent: 0,
+                spans: vec![section_title(
+                    &sections.title.unwrap_or_default(),
+                    title,
+                    options.plain,
+                )],
+                cells: Vec::new(),
+            });
+        }
+    }
+
+    let todo = sections.todo.unwrap_or_default();
+    let shown = |section: BoardSection| !options.hidden.contains(&section);
+    let set_tasks: Vec<_> = tasks
+        .iter()
+        .filter(|f| f.is_task && !f.in_progress && shown(BoardSection::Todo))
+        .collect();
+    if !set_tasks.is_empty() {
+        let task_count = tasks.iter().filter(|f| f.is_task).count();
+        let completed_count = tasks.iter().filter(|f| f.is_task && f.is_done).count();
+        let heading = format!(
+            "{} [{}/{}]",
+            translations.get("todo"),
+            completed_count,
+            task_count
+        );
+        let heading = with_remaining(heading, &set_tasks);
+        lines.push(heading_line(&todo, &heading, options.plain));
+    }
+    for task in set_tasks {
+        let blockers: Vec<String> = task
+            .blockers(all)
+            .iter()
+            .map(|f| f.id.to_string())
+            .collect();
+        let value = match (&task.checked_by, task.is_waiting()) {
+            (Some(checker), _) => format!("{} (checked by {})", &task.name, checker),
+            _ if !blockers.is_empty() => {
+                format!("{} (blocked by {})", &task.name, blockers.join(", "))
+            }
+            (None, true) => match task.assignee {
+                Some(ref assignee) => format!("{} (waiting on {})", &task.name, assignee),
+                None => format!("{} (waiting)", &task.name),
+            },
+            (None, false) => task.name.to_owned(),
+        };
+        lines.push(entry_line(&todo, config, task, value, options.plain));
+    }
+
+    let in_progress = sections.in_progress.unwrap_or_default();
+    let started: Vec<_> = tasks
+        .iter()
+        .filter(|f| f.in_progress && shown(BoardSection::InProgress))
+        .collect();
+    if !started.is_empty() {
+        let heading = with_remaining(translations.get("in-progress"), &started);
+        let mut line = heading_line(&in_progress, &heading, options.plain);
+        if let Some(limit) = config.wip_limit {
+            // a full section stands out, a section with room takes the heading's colour
+            let colour = if started.len() >= limit {
+                config.wip_colour()
+            } else {
+                in_progress.title_colour()
+            };
+            line.spans.push(Span::new(
+                format!(" [{}/{}]", started.len(), limit),
+                Style {
+                    colour: parse_colour(colour).ok(),
+                    ..Style::default()
+                },
+            ));
+        }
+        lines.push(line);
+    }
+    for task in started {
+        let value = task.name.to_owned();
+        lines.push(entry_line(&in_progress, config, task, value, options.plain));
+    }
+
+    let notes_section = sections.notes.unwrap_or_default();
+    let notes: Vec<_> = tasks
+        .iter()
+        .filter(|f| !f.is_task && shown(BoardSection::Notes))
+        .collect();
+    if !notes.is_empty() {
+        lines.push(heading_line(
+            &notes_section,
+            &translations.get("notes"),
+            options.plain,
+        ));
+    }
+    for task in notes {
+        let value = task.name.to_owned();
+        lines.push(entry_line(
+            &notes_section,
+            config,
+            task,
+            value,
+            options.plain,
+        ));
+    }
+    for goal in options.goals.iter().filter(|f| f.is_active()) {
+        let style = Style {
+            colour: parse_colour(&todo_colour(config)).ok(),
+            dimmed: goal.is_met(),
+            ..Style::default()
+        };
+        lines.push(StyledLine {
+            kind: LineKind::Footer,
+            indent: 0,
+            spans: vec![
+                Span::new(goal.progress_bar(10), style),
+                Span::plain(format!(" {}", goal.name)),
+            ],
+            cells: Vec::new(),
+        });
+    }
+    fit_entries(lines, config, options.width)
+}
+
+/// Appends the estimated work left in a section, e.g. `to-do [1/4] ~3h 30m`
+fn with_remaining(heading: String, tasks: &[&&Task]) -> String {
+    let remaining: Vec<u64> = tasks.iter().filter_map(|f| f.remaining()).collect();
+    if remaining.is_empty() {
+        heading
+    } else {
+        format!("{} ~{}", heading, format_duration(remaining.iter().sum()))
+    }
+}
+
+/// A footer such as `[███░░░░░░░] ✔ 4 done · ▶ 2 in progress · ☐ 7 pending (31%)`,
+/// or nothing without tasks
+pub fn summary_footer(tasks: &Tasks, config: &Config, plain: bool) -> Option<StyledLine> {
+    let total = tasks.iter().filter(|f| f.is_task).count();
+    if total == 0 {
+        return None;
+    }
+    let done = tasks.iter().filter(|f| f.is_task && f.is_done).count();
+    let started = tasks
+        .iter()
+        .filter(|f| f.is_task && !f.is_done && f.in_progress)
+        .count();
+    let pending = total - done - started;
+    let ascii = plain || crate::icon_profile() == IconProfile::Ascii;
+    let (full, empty, marks, separator) = if ascii {
+        ('#', '-', ["x ", "> ", "- "], " | ")
+    } else {
+        (
+            '█',
+            '░',
+            ["\u{2714} ", "\u{25b6} ", "\u{2610} "],
+            " \u{b7} ",
+        )
+    };
+    let width = 10;
+    let filled = width * done / total;
+    let bar = format!(
+        "[{}{}] ",
+        full.to_string().repeat(filled),
+        empty.to_string().repeat(width - filled)
+    );
+    let translations = config.translations();
+    let counts = [
+        (done, translations.get("done")),
+        (started, translations.get("in-progress")),
+        (pending, translations.get("pending")),
+    ];
+    let text: Vec<String> = counts
+        .iter()
+        .zip(marks)
+        .map(|((count, label), mark)| format!("{mark}{count} {label}"))
+        .collect();
+    Some(StyledLine {
+        kind: LineKind::Footer,
+        indent: 0,
+        spans: vec![
+            Span::new(
+                bar,
+                Style {
+                    colour: parse_colour(&todo_colour(config)).ok(),
+                    ..Style::default()
+                },
+            ),
+            Span::plain(format!(
+                "{} ({}%)",
+                text.join(separator),
+                100 * done / total
+            )),
+        ],
+        cells: Vec::new(),
+    })
+}
+
+fn todo_colour(config: &Config) -> String {
+    let sections = config.sections.as_ref().cloned().unwrap_or_default();
+    sections.todo.unwrap_or_default().title_colour().to_owned()
+}
+
+/// Wraps or truncates entries wider than the available width or `max-title-width`
+fn fit_entries(lines: Vec<StyledLine>, config: &Config, width: Option<usize>) -> Vec<StyledLine> {
+    let layout = config.layout.as_ref().cloned().unwrap_or_default();
+    if width.is_none() && layout.max_title_width.is_none() {
+        return lines;
+    }
+    let mut fitted = Vec::with_capacity(lines.len());
+    for line in lines {
+        let limit = match (width, layout.max_title_width) {
+            (Some(width), Some(max)) => width.saturating_sub(line.indent).min(max),
+            (Some(width), None) => width.saturating_sub(line.indent),
+            (None, Some(max)) => max,
+            (None, None) => usize::MAX,
+        };
+        let text_width: usize = line.spans.iter().map(|f| f.text.width()).sum();
+        // too narrow to fit anything useful, leave it to the terminal. Cells are
+        // fitted by the table.
+        if line.kind != LineKind::Entry
+            || !line.cells.is_empty()
+            || text_width <= limit
+            || limit < 8
+        {
+            fitted.push(line);
+        } else if layout.truncate() {
+            fitted.push(StyledLine {
+                spans: truncate_spans(line.spans, limit),
+                ..line
+            });
+        } else {
+            for (i, spans) in wrap_spans(line.spans, limit).into_iter().enumerate() {
+                fitted.push(StyledLine {
+                    kind: line.kind,
+                    // continuation lines hang under the entry text
+                    indent: if i == 0 { line.indent } else { line.indent + 2 },
+                    spans,
+                    cells: Vec::new(),
+                });
+            }
+        }
+    }
+    fitted
+}
+
+fn truncate_spans(spans: Vec<Span>, limit: usize) -> Vec<Span> {
+    let mut remaining = limit - 1;
+    let mut truncated = Vec::new();
+    for span in spans {
+        let mut text = String::new();
+        for c in span.text.chars() {
+            let width = c.width().unwrap_or(0);
+            if width > remaining {
+                text.push('…');
+                truncated.push(Span::new(text, span.style));
+                return truncated;
+            }
+            remaining -= width;
+            text.push(c);
+        }
+        truncated.push(Span::new(text, span.style));
+    }
+    truncated
+}
+
+/// Breaks spans into lines of at most `limit` columns at spaces, splitting longer words
+fn wrap_spans(spans: Vec<Span>, limit: usize) -> Vec<Vec<Span>> {
+    let mut lines: Vec<Vec<Span>> = vec![Vec::new()];
+    let mut used = 0;
+    for span in spans {
+        for word in span.text.split_inclusive(' ') {
+            let mut word = word.to_owned();
+            while !word.is_empty() {
+                let width = word.trim_end().width();
+                if used > 0 && used + width > limit {
+                    lines.push(Vec::new());
+                    used = 0;
+                }
+                let taken = if width > limit {
+                    let mut cut = 0;
+                    let mut cut_width = 0;
+                    for (i, c) in word.char_indices() {
+                        let w = c.width().unwrap_or(0);
+                        if cut_width + w > limit {
+                            break;
+                        }
+                        cut_width += w;
+                        cut = i + c.len_utf8();
+                    }
+                    word.drain(..cut).collect::<String>()
+                } else {
+                    std::mem::take(&mut word)
+                };
+                used += taken.width();
+                let line = lines.last_mut().expect("at least one line");
+                match line.last_mut() {
+                    Some(last) if last.style == span.style => last.text.push_str(&taken),
+                    _ => line.push(Span::new(taken, span.style)),
+                }
+            }
+        }
+    }
+    for line in lines.iter_mut() {
+        if let Some(last) = line.last_mut() {
+            last.text.truncate(last.text.trim_end().len());
+        }
+    }
+    lines
+}
+
+/// Styles `value` as a title of `title`, with its icon
+pub fn title_span(title: &impl Configurable, value: &str) -> Span {
+    section_title(title, value, false)
+}
+
+fn section_title(title: &impl Configurable, value: &str, plain: bool) -> Span {
+    let text = if plain {
+        value.to_owned()
+    } else if !title.title_icon_suffix() {
+        format!("{}{}", title.title_icon(), value)
+    } else {
+        format!("{}{}", value, title.title_icon())
+    };
+    Span::new(
+        text,
+        Style {
+            colour: parse_colour(title.title_colour()).ok(),
+            bold: title.title_bold(),
+            italic: title.title_italic(),
+            underline: title.title_underline(),
+            ..Style::default()
+        },
+    )
+}
+
+fn heading_line(section: &impl Configurable, heading: &str, plain: bool) -> StyledLine {
+    StyledLine {
+        kind: LineKind::Heading,
+        indent: section.indent_spaces().into(),
+        spans: vec![section_title(section, heading, plain)],
+        cells: Vec::new(),
+    }
+}
+
+fn entry_line(
+    section: &impl Configurable,
+    config: &Config,
+    task: &Task,
+    value: String,
+    plain: bool,
+) -> StyledLine {
+    let completed = task.is_done;
+    let columns = config.layout.as_ref().is_some_and(Layout::columns);
+    let value = if columns {
+        value
+    } else {
+        format!("{}. {}", task.id, value)
+    };
+    let age = config.age.as_ref().cloned().unwrap_or_default();
+    let stale =
+        matches!(parse_offset(age.stale_after()), Ok(threshold) if task.is_stale(threshold));
+    let value = if stale && !plain {
+        format!("{}{}", age.stale_icon(), value)
+    } else {
+        value
+    };
+    let pin = task
+        .pinned
+        .then(|| config.pins.as_ref().cloned().unwrap_or_default());
+    let expired = task
+        .expired
+        .then(|| config.expired.as_ref().cloned().unwrap_or_default());
+    let value = match (&expired, &pin) {
+        _ if plain => value,
+        (Some(expired), _) => format!("{}{}", expired.icon(), value),
+        (None, Some(pin)) => format!("{}{}", pin.icon(), value),
+        (None, None) => value,
+    };
+    let value = if plain {
+        value
+    } else if section.entry_icon_suffix() {
+        if completed {
+            format!("{}{}", value, section.completed_icon())
+        } else {
+            format!("{}{}", value, section.entry_icon())
+        }
+    } else if completed {
+        format!("{}{}", section.completed_icon(), value)
+    } else {
+        format!("{}{}", section.entry_icon(), value)
+    };
+    let due = config.due.as_ref().cloned().unwrap_or_default();
+    let priority_colour = match config
+        .priority_levels()
+        .iter()
+        .find(|f| f.name == task.priority)
+        .and_then(|f| f.colour.as_ref())
+    {
+        Some(colour) => parse_colour(colour),
+        None => match task.priority.as_str() {
+            "low" => parse_colour(section.colour_low()),
+            "high" => parse_colour(section.colour_high()),
+            _ => parse_colour(section.colour_normal()),
+        },
+    };
+    let colour = match (completed, pin) {
+        (false, _) if expired.is_some() => {
+            parse_colour(expired.as_ref().map(Expired::colour).unwrap_or_default())
+        }
+        (false, _) if task.is_overdue() => parse_colour(due.overdue_colour()),
+        (false, _) if task.is_due_today() => parse_colour(due.today_colour()),
+        (false, Some(pin)) => parse_colour(pin.colour()),
+        (false, None) => priority_colour.clone(),
+        (true, _) => parse_colour(section.colour_completed()),
+    }
+    .ok();
+    let title = vec![Span::new(
+        value,
+        Style {
+            colour,
+            bold: section.entry_bold(),
+            italic: section.entry_italic(),
+            dimmed: (completed && section.dim_completed()) || task.expired,
+            strikethrough: completed || task.expired,
+            ..Style::default()
+        },
+    )];
+
+    let mut age_spans = Vec::new();
+    if age.show() && task.is_task && !task.is_done {
+        let elapsed = Utc::now() - task.created();
+        let after = |offset: &str| matches!(parse_offset(offset), Ok(offset) if elapsed >= offset);
+        let colour = if after(age.ancient_after()) {
+            age.ancient_colour()
+        } else if after(age.stale_after()) {
+            age.stale_colour()
+        } else {
+            age.colour()
+        };
+        age_spans.push(Span::new(
+            format_age(elapsed.num_seconds().max(0) as u64),
+            Style {
+                colour: parse_colour(colour).ok(),
+                ..Style::default()
+            },
+        ));
+    }
+
+    let mut estimate_spans = Vec::new();
+    if let Some(estimate) = task.estimate {
+        estimate_spans.push(Span::new(
+            format!("~{}", format_duration(estimate)),
+            Style {
+                dimmed: true,
+                ..Style::default()
+            },
+        ));
+    }
+
+    let mut tag_spans = Vec::new();
+    let tags = config.tags.as_ref().cloned().unwrap_or_default();
+    if !task.tags.is_empty() {
+        let style = |colour: &str| Style {
+            colour: parse_colour(colour).ok(),
+            bold: tags.bold(),
+            italic: tags.italic(),
+            underline: tags.underline(),
+            ..Style::default()
+        };
+        let icon = match tags.icon() {
+            icon if plain || icon.is_empty() => None,
+            icon => Some(Span::new(icon, style(tags.colour()))),
+        };
+        if !tags.icon_suffix() {
+            tag_spans.extend(icon.clone());
+        }
+        for (index, tag) in task.tags.iter().enumerate() {
+            if index > 0 {
+                tag_spans.push(Span::plain(" "));
+            }
+            let mut tag_style = style(tags.colour_for(tag));
+            if tags.inherit_priority_colour() && tags.own_colour(tag).is_none() {
+                tag_style.colour = priority_colour.clone().ok();
+            }
+            tag_spans.push(Span::new(tags.label(tag), tag_style));
+        }
+        if tags.icon_suffix() {
+            tag_spans.extend(icon);
+        }
+    }
+    if let Some(ref project) = task.project {
+        let mut spans = vec![Span::new(
+            format!("+{project}"),
+            Style {
+                colour: parse_colour(tags.colour()).ok(),
+                bold: true,
+                ..Style::default()
+            },
+        )];
+        if !tag_spans.is_empty() {
+            spans.push(Span::plain(" "));
+        }
+        spans.append(&mut tag_spans);
+        tag_spans = spans;
+    }
+
+    let cells = if columns {
+        let badge = if task.is_task && !completed {
+            vec![Span::new(
+                task.priority.to_owned(),
+                Style {
+                    colour: priority_colour.ok(),
+                    ..Style::default()
+                },
+            )]
+        } else {
+            Vec::new()
+        };
+        let mut title = title.clone();
+        if !estimate_spans.is_empty() {
+            title.push(Span::plain(" "));
+            title.extend(estimate_spans.clone());
+        }
+        vec![
+            vec![Span::plain(task.id.to_string())],
+            title,
+            badge,
+            age_spans.clone(),
+            tag_spans.clone(),
+        ]
+    } else {
+        Vec::new()
+    };
+    // without a table the columns still read as one line, in the lines order
+    let mut spans = title;
+    for extra in [age_spans, estimate_spans, tag_spans] {
+        if !extra.is_empty() {
+            spans.push(Span::plain(" "));
+            spans.extend(extra);
+        }
+    }
+    if columns {
+        spans.insert(0, Span::plain(format!("{}. ", task.id)));
+    }
+    StyledLine {
+        kind: LineKind::Entry,
+        indent: usize::from(section.indent_spaces()) + 2,
+        spans,
+        cells,
+    }
+}
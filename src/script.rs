@@ -0,0 +1,51 @@
+use rhai::{Array, Dynamic, Engine, Scope};
+
+use crate::{scripts_dir, Tasks};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// Scripts in the scripts directory whose name can be typed as a subcommand, e.g.
+/// `weekly.rhai` runs as `utd weekly`
+pub fn script_names() -> Vec<String> {
+    let entries = match std::fs::read_dir(scripts_dir()) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|f| {
+            let path = f.ok()?.path();
+            if path.extension()? != "rhai" {
+                return None;
+            }
+            Some(path.file_stem()?.to_string_lossy().into_owned())
+        })
+        .filter(|f| {
+            f.chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_'))
+        })
+        .collect();
+    names.sort();
+    names
+}
+
+/// Runs the script `name` with the board as `tasks` and the words after its name as
+/// `args`. Tasks are maps with the fields of the board file, e.g. `task.name` or
+/// `task.is_done`.
+pub fn run_script(name: &str, tasks: &Tasks, args: &[String]) -> Result<()> {
+    let path = scripts_dir().join(format!("{name}.rhai"));
+    if !path.exists() {
+        return Err(format!("no script {}", path.display()).into());
+    }
+    let engine = Engine::new();
+    let mut scope = Scope::new();
+    // rhai errors hold values that can't leave the thread
+    let board = rhai::serde::to_dynamic(tasks).map_err(|e| e.to_string())?;
+    scope.push("tasks", board);
+    scope.push(
+        "args",
+        args.iter().cloned().map(Dynamic::from).collect::<Array>(),
+    );
+    engine
+        .run_file_with_scope(&mut scope, path)
+        .map_err(|e| format!("{name}: {e}").into())
+}
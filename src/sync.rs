@@ -0,0 +1,757 @@
+use std::collections::VecDeque;
+use std::fmt;
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use chrono::Utc;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{debug, warn};
+
+use crate::{OAuthApp, Task, Tasks};
+
+type Result<T> = std::result::Result<T, Box<dyn std::error::Error + Send + Sync>>;
+
+/// A task as a hosted task list keeps it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteTask {
+    pub id: String,
+    pub title: String,
+    pub done: bool,
+    pub notes: Option<String>,
+}
+
+/// A hosted task list the board is synced with
+pub trait Provider {
+    /// Every task of the list, completed ones included
+    fn tasks(&self) -> Result<Vec<RemoteTask>>;
+    /// Adds `task` to the list, returning its remote id
+    fn create(&self, task: &Task) -> Result<String>;
+    fn set_done(&self, id: &str, done: bool) -> Result<()>;
+}
+
+/// The remote task each entry is linked to, kept per board and provider between syncs
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SyncState {
+    pub links: Vec<Link>,
+    /// Entries whose remote task was deleted, not to be created there again
+    #[serde(default)]
+    pub detached: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Link {
+    /// Timestamp of the entry
+    pub timestamp: String,
+    pub remote: String,
+    /// Completion both sides agreed on at the last sync
+    pub done: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncReport {
+    /// Open entries added to the remote list
+    pub created: usize,
+    /// Remote tasks added to the board
+    pub added: usize,
+    /// Completion changes sent to the remote list
+    pub pushed: usize,
+    /// Completion changes taken from the remote list
+    pub pulled: usize,
+}
+
+impl fmt::Display for SyncReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} sent, {} received, {} updated there, {} updated here",
+            self.created, self.added, self.pushed, self.pulled
+        )
+    }
+}
+
+/// Brings the board and the remote list in line. Of an entry and its remote task, the
+/// side whose completion changed since the last sync wins. Open tasks new to either
+/// side are added to the other, `new_entry` making the entries. An entry deleted from
+/// the board is completed remotely rather than deleted. When a remote call fails,
+/// `tasks` and `state` are left as far as the sync got, to be saved all the same.
+pub fn sync(
+    tasks: &mut Tasks,
+    state: &mut SyncState,
+    provider: &dyn Provider,
+    new_entry: impl FnMut(&RemoteTask) -> Task,
+) -> Result<SyncReport> {
+    let remote = provider.tasks()?;
+    let mut report = SyncReport::default();
+    let mut links = Vec::new();
+    let mut pending: VecDeque<Link> = std::mem::take(&mut state.links).into();
+    let result = sync_links(
+        tasks,
+        state,
+        &mut pending,
+        &mut links,
+        provider,
+        &remote,
+        new_entry,
+        &mut report,
+    );
+    // links not reached yet are kept as they were
+    links.extend(pending);
+    state.links = links;
+    result.map(|()| report)
+}
+
+/// Takes the links of `pending` one by one, moving them to `links` once they are synced
+#[allow(clippy::too_many_arguments)]
+fn sync_links(
+    tasks: &mut Tasks,
+    state: &mut SyncState,
+    pending: &mut VecDeque<Link>,
+    links: &mut Vec<Link>,
+    provider: &dyn Provider,
+    remote: &[RemoteTask],
+    mut new_entry: impl FnMut(&RemoteTask) -> Task,
+    report: &mut SyncReport,
+) -> Result<()> {
+    while let Some(link) = pending.pop_front() {
+        let theirs = match remote.iter().find(|f| f.id == link.remote) {
+            Some(theirs) => theirs,
+            None => {
+                debug!("remote task of {} is gone, unlinking", link.timestamp);
+                state.detached.push(link.timestamp);
+                continue;
+            }
+        };
+        let task = match tasks.iter_mut().find(|f| f.timestamp == link.timestamp) {
+            Some(task) => task,
+            None => {
+                if !theirs.done {
+                    if let Err(e) = provider.set_done(&theirs.id, true) {
+                        pending.push_front(link);
+                        return Err(e);
+                    }
+                    report.pushed += 1;
+                }
+                continue;
+            }
+        };
+        if task.is_done != link.done {
+            if theirs.done != task.is_done {
+                if let Err(e) = provider.set_done(&theirs.id, task.is_done) {
+                    pending.push_front(link);
+                    return Err(e);
+                }
+                report.pushed += 1;
+            }
+        } else if theirs.done != task.is_done {
+            if theirs.done {
+                task.complete();
+            } else {
+                task.is_done = false;
+                task.completed_at = None;
+                task.record("reopened");
+            }
+            report.pulled += 1;
+        }
+        links.push(Link {
+            done: task.is_done,
+            ..link
+        });
+    }
+    let unlinked: Vec<&Task> = tasks
+        .iter()
+        .filter(|f| {
+            f.is_task
+                && !f.is_done
+                && !f.cancelled
+                && !f.expired
+                && !state.detached.contains(&f.timestamp)
+                && !links.iter().any(|link| link.timestamp == f.timestamp)
+        })
+        .collect();
+    for task in unlinked {
+        let id = provider.create(task)?;
+        links.push(Link {
+            timestamp: task.timestamp.clone(),
+            remote: id,
+            done: false,
+        });
+        report.created += 1;
+    }
+    let unlinked: Vec<&RemoteTask> = remote
+        .iter()
+        .filter(|f| !f.done && !links.iter().any(|link| link.remote == f.id))
+        .collect();
+    for theirs in unlinked {
+        let task = new_entry(theirs);
+        links.push(Link {
+            timestamp: task.timestamp.clone(),
+            remote: theirs.id.clone(),
+            done: task.is_done,
+        });
+        tasks.push(task);
+        report.added += 1;
+    }
+    // entries deleted since are of no more interest
+    state
+        .detached
+        .retain(|timestamp| tasks.iter().any(|f| f.timestamp == *timestamp));
+    Ok(())
+}
+
+/// Endpoints of an OAuth provider, signed in to through the browser
+pub struct OAuth<'a> {
+    pub authorize_url: String,
+    pub token_url: String,
+    pub scope: &'a str,
+    /// Parameters the provider needs to hand out a refresh token, e.g. `&prompt=consent`
+    pub extra: &'a str,
+    pub app: &'a OAuthApp,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    refresh_token: Option<String>,
+}
+
+impl OAuth<'_> {
+    /// An access token and the refresh token to keep for next time. `refresh_token`
+    /// is tried first, signing in through the browser when there is none or it expired.
+    pub fn access_token(&self, refresh_token: Option<&str>) -> Result<(String, Option<String>)> {
+        if let Some(refresh_token) = refresh_token {
+            match self.token(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", refresh_token),
+            ]) {
+                Ok(token) => {
+                    let refresh_token = token.refresh_token.or(Some(refresh_token.to_owned()));
+                    return Ok((token.access_token, refresh_token));
+                }
+                Err(e) => warn!("cannot refresh the access token, signing in again: {e}"),
+            }
+        }
+        let server = tiny_http::Server::http("127.0.0.1:0")?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|f| f.port())
+            .ok_or("cannot listen for the sign-in")?;
+        let redirect_uri = format!("http://127.0.0.1:{port}");
+        let random = |length: usize| -> String {
+            rand::thread_rng()
+                .sample_iter(&rand::distributions::Alphanumeric)
+                .take(length)
+                .map(char::from)
+                .collect()
+        };
+        let verifier = random(64);
+        let challenge = code_challenge(&verifier);
+        let state = random(16);
+        let url = format!(
+            "{}?client_id={}&redirect_uri={}&response_type=code&scope={}&state={state}\
+             &code_challenge={challenge}&code_challenge_method=S256{}",
+            self.authorize_url,
+            encode(self.app.client_id()?),
+            encode(&redirect_uri),
+            encode(self.scope),
+            self.extra,
+        );
+        eprintln!("Sign in on the page opened in your browser, or open:\n{url}");
+        open_url(&url);
+        let code = loop {
+            let request = server.recv()?;
+            let query = request
+                .url()
+                .split_once('?')
+                .map(|(_, query)| query.to_owned())
+                .unwrap_or_default();
+            let value = |key: &str| {
+                query
+                    .split('&')
+                    .filter_map(|f| f.split_once('='))
+                    .find(|(name, _)| *name == key)
+                    .map(|(_, value)| decode(value))
+            };
+            if value("state").as_deref() != Some(state.as_str()) {
+                let _ = request.respond(tiny_http::Response::empty(404));
+                continue;
+            }
+            let code = value("code");
+            let message = match code {
+                Some(_) => "Signed in to utd, this tab can be closed",
+                None => "Signing in to utd failed, see the terminal",
+            };
+            let _ = request.respond(tiny_http::Response::from_string(message));
+            break code.ok_or(format!(
+                "sign-in refused: {}",
+                value("error").unwrap_or_default()
+            ))?;
+        };
+        let token = self.token(&[
+            ("grant_type", "authorization_code"),
+            ("code", &code),
+            ("redirect_uri", &redirect_uri),
+            ("code_verifier", &verifier),
+        ])?;
+        Ok((token.access_token, token.refresh_token))
+    }
+
+    fn token(&self, params: &[(&str, &str)]) -> Result<TokenResponse> {
+        let mut form = params.to_vec();
+        form.push(("client_id", self.app.client_id()?));
+        if let Some(ref secret) = self.app.client_secret {
+            form.push(("client_secret", secret));
+        }
+        Ok(ureq::post(&self.token_url).send_form(&form)?.into_json()?)
+    }
+}
+
+/// The S256 PKCE challenge of `verifier`, its SHA-256 hash in unpadded base64url
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+fn open_url(url: &str) {
+    let mut opener = if cfg!(target_os = "macos") {
+        std::process::Command::new("open")
+    } else if cfg!(target_os = "windows") {
+        let mut command = std::process::Command::new("rundll32");
+        command.arg("url.dll,FileProtocolHandler");
+        command
+    } else {
+        std::process::Command::new("xdg-open")
+    };
+    if let Err(e) = opener.arg(url).spawn() {
+        debug!("cannot open a browser: {e}");
+    }
+}
+
+/// Percent-encodes everything but unreserved characters
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = bytes
+            .get(i + 1..i + 3)
+            .and_then(|f| std::str::from_utf8(f).ok())
+            .and_then(|f| u8::from_str_radix(f, 16).ok());
+        match (bytes[i], escaped) {
+            (b'%', Some(b)) => {
+                decoded.push(b);
+                i += 3;
+                continue;
+            }
+            (b'+', _) => decoded.push(b' '),
+            (b, _) => decoded.push(b),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+const GOOGLE_TASKS: &str = "https://tasks.googleapis.com/tasks/v1";
+
+/// A task list of Google Tasks
+pub struct GoogleTasks {
+    token: String,
+    list: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GooglePage<T> {
+    #[serde(default = "Vec::new")]
+    items: Vec<T>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleList {
+    id: String,
+    title: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GoogleTask {
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    notes: Option<String>,
+}
+
+impl GoogleTasks {
+    pub fn oauth(app: &OAuthApp) -> OAuth<'_> {
+        OAuth {
+            authorize_url: String::from("https://accounts.google.com/o/oauth2/v2/auth"),
+            token_url: String::from("https://oauth2.googleapis.com/token"),
+            scope: "https://www.googleapis.com/auth/tasks",
+            extra: "&access_type=offline&prompt=consent",
+            app,
+        }
+    }
+
+    /// The task list titled `title`, created when there is none
+    pub fn connect(token: String, title: &str) -> Result<Self> {
+        let mut google = Self {
+            token,
+            list: String::new(),
+        };
+        let lists: Vec<GoogleList> = google.pages(&format!("{GOOGLE_TASKS}/users/@me/lists"))?;
+        google.list = match lists.into_iter().find(|f| f.title == title) {
+            Some(list) => list.id,
+            None => {
+                let list: GoogleList = google
+                    .request("POST", &format!("{GOOGLE_TASKS}/users/@me/lists"))
+                    .send_json(serde_json::json!({ "title": title }))?
+                    .into_json()?;
+                debug!("created the task list {}", list.title);
+                list.id
+            }
+        };
+        Ok(google)
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        ureq::request(method, url)
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .timeout(std::time::Duration::from_secs(30))
+    }
+
+    fn pages<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut request = self.request("GET", url).query("maxResults", "100");
+            if let Some(ref page_token) = page_token {
+                request = request.query("pageToken", page_token);
+            }
+            if url.ends_with("/tasks") {
+                request = request
+                    .query("showCompleted", "true")
+                    .query("showHidden", "true");
+            }
+            let page: GooglePage<T> = request.call()?.into_json()?;
+            items.extend(page.items);
+            page_token = match page.next_page_token {
+                Some(next) => Some(next),
+                None => return Ok(items),
+            };
+        }
+    }
+}
+
+impl Provider for GoogleTasks {
+    fn tasks(&self) -> Result<Vec<RemoteTask>> {
+        let tasks: Vec<GoogleTask> =
+            self.pages(&format!("{GOOGLE_TASKS}/lists/{}/tasks", self.list))?;
+        Ok(tasks
+            .into_iter()
+            .map(|f| RemoteTask {
+                id: f.id,
+                title: f.title,
+                done: f.status == "completed",
+                notes: f.notes,
+            })
+            .collect())
+    }
+
+    fn create(&self, task: &Task) -> Result<String> {
+        let created: GoogleTask = self
+            .request("POST", &format!("{GOOGLE_TASKS}/lists/{}/tasks", self.list))
+            .send_json(serde_json::json!({
+                "title": task.name,
+                "notes": task.body,
+                "due": task.due.map(|f| f.to_rfc3339()),
+            }))?
+            .into_json()?;
+        Ok(created.id)
+    }
+
+    fn set_done(&self, id: &str, done: bool) -> Result<()> {
+        let patch = if done {
+            serde_json::json!({ "status": "completed", "completed": Utc::now().to_rfc3339() })
+        } else {
+            serde_json::json!({ "status": "needsAction", "completed": null })
+        };
+        self.request(
+            "PATCH",
+            &format!("{GOOGLE_TASKS}/lists/{}/tasks/{id}", self.list),
+        )
+        .send_json(patch)?;
+        Ok(())
+    }
+}
+
+const GRAPH: &str = "https://graph.microsoft.com/v1.0";
+
+/// A task list of Microsoft To Do, through the Graph API
+pub struct MicrosoftTodo {
+    token: String,
+    list: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GraphPage<T> {
+    #[serde(default = "Vec::new")]
+    value: Vec<T>,
+    #[serde(default, rename = "@odata.nextLink")]
+    next_link: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TodoList {
+    id: String,
+    display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct TodoTask {
+    id: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    status: String,
+    #[serde(default)]
+    body: Option<TodoBody>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TodoBody {
+    #[serde(default)]
+    content: String,
+}
+
+impl MicrosoftTodo {
+    pub fn oauth(app: &OAuthApp) -> OAuth<'_> {
+        let tenant = app.tenant();
+        OAuth {
+            authorize_url: format!(
+                "https://login.microsoftonline.com/{tenant}/oauth2/v2.0/authorize"
+            ),
+            token_url: format!("https://login.microsoftonline.com/{tenant}/oauth2/v2.0/token"),
+            scope: "Tasks.ReadWrite offline_access",
+            extra: "",
+            app,
+        }
+    }
+
+    /// The task list named `name`, created when there is none
+    pub fn connect(token: String, name: &str) -> Result<Self> {
+        let mut todo = Self {
+            token,
+            list: String::new(),
+        };
+        let lists: Vec<TodoList> = todo.pages(&format!("{GRAPH}/me/todo/lists"))?;
+        todo.list = match lists.into_iter().find(|f| f.display_name == name) {
+            Some(list) => list.id,
+            None => {
+                let list: TodoList = todo
+                    .request("POST", &format!("{GRAPH}/me/todo/lists"))
+                    .send_json(serde_json::json!({ "displayName": name }))?
+                    .into_json()?;
+                debug!("created the task list {}", list.display_name);
+                list.id
+            }
+        };
+        Ok(todo)
+    }
+
+    fn request(&self, method: &str, url: &str) -> ureq::Request {
+        ureq::request(method, url)
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .timeout(std::time::Duration::from_secs(30))
+    }
+
+    /// Follows `@odata.nextLink` until the last page
+    fn pages<T: serde::de::DeserializeOwned>(&self, url: &str) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        let mut url = url.to_owned();
+        loop {
+            let page: GraphPage<T> = self.request("GET", &url).call()?.into_json()?;
+            items.extend(page.value);
+            url = match page.next_link {
+                Some(next) => next,
+                None => return Ok(items),
+            };
+        }
+    }
+}
+
+impl Provider for MicrosoftTodo {
+    fn tasks(&self) -> Result<Vec<RemoteTask>> {
+        let tasks: Vec<TodoTask> =
+            self.pages(&format!("{GRAPH}/me/todo/lists/{}/tasks", self.list))?;
+        Ok(tasks
+            .into_iter()
+            .map(|f| RemoteTask {
+                id: f.id,
+                title: f.title,
+                done: f.status == "completed",
+                notes: f
+                    .body
+                    .map(|body| body.content)
+                    .filter(|content| !content.trim().is_empty()),
+            })
+            .collect())
+    }
+
+    fn create(&self, task: &Task) -> Result<String> {
+        let mut body = serde_json::json!({ "title": task.name });
+        if let Some(ref notes) = task.body {
+            body["body"] = serde_json::json!({ "content": notes, "contentType": "text" });
+        }
+        if let Some(due) = task.due {
+            body["dueDateTime"] = serde_json::json!({
+                "dateTime": due.format("%Y-%m-%dT%H:%M:%S").to_string(),
+                "timeZone": "UTC",
+            });
+        }
+        let created: TodoTask = self
+            .request(
+                "POST",
+                &format!("{GRAPH}/me/todo/lists/{}/tasks", self.list),
+            )
+            .send_json(body)?
+            .into_json()?;
+        Ok(created.id)
+    }
+
+    fn set_done(&self, id: &str, done: bool) -> Result<()> {
+        let status = if done { "completed" } else { "notStarted" };
+        self.request(
+            "PATCH",
+            &format!("{GRAPH}/me/todo/lists/{}/tasks/{id}", self.list),
+        )
+        .send_json(serde_json::json!({ "status": status }))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    /// A remote list that accepts `allowed` changes and fails the ones after
+    struct Flaky {
+        remote: Vec<RemoteTask>,
+        allowed: Cell<usize>,
+    }
+
+    impl Flaky {
+        fn change(&self) -> Result<()> {
+            match self.allowed.get() {
+                0 => Err("offline".into()),
+                n => {
+                    self.allowed.set(n - 1);
+                    Ok(())
+                }
+            }
+        }
+    }
+
+    impl Provider for Flaky {
+        fn tasks(&self) -> Result<Vec<RemoteTask>> {
+            Ok(self.remote.clone())
+        }
+
+        fn create(&self, task: &Task) -> Result<String> {
+            self.change()?;
+            Ok(format!("remote-{}", task.timestamp))
+        }
+
+        fn set_done(&self, _: &str, _: bool) -> Result<()> {
+            self.change()
+        }
+    }
+
+    fn entry(timestamp: &str, done: bool) -> Task {
+        Task {
+            name: timestamp.to_owned(),
+            timestamp: timestamp.to_owned(),
+            is_task: true,
+            is_done: done,
+            ..Task::default()
+        }
+    }
+
+    fn remote(id: &str) -> RemoteTask {
+        RemoteTask {
+            id: id.to_owned(),
+            title: id.to_owned(),
+            done: false,
+            notes: None,
+        }
+    }
+
+    #[test]
+    fn code_challenge_matches_the_rfc_example() {
+        assert_eq!(
+            code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn a_failed_sync_keeps_the_links_made_before_it() {
+        let mut tasks = vec![entry("1", false), entry("2", false)];
+        let mut state = SyncState::default();
+        let provider = Flaky {
+            remote: Vec::new(),
+            allowed: Cell::new(1),
+        };
+        assert!(sync(&mut tasks, &mut state, &provider, |_| unreachable!()).is_err());
+        assert_eq!(state.links.len(), 1);
+        assert_eq!(state.links[0].remote, "remote-1");
+    }
+
+    #[test]
+    fn a_failed_sync_keeps_the_links_it_did_not_get_to() {
+        let mut tasks = vec![entry("1", true), entry("2", true)];
+        let link = |timestamp: &str| Link {
+            timestamp: timestamp.to_owned(),
+            remote: format!("remote-{timestamp}"),
+            done: false,
+        };
+        let mut state = SyncState {
+            links: vec![link("1"), link("2")],
+            detached: Vec::new(),
+        };
+        let provider = Flaky {
+            remote: vec![remote("remote-1"), remote("remote-2")],
+            allowed: Cell::new(1),
+        };
+        assert!(sync(&mut tasks, &mut state, &provider, |_| unreachable!()).is_err());
+        // the first completion was sent, the second is still to send
+        assert_eq!(
+            state.links,
+            [
+                Link {
+                    done: true,
+                    ..link("1")
+                },
+                link("2")
+            ]
+        );
+    }
+}
@@ -0,0 +1,220 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, Utc};
+
+use crate::{format_age, format_duration, Tags, Task};
+
+/// One line per task, e.g. `{id}\t{priority}\t{name}`.
+///
+/// `\t` and `\n` stand for a tab and a newline, `{{` and `}}` for literal braces.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(Field),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Id,
+    Name,
+    Priority,
+    Tags,
+    State,
+    Kind,
+    Created,
+    Age,
+    Due,
+    Completed,
+    Estimate,
+    Tracked,
+    Assignee,
+    Reference,
+    Owner,
+    Project,
+}
+
+const FIELDS: &[(&str, Field)] = &[
+    ("id", Field::Id),
+    ("name", Field::Name),
+    ("priority", Field::Priority),
+    ("tags", Field::Tags),
+    ("state", Field::State),
+    ("type", Field::Kind),
+    ("created", Field::Created),
+    ("age", Field::Age),
+    ("due", Field::Due),
+    ("completed", Field::Completed),
+    ("estimate", Field::Estimate),
+    ("tracked", Field::Tracked),
+    ("assignee", Field::Assignee),
+    ("ref", Field::Reference),
+    ("owner", Field::Owner),
+    ("project", Field::Project),
+];
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = s.chars().peekable();
+        while let Some(c) = chars.next() {
+            match (c, chars.peek()) {
+                ('{', Some('{')) | ('}', Some('}')) => {
+                    chars.next();
+                    text.push(c);
+                }
+                ('\\', Some('t')) => {
+                    chars.next();
+                    text.push('\t');
+                }
+                ('\\', Some('n')) => {
+                    chars.next();
+                    text.push('\n');
+                }
+                ('{', _) => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => return Err(format!("unclosed {{{name} in format")),
+                        }
+                    }
+                    let field = FIELDS
+                        .iter()
+                        .find(|(f, _)| *f == name.trim())
+                        .map(|(_, field)| *field)
+                        .ok_or_else(|| {
+                            let known: Vec<&str> = FIELDS.iter().map(|(f, _)| *f).collect();
+                            format!(
+                                "unknown field {{{name}}}, expected one of {}",
+                                known.join(", ")
+                            )
+                        })?;
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(Part::Field(field));
+                }
+                ('}', _) => return Err(String::from("unmatched } in format, use }} for a brace")),
+                (c, _) => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+}
+
+fn date(date: &DateTime<Utc>) -> String {
+    crate::dates::to_local(date)
+        .format("%Y-%m-%d %H:%M")
+        .to_string()
+}
+
+impl Template {
+    /// Fills in the fields of `task`, leaving unset ones empty
+    pub fn render(&self, task: &Task, tags: &Tags) -> String {
+        let mut line = String::new();
+        for part in self.parts.iter() {
+            match part {
+                Part::Text(text) => line.push_str(text),
+                Part::Field(field) => line.push_str(&field_value(*field, task, tags)),
+            }
+        }
+        line
+    }
+}
+
+fn field_value(field: Field, task: &Task, tags: &Tags) -> String {
+    match field {
+        Field::Id => task.id.to_string(),
+        Field::Name => task.name.to_owned(),
+        Field::Priority => task.priority.to_owned(),
+        Field::Tags => tags.labels(&task.tags),
+        Field::State => String::from(if task.cancelled {
+            "cancelled"
+        } else if task.expired {
+            "expired"
+        } else if task.is_done {
+            "done"
+        } else if task.in_progress {
+            "in-progress"
+        } else {
+            "open"
+        }),
+        Field::Kind => String::from(if task.is_task { "task" } else { "note" }),
+        Field::Created => date(&task.created()),
+        Field::Age => {
+            let elapsed = Utc::now() - task.created();
+            format_age(elapsed.num_seconds().max(0) as u64)
+        }
+        Field::Due => task.due.as_ref().map(date).unwrap_or_default(),
+        Field::Completed => task.completed_at.as_ref().map(date).unwrap_or_default(),
+        Field::Estimate => task.estimate.map(format_duration).unwrap_or_default(),
+        Field::Tracked => match task.tracked() {
+            0 => String::new(),
+            seconds => format_duration(seconds),
+        },
+        Field::Assignee => task.assignee.clone().unwrap_or_default(),
+        Field::Reference => task.external_ref.clone().unwrap_or_default(),
+        Field::Owner => task.owner.clone().unwrap_or_default(),
+        Field::Project => task.project.clone().unwrap_or_default(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> Task {
+        Task {
+            id: 3,
+            name: String::from("call the plumber"),
+            priority: String::from("high"),
+            tags: vec![String::from("home"), String::from("deep work")],
+            is_task: true,
+            ..Task::default()
+        }
+    }
+
+    fn render(format: &str) -> String {
+        let template: Template = format.parse().unwrap();
+        template.render(&task(), &Tags::default())
+    }
+
+    #[test]
+    fn fields_are_filled_in() {
+        assert_eq!(
+            render("{id}\\t{priority}\\t{ name }"),
+            "3\thigh\tcall the plumber"
+        );
+        assert_eq!(render("{tags}"), "@home @\"deep work\"");
+        assert_eq!(render("{type} {state}"), "task open");
+    }
+
+    #[test]
+    fn unset_fields_are_left_empty() {
+        assert_eq!(render("[{due}|{project}|{assignee}]"), "[||]");
+    }
+
+    #[test]
+    fn doubled_braces_and_escapes_are_literal() {
+        assert_eq!(render("{{id}} {id}\\n"), "{id} 3\n");
+    }
+
+    #[test]
+    fn bad_formats_are_rejected() {
+        assert!("{nope}".parse::<Template>().is_err());
+        assert!("{id".parse::<Template>().is_err());
+        assert!("a } b".parse::<Template>().is_err());
+    }
+}